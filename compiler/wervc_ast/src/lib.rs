@@ -1,6 +1,7 @@
 pub mod ty;
 
 use ty::Type;
+pub use wervc_span::Span;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Program<E> {
@@ -23,22 +24,50 @@ pub enum Statement<E> {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Integer {
     pub value: isize,
+    pub span: Span,
+}
+
+/// 倍精度浮動小数点数
+#[derive(Debug, PartialEq, Clone)]
+pub struct Float {
+    pub value: f64,
+    pub span: Span,
+}
+// リテラルとして書かれた値はNaNにならないので、反射律を満たす
+impl Eq for Float {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Str {
+    pub value: String,
+    pub span: Span,
+}
+
+/// 1バイトで表現できるASCII文字
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Char {
+    pub value: u8,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Ident {
     pub name: String,
     pub offset: isize, // 型チェックの際にoffsetを計算する
+    // `let mut`で定義された変数か。参照している箇所にも定義と同じ値を持たせる
+    pub mutable: bool,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Boolean {
     pub value: bool,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Array<E> {
     pub elements: Vec<E>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -46,6 +75,7 @@ pub struct BinaryExpr<E> {
     pub kind: BinaryExprKind,
     pub lhs: Box<E>,
     pub rhs: Box<E>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -53,17 +83,22 @@ pub struct LetExpr<E> {
     pub name: Box<E>,
     pub value: Option<Box<E>>,
     pub ty: Type,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct BlockExpr<E> {
     pub statements: Vec<Statement<E>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct CallExpr<E> {
+    // 戻り値を受け取る一時領域のオフセット。戻り値が構造体などの場合に使う
+    pub offset: isize,
     pub func: Box<E>,
     pub args: Vec<E>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -73,6 +108,162 @@ pub struct FunctionDefExpr<E> {
     pub params: Vec<(E, Type)>,
     pub return_ty: Type,
     pub body: Box<E>,
+    pub span: Span,
+}
+
+/// `fn(x: int): int => x + 1`のような名前のない関数
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LambdaExpr<E> {
+    pub params: Vec<(E, Type)>,
+    /// 省略された場合は`Type::unknown()`で、本体の型から推論する
+    pub return_ty: Type,
+    pub body: Box<E>,
+    pub span: Span,
+}
+
+/// `struct Point { x: int, y: int }`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StructDefExpr {
+    pub name: String,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// `type Index = int;`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TypeAliasExpr {
+    pub name: String,
+    // 別名の型。`TypeKind::Alias`で元の型を包む
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// `const N: int = 4 * 16;`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConstExpr<E> {
+    pub name: String,
+    pub ty: Type,
+    // コンパイル時に評価した初期値。整数などのリテラルになる
+    pub value: Box<E>,
+    pub span: Span,
+}
+
+/// `Point { x: 1, y: 2 }`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StructLiteral<E> {
+    pub ty: Type,
+    // ソースコードに書かれた順の (フィールド名, 値)
+    pub fields: Vec<(String, E)>,
+    // 値を組み立てる一時領域のオフセット
+    pub offset: isize,
+    pub span: Span,
+}
+
+/// `(1, true)`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TupleExpr<E> {
+    pub elements: Vec<E>,
+    // 値を組み立てる一時領域のオフセット
+    pub offset: isize,
+    pub span: Span,
+}
+
+/// `p.x`や`t.0`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FieldExpr<E> {
+    pub expr: Box<E>,
+    pub field: String,
+    pub span: Span,
+}
+
+/// `enum Shape { Circle(int), Rect(int, int) }`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EnumDefExpr {
+    pub name: String,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// `Shape::Rect(1, 2)`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EnumVariantExpr<E> {
+    pub ty: Type,
+    pub variant: String,
+    pub args: Vec<E>,
+    // 値を組み立てる一時領域のオフセット
+    pub offset: isize,
+    pub span: Span,
+}
+
+/// `match`の腕で値と照合するパターン
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Pattern<E> {
+    /// `_`
+    Wildcard(Span),
+    /// どんな値にも一致し、その値を変数に束縛する
+    Binding { ident: Box<E>, span: Span },
+    /// 整数、文字、真偽値のリテラル
+    Literal { value: Box<E>, span: Span },
+    /// `Shape::Rect(w, _)`
+    Variant {
+        ty: Type,
+        variant: String,
+        fields: Vec<Pattern<E>>,
+        span: Span,
+    },
+}
+
+impl<E> Pattern<E> {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(span)
+            | Pattern::Binding { span, .. }
+            | Pattern::Literal { span, .. }
+            | Pattern::Variant { span, .. } => *span,
+        }
+    }
+
+    /// パターンの中の式を変換する
+    pub fn map<F>(self, f: &mut impl FnMut(E) -> F) -> Pattern<F> {
+        match self {
+            Pattern::Wildcard(span) => Pattern::Wildcard(span),
+            Pattern::Binding { ident, span } => Pattern::Binding {
+                ident: Box::new(f(*ident)),
+                span,
+            },
+            Pattern::Literal { value, span } => Pattern::Literal {
+                value: Box::new(f(*value)),
+                span,
+            },
+            Pattern::Variant {
+                ty,
+                variant,
+                fields,
+                span,
+            } => Pattern::Variant {
+                ty,
+                variant,
+                fields: fields.into_iter().map(|p| p.map(f)).collect(),
+                span,
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MatchArm<E> {
+    pub pattern: Pattern<E>,
+    pub guard: Option<Box<E>>,
+    pub body: Box<E>,
+    pub span: Span,
+}
+
+/// 腕は上から順に照合し、最初に一致した腕の本体を評価する
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MatchExpr<E> {
+    pub expr: Box<E>,
+    pub arms: Vec<MatchArm<E>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -80,22 +271,80 @@ pub struct IfExpr<E> {
     pub condition: Box<E>,
     pub consequence: Box<E>,
     pub alternative: Option<Box<E>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ReturnExpr<E> {
     pub value: Box<E>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WhileExpr<E> {
+    pub label: Option<String>,
+    pub condition: Box<E>,
+    pub body: Box<E>,
+    pub span: Span,
+}
+
+/// `for`式で繰り返す対象
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Iterable<E> {
+    /// `start..end`または`start..=end`
+    Range {
+        start: Box<E>,
+        end: Box<E>,
+        inclusive: bool,
+    },
+    /// 固定長の配列
+    Array(Box<E>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ForExpr<E> {
+    pub label: Option<String>,
+    pub var: Box<E>,
+    pub iterable: Iterable<E>,
+    pub body: Box<E>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LoopExpr<E> {
+    pub label: Option<String>,
+    pub body: Box<E>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BreakExpr<E> {
+    /// 抜けるループのラベル。省略すると最も内側のループ
+    pub label: Option<String>,
+    /// `loop`式の値
+    pub value: Option<Box<E>>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ContinueExpr {
+    pub label: Option<String>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct UnaryExpr<E> {
     pub kind: UnaryExprKind,
     pub expr: Box<E>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Expression {
     Integer(Integer),
+    Float(Float),
+    Str(Str),
+    Char(Char),
     Ident(Ident),
     Boolean(Boolean),
     Array(Array<Expression>),
@@ -104,23 +353,83 @@ pub enum Expression {
     BlockExpr(BlockExpr<Expression>),
     CallExpr(CallExpr<Expression>),
     FunctionDefExpr(FunctionDefExpr<Expression>),
+    LambdaExpr(LambdaExpr<Expression>),
+    StructDefExpr(StructDefExpr),
+    StructLiteral(StructLiteral<Expression>),
+    FieldExpr(FieldExpr<Expression>),
+    TupleExpr(TupleExpr<Expression>),
+    TypeAliasExpr(TypeAliasExpr),
+    ConstExpr(ConstExpr<Expression>),
+    EnumDefExpr(EnumDefExpr),
+    EnumVariantExpr(EnumVariantExpr<Expression>),
+    MatchExpr(MatchExpr<Expression>),
     IfExpr(IfExpr<Expression>),
     ReturnExpr(ReturnExpr<Expression>),
+    WhileExpr(WhileExpr<Expression>),
+    ForExpr(ForExpr<Expression>),
+    LoopExpr(LoopExpr<Expression>),
+    BreakExpr(BreakExpr<Expression>),
+    ContinueExpr(ContinueExpr),
     UnaryExpr(UnaryExpr<Expression>),
 }
 
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Integer(e) => e.span,
+            Expression::Float(e) => e.span,
+            Expression::Str(e) => e.span,
+            Expression::Char(e) => e.span,
+            Expression::Ident(e) => e.span,
+            Expression::Boolean(e) => e.span,
+            Expression::Array(e) => e.span,
+            Expression::BinaryExpr(e) => e.span,
+            Expression::LetExpr(e) => e.span,
+            Expression::BlockExpr(e) => e.span,
+            Expression::CallExpr(e) => e.span,
+            Expression::FunctionDefExpr(e) => e.span,
+            Expression::LambdaExpr(e) => e.span,
+            Expression::StructDefExpr(e) => e.span,
+            Expression::StructLiteral(e) => e.span,
+            Expression::FieldExpr(e) => e.span,
+            Expression::TupleExpr(e) => e.span,
+            Expression::TypeAliasExpr(e) => e.span,
+            Expression::ConstExpr(e) => e.span,
+            Expression::EnumDefExpr(e) => e.span,
+            Expression::EnumVariantExpr(e) => e.span,
+            Expression::MatchExpr(e) => e.span,
+            Expression::IfExpr(e) => e.span,
+            Expression::ReturnExpr(e) => e.span,
+            Expression::WhileExpr(e) => e.span,
+            Expression::ForExpr(e) => e.span,
+            Expression::LoopExpr(e) => e.span,
+            Expression::BreakExpr(e) => e.span,
+            Expression::ContinueExpr(e) => e.span,
+            Expression::UnaryExpr(e) => e.span,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum BinaryExprKind {
     Add,
     Sub,
     Mul,
     Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
     Eq,
     Ne,
     Lt,
     Le,
     Gt,
     Ge,
+    And,
+    Or,
     Assign,
 }
 
@@ -128,6 +437,7 @@ pub enum BinaryExprKind {
 pub enum UnaryExprKind {
     Minus,
     Not,
+    BitNot,
     Deref,
     Addr,
 }
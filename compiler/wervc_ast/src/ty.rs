@@ -1,8 +1,16 @@
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
 pub struct Type {
     pub kind: TypeKind,
 }
 
+/// 別名は元の型と同じ型として比べる
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        self.unaliased().kind == other.unaliased().kind
+    }
+}
+impl Eq for Type {}
+
 impl Type {
     fn new(kind: TypeKind) -> Type {
         Type { kind }
@@ -10,17 +18,97 @@ impl Type {
     pub fn calc_size(&self) -> isize {
         match &self.kind {
             TypeKind::Int => 8,
+            TypeKind::Float => 8,
             TypeKind::Bool => 8,
+            TypeKind::Str => 8,
+            TypeKind::Char => 1,
             TypeKind::Ptr { .. } => 8,
             TypeKind::Func { .. } => 8,
             TypeKind::Array {
                 element_type,
                 length,
             } => element_type.calc_size() * length,
+            TypeKind::Struct { fields, .. } => layout(fields.iter().map(|(_, ty)| ty)).1,
+            TypeKind::Tuple { elements } => layout(elements).1,
+            TypeKind::Enum { variants, .. } => {
+                let payload_size = variants
+                    .iter()
+                    .map(|(_, payload)| layout(payload).1)
+                    .max()
+                    .unwrap_or(0);
+
+                align_to(ENUM_TAG_SIZE + payload_size, self.align())
+            }
+            TypeKind::Alias { ty, .. } => ty.calc_size(),
             TypeKind::Unknown => 0,
             TypeKind::Never => 0,
         }
     }
+    /// 値を配置するアドレスの境界
+    pub fn align(&self) -> isize {
+        match &self.kind {
+            TypeKind::Array { element_type, .. } => element_type.align(),
+            TypeKind::Struct { fields, .. } => {
+                fields.iter().map(|(_, ty)| ty.align()).max().unwrap_or(1)
+            }
+            TypeKind::Tuple { elements } => elements.iter().map(Type::align).max().unwrap_or(1),
+            // タグと同じ境界に揃える。ペイロードの境界がタグより大きくなることはない
+            TypeKind::Enum { .. } => ENUM_TAG_SIZE,
+            TypeKind::Alias { ty, .. } => ty.align(),
+            TypeKind::Unknown | TypeKind::Never => 1,
+            _ => self.calc_size(),
+        }
+    }
+    /// 構造体のフィールドの先頭からのオフセットと型を返す
+    ///
+    /// タプルは`0`や`1`のような要素の番号をフィールドの名前とする
+    pub fn field(&self, name: &str) -> Option<(isize, &Type)> {
+        match &self.unaliased().kind {
+            TypeKind::Struct { fields, .. } => {
+                let (offsets, _) = layout(fields.iter().map(|(_, ty)| ty));
+
+                fields
+                    .iter()
+                    .zip(offsets)
+                    .find(|((field_name, _), _)| field_name == name)
+                    .map(|((_, ty), offset)| (offset, ty))
+            }
+            TypeKind::Tuple { elements } => {
+                let index: usize = name.parse().ok()?;
+                let (offsets, _) = layout(elements);
+
+                Some((*offsets.get(index)?, &elements[index]))
+            }
+            _ => None,
+        }
+    }
+    /// 値をアドレスで扱い、代入するときは領域ごとコピーする型か
+    pub fn is_aggregate(&self) -> bool {
+        matches!(
+            self.unaliased().kind,
+            TypeKind::Struct { .. } | TypeKind::Enum { .. } | TypeKind::Tuple { .. }
+        )
+    }
+    /// 列挙型のバリアントのタグと、ペイロードのそれぞれの値の先頭からのオフセットと型を返す
+    ///
+    /// タグは先頭に置き、ペイロードはその後ろに構造体のフィールドと同じように並べる
+    pub fn variant(&self, name: &str) -> Option<(isize, Vec<(isize, &Type)>)> {
+        let TypeKind::Enum { variants, .. } = &self.unaliased().kind else {
+            return None;
+        };
+        let tag = variants.iter().position(|(variant, _)| variant == name)?;
+        let payload = &variants[tag].1;
+        let (offsets, _) = layout(payload);
+
+        Some((
+            tag as isize,
+            offsets
+                .into_iter()
+                .map(|offset| ENUM_TAG_SIZE + offset)
+                .zip(payload)
+                .collect(),
+        ))
+    }
     pub fn is_assignable_to(&self, to: &Type) -> bool {
         if self == to {
             return true;
@@ -32,7 +120,7 @@ impl Type {
         )
     }
     pub fn try_cast_to_ptr(&mut self) -> Option<&Type> {
-        match &self.kind {
+        match &self.unaliased().kind {
             TypeKind::Array { element_type, .. } => {
                 *self = Type::pointer_to(element_type.clone());
                 Some(self)
@@ -41,12 +129,76 @@ impl Type {
         }
     }
 
+    /// 別名をたどった先の型を返す
+    pub fn unaliased(&self) -> &Type {
+        match &self.kind {
+            TypeKind::Alias { ty, .. } => ty.unaliased(),
+            _ => self,
+        }
+    }
+    /// 内側の型も含めて、別名をすべて元の型に置き換えた型を返す
+    pub fn resolved(&self) -> Type {
+        let kind = match &self.unaliased().kind {
+            TypeKind::Func {
+                params_ty,
+                return_ty,
+            } => TypeKind::Func {
+                params_ty: params_ty.iter().map(Type::resolved).collect(),
+                return_ty: Box::new(return_ty.resolved()),
+            },
+            TypeKind::Ptr { ptr_to } => TypeKind::Ptr {
+                ptr_to: Box::new(ptr_to.resolved()),
+            },
+            TypeKind::Array {
+                element_type,
+                length,
+            } => TypeKind::Array {
+                element_type: Box::new(element_type.resolved()),
+                length: *length,
+            },
+            TypeKind::Struct { name, fields } => TypeKind::Struct {
+                name: name.clone(),
+                fields: fields
+                    .iter()
+                    .map(|(field, ty)| (field.clone(), ty.resolved()))
+                    .collect(),
+            },
+            TypeKind::Tuple { elements } => TypeKind::Tuple {
+                elements: elements.iter().map(Type::resolved).collect(),
+            },
+            TypeKind::Enum { name, variants } => TypeKind::Enum {
+                name: name.clone(),
+                variants: variants
+                    .iter()
+                    .map(|(variant, payload)| {
+                        (
+                            variant.clone(),
+                            payload.iter().map(Type::resolved).collect(),
+                        )
+                    })
+                    .collect(),
+            },
+            kind => kind.clone(),
+        };
+
+        Type { kind }
+    }
+
     pub fn int() -> Type {
         Type::new(TypeKind::Int)
     }
+    pub fn float() -> Type {
+        Type::new(TypeKind::Float)
+    }
     pub fn bool() -> Type {
         Type::new(TypeKind::Bool)
     }
+    pub fn str() -> Type {
+        Type::new(TypeKind::Str)
+    }
+    pub fn char() -> Type {
+        Type::new(TypeKind::Char)
+    }
     pub fn unknown() -> Type {
         Type::new(TypeKind::Unknown)
     }
@@ -74,6 +226,59 @@ impl Type {
             },
         }
     }
+    pub fn structure(name: impl ToString, fields: Vec<(String, Type)>) -> Type {
+        Type {
+            kind: TypeKind::Struct {
+                name: name.to_string().into_boxed_str(),
+                fields: fields.into_boxed_slice(),
+            },
+        }
+    }
+    pub fn tuple(elements: Vec<Type>) -> Type {
+        Type {
+            kind: TypeKind::Tuple { elements },
+        }
+    }
+    pub fn alias(name: impl ToString, ty: Box<Type>) -> Type {
+        Type {
+            kind: TypeKind::Alias {
+                name: name.to_string().into_boxed_str(),
+                ty,
+            },
+        }
+    }
+    pub fn enumeration(name: impl ToString, variants: Vec<(String, Vec<Type>)>) -> Type {
+        Type {
+            kind: TypeKind::Enum {
+                name: name.to_string().into_boxed_str(),
+                variants: variants.into_boxed_slice(),
+            },
+        }
+    }
+}
+
+/// 列挙型の値の先頭に置く、バリアントを表すタグの大きさ
+const ENUM_TAG_SIZE: isize = 8;
+
+/// `value`を`align`の倍数に切り上げる
+fn align_to(value: isize, align: isize) -> isize {
+    (value + align - 1) / align * align
+}
+
+/// 型を順に並べたときの、それぞれの先頭からのオフセットと全体の大きさを返す
+fn layout<'a>(tys: impl IntoIterator<Item = &'a Type>) -> (Vec<isize>, isize) {
+    let mut offsets = Vec::new();
+    let mut size = 0;
+    let mut align = 1;
+
+    for ty in tys {
+        size = align_to(size, ty.align());
+        offsets.push(size);
+        size += ty.calc_size();
+        align = align.max(ty.align());
+    }
+
+    (offsets, align_to(size, align))
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -81,7 +286,12 @@ pub enum TypeKind {
     Never,
     Unknown,
     Int,
+    /// 倍精度浮動小数点数
+    Float,
     Bool,
+    /// NUL終端されたバイト列へのポインタとして表現される文字列
+    Str,
+    Char,
     Func {
         params_ty: Vec<Type>,
         return_ty: Box<Type>,
@@ -93,14 +303,41 @@ pub enum TypeKind {
         element_type: Box<Type>,
         length: isize,
     },
+    /// フィールドは宣言した順にメモリ上に並ぶ
+    ///
+    /// 型を小さく保つため、名前とフィールドは伸び縮みしない`Box`で持つ
+    Struct {
+        name: Box<str>,
+        fields: Box<[(String, Type)]>,
+    },
+    /// 要素は構造体のフィールドと同じように並ぶ
+    Tuple {
+        elements: Vec<Type>,
+    },
+    /// バリアントは宣言した順に0から番号をつけ、その番号をタグとする
+    Enum {
+        name: Box<str>,
+        variants: Box<[(String, Vec<Type>)]>,
+    },
+    /// `type`で宣言した型の別名
+    ///
+    /// 比較や大きさの計算では元の型として扱い、名前はエラーなどで表示するために残す
+    Alias {
+        name: Box<str>,
+        ty: Box<Type>,
+    },
 }
 
-impl<T: ToString> From<T> for TypeKind {
-    fn from(value: T) -> Self {
-        match value.to_string().as_str() {
-            "int" => Self::Int,
-            "bool" => Self::Bool,
-            _ => Self::Unknown,
+impl TypeKind {
+    /// 組み込みの型の名前から型を返す。組み込みの型の名前でなければ`None`を返す
+    pub fn builtin(name: &str) -> Option<TypeKind> {
+        match name {
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "bool" => Some(Self::Bool),
+            "str" => Some(Self::Str),
+            "char" => Some(Self::Char),
+            _ => None,
         }
     }
 }
@@ -1,11 +1,31 @@
+use wervc_ast::Span;
+use wervc_lexer::error::LexError;
+use wervc_parser::parser::error::ParserError;
 use wervc_type::{error::TypeCheckError, TypedExpression};
 
 #[derive(Debug)]
 pub enum CompileError {
     Unimplemented,
-    ParserError(wervc_parser::parser::error::ParserError),
+    LexError(LexError),
+    ParserError(ParserError),
+    /// 構文解析で見つかったすべてのエラー。字句エラーも含む
+    ParserErrors(Vec<CompileError>),
     InputIsNotProgram,
-    NotLeftValue,
-    ExpectedIdent { actual: TypedExpression },
+    NotLeftValue(Span),
+    /// 引数を渡すレジスタが足りない。スタックでは渡さない
+    TooManyArgs(Span),
+    OutsideLoop(Span),
+    ExpectedIdent {
+        actual: Box<TypedExpression>,
+    },
     TypeCheckError(TypeCheckError),
 }
+
+impl From<ParserError> for CompileError {
+    fn from(value: ParserError) -> Self {
+        match value {
+            ParserError::LexError(e) => CompileError::LexError(e),
+            e => CompileError::ParserError(e),
+        }
+    }
+}
@@ -1,10 +1,16 @@
 pub mod error;
 
 use error::CompileError;
-use std::fmt::Display;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
 use wervc_ast::{
-    BinaryExpr, BinaryExprKind, BlockExpr, CallExpr, FunctionDefExpr, Integer, LetExpr, Program,
-    ReturnExpr, Statement, UnaryExpr, UnaryExprKind,
+    ty::{Type, TypeKind},
+    BinaryExpr, BinaryExprKind, BlockExpr, Boolean, BreakExpr, CallExpr, Char, EnumVariantExpr,
+    FieldExpr, Float, ForExpr, FunctionDefExpr, Integer, Iterable, LambdaExpr, LetExpr, LoopExpr,
+    MatchExpr, Pattern, Program, ReturnExpr, Span, Statement, Str, StructLiteral, TupleExpr,
+    UnaryExpr, UnaryExprKind, WhileExpr,
 };
 use wervc_parser::parser::Parser;
 use wervc_type::{TypedExpression, TypedExpressionKind, TypedNode};
@@ -14,7 +20,15 @@ type CResult = Result<(), CompileError>;
 /// 式の種類を必要とする型に対して与える型
 type Expr = TypedExpression;
 
+/// 関数の外側で定義された変数を置く静的な領域の末尾のラベル
+///
+/// スタック上の変数と同じく、末尾からオフセットを引いた位置に変数を置く
+const GLOBALS_LABEL: &str = ".L.globals";
+
 const X86_64_ARG_REGISTERS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+const X86_64_FLOAT_ARG_REGISTERS: [&str; 8] = [
+    "%xmm0", "%xmm1", "%xmm2", "%xmm3", "%xmm4", "%xmm5", "%xmm6", "%xmm7",
+];
 
 /// アセンブリで数値に$をつけて表示するためのユーティリティ
 trait IntoAssembly: ToString {
@@ -31,6 +45,17 @@ impl IntoAssembly for String {}
 impl IntoAssembly for &String {}
 impl IntoAssembly for &str {}
 
+/// `break`と`continue`の飛び先
+pub struct LoopLabels {
+    // ソースコード上のループのラベル
+    pub label: Option<String>,
+    pub continue_label: String,
+    pub break_label: String,
+    // ループに入った時点の depth
+    // ループから抜けるときは、それ以降に積んだ値を捨てる
+    pub depth: usize,
+}
+
 pub struct Compiler {
     pub outputs: Vec<String>,
     pub label_count: usize,
@@ -39,6 +64,16 @@ pub struct Compiler {
     pub depth: usize,
     pub cur_output_index: usize,
     pub total_stack_size: isize,
+    // .rodata に配置する文字列リテラルのラベルと値
+    pub str_literals: Vec<(String, String)>,
+    // 生成中のループの飛び先。内側のループほど後ろにある
+    pub loop_labels: Vec<LoopLabels>,
+    // 定義済みの関数のラベル。関数名の変数のオフセットから引く
+    pub functions: HashMap<isize, String>,
+    // 静的な領域に置く、関数の外側で定義された変数のオフセット
+    pub globals: HashSet<isize>,
+    // 生成中の関数の戻り値の型
+    pub return_ty: Type,
 }
 
 impl Compiler {
@@ -49,6 +84,11 @@ impl Compiler {
             depth: 0,
             cur_output_index: 0,
             total_stack_size: 0,
+            str_literals: Vec::new(),
+            loop_labels: Vec::new(),
+            functions: HashMap::new(),
+            globals: HashSet::new(),
+            return_ty: Type::int(),
         }
     }
 
@@ -166,20 +206,78 @@ impl Compiler {
         self.binary_op("movzb", lhs, rhs);
     }
 
+    /// 汎用レジスタとxmmレジスタの間でビット列をそのままコピーする
+    fn movq(&mut self, lhs: impl IntoAssembly, rhs: impl IntoAssembly) {
+        self.binary_op("movq", lhs, rhs);
+    }
+
+    /// %raxが指すアドレスから型のサイズ分の値を%raxに読み込む
+    ///
+    /// 配列と構造体と列挙型とタプルはそのアドレス自体を値として扱うので読み込まない
+    fn load(&mut self, ty: &Type) {
+        match ty.unaliased().kind {
+            TypeKind::Array { .. } => {}
+            _ if ty.is_aggregate() => {}
+            _ if ty.calc_size() == 1 => self.binary_op("movzbq", "(%rax)", "%rax"),
+            _ => self.mov("(%rax)", "%rax"),
+        }
+    }
+
+    /// %rdiの値を型のサイズ分だけ%raxが指すアドレスに書き込む
+    ///
+    /// 構造体と列挙型とタプルは%rdiが指すアドレスから型のサイズ分をコピーする
+    fn store(&mut self, ty: &Type) {
+        if ty.is_aggregate() {
+            let size = ty.calc_size();
+            let mut copied = 0;
+
+            while copied + 8 <= size {
+                self.mov(format!("{}(%rdi)", copied), "%r11");
+                self.mov("%r11", format!("{}(%rax)", copied));
+                copied += 8;
+            }
+
+            while copied < size {
+                self.mov(format!("{}(%rdi)", copied), "%r11b");
+                self.mov("%r11b", format!("{}(%rax)", copied));
+                copied += 1;
+            }
+        } else if ty.calc_size() == 1 {
+            self.mov("%dil", "(%rax)");
+        } else {
+            self.mov("%rdi", "(%rax)");
+        }
+    }
+
     fn je(&mut self, label: impl IntoAssembly) {
         self.unary_op("je", label);
     }
 
+    fn jne(&mut self, label: impl IntoAssembly) {
+        self.unary_op("jne", label);
+    }
+
     fn jmp(&mut self, label: impl IntoAssembly) {
         self.unary_op("jmp", label);
     }
 
     pub fn compile(&mut self, program: impl ToString) -> CResult {
-        let mut program = TypedNode::from(
-            Parser::new(program)
-                .parse_program()
-                .map_err(CompileError::ParserError)?,
-        );
+        let mut parser = Parser::new(program);
+        let program = parser.parse_partial_program();
+
+        // 1つずつ直さなくて済むように、見つかったエラーをまとめて返す
+        if !parser.errors().is_empty() {
+            return Err(CompileError::ParserErrors(
+                parser
+                    .errors()
+                    .iter()
+                    .cloned()
+                    .map(CompileError::from)
+                    .collect(),
+            ));
+        }
+
+        let mut program = TypedNode::from(program);
 
         let (_, resolver) = program
             .resolve_type()
@@ -191,7 +289,9 @@ impl Compiler {
             }
         };
 
-        self.total_stack_size = resolver.cur_offset;
+        // 関数の本体でもスタックが16バイト境界に揃うよう切り上げる
+        self.total_stack_size = (resolver.cur_offset + 15) / 16 * 16;
+        self.globals = resolver.globals;
         self.gen_program(&program)?;
 
         Ok(())
@@ -206,6 +306,8 @@ impl Compiler {
         self.push("%rax");
         self.gen_epilogue();
 
+        self.gen_rodata();
+        self.gen_globals();
         self.gen_program_prologue();
 
         Ok(())
@@ -226,6 +328,47 @@ impl Compiler {
         self.sub(self.total_stack_size, "%rsp");
     }
 
+    fn gen_rodata(&mut self) {
+        if self.str_literals.is_empty() {
+            return;
+        }
+
+        self.add_output();
+        self.change_output_to_end();
+        self.add_code("  .section .rodata");
+
+        for (label, value) in std::mem::take(&mut self.str_literals) {
+            self.gen_label(label);
+            self.add_code(format!("  .string \"{}\"", escape_asm_string(&value)));
+        }
+
+        self.change_output_to_head();
+    }
+
+    fn gen_globals(&mut self) {
+        if self.globals.is_empty() {
+            return;
+        }
+
+        self.add_output();
+        self.change_output_to_end();
+        self.add_code("  .bss");
+        self.add_code("  .align 16");
+        self.add_code(format!("  .zero {}", self.total_stack_size));
+        self.gen_label(GLOBALS_LABEL);
+        self.change_output_to_head();
+    }
+
+    /// 変数のアドレスを%raxに求める
+    fn gen_var_addr(&mut self, offset: isize) {
+        if self.globals.contains(&offset) {
+            self.binary_op("lea", format!("{}-{}(%rip)", GLOBALS_LABEL, offset), "%rax");
+        } else {
+            self.mov("%rbp", "%rax");
+            self.sub(offset, "%rax");
+        }
+    }
+
     fn gen_statements(&mut self, statements: &Vec<Statement<Expr>>) -> CResult {
         for statement in statements {
             self.gen_statement(statement)?;
@@ -237,10 +380,12 @@ impl Compiler {
 
     fn gen_statement(&mut self, statement: &Statement<Expr>) -> CResult {
         match statement {
+            // 式文の値は捨てて、代わりに0を積む
             Statement::ExprStmt(e) => {
                 self.gen_expr(e)?;
                 self.pop("%rax");
                 self.mov(0, "%rax");
+                self.push("%rax");
             }
             Statement::ExprReturnStmt(e) => {
                 self.gen_expr(e)?;
@@ -253,32 +398,116 @@ impl Compiler {
     fn gen_expr(&mut self, e: &TypedExpression) -> CResult {
         match &e.kind {
             TypedExpressionKind::Integer(e) => self.gen_integer(e),
-            TypedExpressionKind::BinaryExpr(e) => self.gen_binary_expr(e),
-            TypedExpressionKind::UnaryExpr(e) => self.gen_unary_expr(e),
+            TypedExpressionKind::Float(e) => self.gen_float(e),
+            TypedExpressionKind::Str(e) => self.gen_str(e),
+            TypedExpressionKind::Char(c) => self.gen_char(c),
+            TypedExpressionKind::Boolean(b) => self.gen_boolean(b),
+            TypedExpressionKind::BinaryExpr(b) => self.gen_binary_expr(b, &e.ty),
+            TypedExpressionKind::UnaryExpr(u) => self.gen_unary_expr(u, &e.ty),
             TypedExpressionKind::Ident(_) => self.gen_ident(e),
             TypedExpressionKind::ReturnExpr(e) => self.gen_return_expr(e),
             TypedExpressionKind::IfExpr(e) => self.gen_if_expr(e),
+            TypedExpressionKind::WhileExpr(e) => self.gen_while_expr(e),
+            TypedExpressionKind::ForExpr(e) => self.gen_for_expr(e),
+            TypedExpressionKind::LoopExpr(e) => self.gen_loop_expr(e),
+            TypedExpressionKind::BreakExpr(e) => self.gen_break_expr(e),
+            TypedExpressionKind::ContinueExpr(c) => {
+                self.gen_loop_jump(c.span, &c.label, |labels| labels.continue_label.clone())
+            }
             TypedExpressionKind::BlockExpr(e) => self.gen_block_expr(e),
-            TypedExpressionKind::CallExpr(e) => self.gen_call_expr(e),
+            TypedExpressionKind::CallExpr(c) => self.gen_call_expr(c, &e.ty),
             TypedExpressionKind::FunctionDefExpr(e) => self.gen_function_def_expr(e),
+            TypedExpressionKind::LambdaExpr(e) => self.gen_lambda_expr(e),
             TypedExpressionKind::LetExpr(e) => self.gen_let_expr(e),
+            TypedExpressionKind::StructLiteral(e) => self.gen_struct_literal(e),
+            TypedExpressionKind::FieldExpr(_) => self.gen_field_expr(e),
+            TypedExpressionKind::TupleExpr(t) => self.gen_tuple_expr(t, &e.ty),
+            TypedExpressionKind::EnumVariantExpr(e) => self.gen_enum_variant_expr(e),
+            TypedExpressionKind::MatchExpr(e) => self.gen_match_expr(e),
+            TypedExpressionKind::StructDefExpr(_)
+            | TypedExpressionKind::EnumDefExpr(_)
+            | TypedExpressionKind::TypeAliasExpr(_)
+            | TypedExpressionKind::ConstExpr(_) => {
+                self.push(0);
+
+                Ok(())
+            }
             _ => Err(CompileError::Unimplemented),
         }
     }
 
+    /// pushの即値は32ビットを符号拡張したものなので、収まらない値はレジスタ経由で積む
     fn gen_integer(&mut self, e: &Integer) -> CResult {
-        self.push(e.value);
+        if i32::try_from(e.value).is_ok() {
+            self.push(e.value);
+        } else {
+            self.mov(e.value, "%rax");
+            self.push("%rax");
+        }
 
         Ok(())
     }
 
-    fn gen_binary_expr(&mut self, e: &BinaryExpr<Expr>) -> CResult {
+    /// 浮動小数点数はビット列のまま汎用レジスタ経由でスタックに積む
+    fn gen_float(&mut self, e: &Float) -> CResult {
+        self.mov(e.value.to_bits() as isize, "%rax");
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    fn gen_str(&mut self, e: &Str) -> CResult {
+        let label = self.get_serial_label("str");
+
+        self.str_literals.push((label.clone(), e.value.clone()));
+        self.binary_op("lea", format!("{}(%rip)", label), "%rax");
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    fn gen_char(&mut self, e: &Char) -> CResult {
+        self.push(e.value as isize);
+
+        Ok(())
+    }
+
+    fn gen_boolean(&mut self, e: &Boolean) -> CResult {
+        self.push(e.value as isize);
+
+        Ok(())
+    }
+
+    fn gen_binary_expr(&mut self, e: &BinaryExpr<Expr>, ty: &Type) -> CResult {
+        if e.kind == BinaryExprKind::Assign {
+            self.gen_left_val(&e.lhs)?;
+            self.gen_expr(&e.rhs)?;
+
+            self.pop("%rdi");
+            self.pop("%rax");
+            self.store(&e.lhs.ty);
+            self.push("%rdi");
+
+            return Ok(());
+        }
+
+        if matches!(e.kind, BinaryExprKind::And | BinaryExprKind::Or) {
+            return self.gen_logical_expr(e);
+        }
+
         self.gen_expr(&e.lhs)?;
         self.gen_expr(&e.rhs)?;
 
         self.pop("%rdi");
         self.pop("%rax");
 
+        if e.lhs.ty.unaliased().kind == TypeKind::Float {
+            self.gen_float_binary_op(e.kind);
+            self.push("%rax");
+
+            return Ok(());
+        }
+
         match e.kind {
             BinaryExprKind::Add => {
                 self.add("%rdi", "%rax");
@@ -292,6 +521,28 @@ impl Compiler {
             BinaryExprKind::Div => {
                 self.idiv("%rdi");
             }
+            BinaryExprKind::Rem => {
+                // 剰余はidivによって%rdxに格納される
+                self.idiv("%rdi");
+                self.mov("%rdx", "%rax");
+            }
+            BinaryExprKind::BitAnd => {
+                self.binary_op("and", "%rdi", "%rax");
+            }
+            BinaryExprKind::BitOr => {
+                self.binary_op("or", "%rdi", "%rax");
+            }
+            BinaryExprKind::BitXor => {
+                self.binary_op("xor", "%rdi", "%rax");
+            }
+            BinaryExprKind::Shl => {
+                self.mov("%rdi", "%rcx");
+                self.binary_op("sal", "%cl", "%rax");
+            }
+            BinaryExprKind::Shr => {
+                self.mov("%rdi", "%rcx");
+                self.binary_op("sar", "%cl", "%rax");
+            }
             BinaryExprKind::Eq => {
                 self.cmp("%rdi", "%rax");
                 self.unary_op("sete", "%al");
@@ -322,15 +573,12 @@ impl Compiler {
                 self.unary_op("setl", "%al");
                 self.movzb("%al", "%rax");
             }
-            BinaryExprKind::Assign => {
-                self.gen_left_val(&e.lhs)?;
-                self.gen_expr(&e.rhs)?;
+            BinaryExprKind::And | BinaryExprKind::Or | BinaryExprKind::Assign => unreachable!(),
+        }
 
-                self.pop("%rdi");
-                self.pop("%rax");
-                self.mov("%rdi", "(%rax)");
-                self.push("%rdi");
-            }
+        // 文字の演算結果は1バイトに切り詰める
+        if ty.unaliased().kind == TypeKind::Char {
+            self.movzb("%al", "%rax");
         }
 
         self.push("%rax");
@@ -338,23 +586,81 @@ impl Compiler {
         Ok(())
     }
 
-    fn gen_unary_expr(&mut self, e: &UnaryExpr<Expr>) -> CResult {
+    /// %raxと%rdiに入った浮動小数点数の演算を、xmmレジスタを使って行う
+    fn gen_float_binary_op(&mut self, kind: BinaryExprKind) {
+        self.movq("%rax", "%xmm0");
+        self.movq("%rdi", "%xmm1");
+
+        // ucomisdはNaNとの比較で CF=ZF=PF=1 になるので、
+        // 大小比較は CF=0 を条件とする seta/setae になるよう比較の向きを選ぶ
+        match kind {
+            BinaryExprKind::Add => self.binary_op("addsd", "%xmm1", "%xmm0"),
+            BinaryExprKind::Sub => self.binary_op("subsd", "%xmm1", "%xmm0"),
+            BinaryExprKind::Mul => self.binary_op("mulsd", "%xmm1", "%xmm0"),
+            BinaryExprKind::Div => self.binary_op("divsd", "%xmm1", "%xmm0"),
+            BinaryExprKind::Eq => {
+                self.binary_op("ucomisd", "%xmm1", "%xmm0");
+                self.unary_op("sete", "%al");
+                self.unary_op("setnp", "%dl");
+                self.binary_op("and", "%dl", "%al");
+            }
+            BinaryExprKind::Ne => {
+                self.binary_op("ucomisd", "%xmm1", "%xmm0");
+                self.unary_op("setne", "%al");
+                self.unary_op("setp", "%dl");
+                self.binary_op("or", "%dl", "%al");
+            }
+            BinaryExprKind::Gt => {
+                self.binary_op("ucomisd", "%xmm1", "%xmm0");
+                self.unary_op("seta", "%al");
+            }
+            BinaryExprKind::Ge => {
+                self.binary_op("ucomisd", "%xmm1", "%xmm0");
+                self.unary_op("setae", "%al");
+            }
+            BinaryExprKind::Lt => {
+                self.binary_op("ucomisd", "%xmm0", "%xmm1");
+                self.unary_op("seta", "%al");
+            }
+            BinaryExprKind::Le => {
+                self.binary_op("ucomisd", "%xmm0", "%xmm1");
+                self.unary_op("setae", "%al");
+            }
+            // 代入と整数専用の演算は、浮動小数点数に対しては型検査で弾かれている
+            _ => unreachable!(),
+        }
+
+        match kind {
+            BinaryExprKind::Add
+            | BinaryExprKind::Sub
+            | BinaryExprKind::Mul
+            | BinaryExprKind::Div => self.movq("%xmm0", "%rax"),
+            _ => self.movzb("%al", "%rax"),
+        }
+    }
+
+    fn gen_unary_expr(&mut self, e: &UnaryExpr<Expr>, ty: &Type) -> CResult {
+        if e.kind == UnaryExprKind::Addr {
+            return self.gen_left_val(&e.expr);
+        }
+
         self.gen_expr(&e.expr)?;
 
         self.pop("%rax");
 
         match e.kind {
+            // 浮動小数点数は符号ビットを反転する
+            UnaryExprKind::Minus if ty.unaliased().kind == TypeKind::Float => {
+                self.binary_op("btc", 63, "%rax");
+            }
             UnaryExprKind::Minus => {
                 self.neg("%rax");
             }
-            UnaryExprKind::Addr => {
-                self.gen_left_val(&e.expr)?;
-                return Ok(());
+            UnaryExprKind::BitNot => {
+                self.unary_op("not", "%rax");
             }
             UnaryExprKind::Deref => {
-                self.gen_expr(&e.expr)?;
-                self.pop("%rax");
-                self.mov("(%rax)", "%rax");
+                self.load(ty);
             }
             _ => {
                 return Err(CompileError::Unimplemented);
@@ -369,18 +675,27 @@ impl Compiler {
     fn gen_left_val(&mut self, e: &TypedExpression) -> CResult {
         match &e.kind {
             TypedExpressionKind::Ident(e) => {
-                self.mov("%rbp", "%rax");
-                self.sub(e.offset, "%rax");
+                self.gen_var_addr(e.offset);
                 self.push("%rax");
             }
             TypedExpressionKind::UnaryExpr(UnaryExpr {
                 kind: UnaryExprKind::Deref,
                 expr,
+                ..
             }) => {
                 self.gen_expr(expr)?;
             }
+            // 構造体やタプルの値はそのアドレスなので、フィールドのオフセットを足す
+            TypedExpressionKind::FieldExpr(FieldExpr { expr, field, .. }) => {
+                let (offset, _) = expr.ty.field(field).ok_or(CompileError::Unimplemented)?;
+
+                self.gen_expr(expr)?;
+                self.pop("%rax");
+                self.add(offset, "%rax");
+                self.push("%rax");
+            }
             _ => {
-                return Err(CompileError::NotLeftValue);
+                return Err(CompileError::NotLeftValue(e.span()));
             }
         }
 
@@ -388,14 +703,198 @@ impl Compiler {
     }
 
     fn gen_ident(&mut self, e: &TypedExpression) -> CResult {
+        if let TypedExpressionKind::Ident(ident) = &e.kind {
+            if let Some(label) = self.functions.get(&ident.offset).cloned() {
+                self.gen_function_address(&label);
+
+                return Ok(());
+            }
+        }
+        self.gen_left_val(e)?;
+        self.pop("%rax");
+        self.load(&e.ty);
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    fn gen_field_expr(&mut self, e: &TypedExpression) -> CResult {
         self.gen_left_val(e)?;
         self.pop("%rax");
-        self.mov("(%rax)", "%rax");
+        self.load(&e.ty);
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    /// 一時領域にフィールドの値を書き込み、そのアドレスを値とする
+    fn gen_struct_literal(&mut self, e: &StructLiteral<Expr>) -> CResult {
+        for (field, value) in &e.fields {
+            let (offset, ty) = e.ty.field(field).ok_or(CompileError::Unimplemented)?;
+
+            self.mov("%rbp", "%rax");
+            self.sub(e.offset - offset, "%rax");
+            self.push("%rax");
+            self.gen_expr(value)?;
+            self.pop("%rdi");
+            self.pop("%rax");
+            self.store(ty);
+        }
+
+        self.mov("%rbp", "%rax");
+        self.sub(e.offset, "%rax");
         self.push("%rax");
 
         Ok(())
     }
 
+    /// 一時領域に要素の値を順に書き込み、そのアドレスを値とする
+    fn gen_tuple_expr(&mut self, e: &TupleExpr<Expr>, ty: &Type) -> CResult {
+        for (i, element) in e.elements.iter().enumerate() {
+            let (offset, element_ty) = ty
+                .field(&i.to_string())
+                .ok_or(CompileError::Unimplemented)?;
+
+            self.mov("%rbp", "%rax");
+            self.sub(e.offset - offset, "%rax");
+            self.push("%rax");
+            self.gen_expr(element)?;
+            self.pop("%rdi");
+            self.pop("%rax");
+            self.store(element_ty);
+        }
+
+        self.mov("%rbp", "%rax");
+        self.sub(e.offset, "%rax");
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    /// 一時領域の先頭にタグを、その後ろにペイロードを書き込み、そのアドレスを値とする
+    fn gen_enum_variant_expr(&mut self, e: &EnumVariantExpr<Expr>) -> CResult {
+        let (tag, payload) =
+            e.ty.variant(&e.variant)
+                .ok_or(CompileError::Unimplemented)?;
+
+        self.mov("%rbp", "%rax");
+        self.sub(e.offset, "%rax");
+        self.binary_op("movq", tag, "(%rax)");
+
+        for (arg, (offset, ty)) in e.args.iter().zip(payload) {
+            self.mov("%rbp", "%rax");
+            self.sub(e.offset - offset, "%rax");
+            self.push("%rax");
+            self.gen_expr(arg)?;
+            self.pop("%rdi");
+            self.pop("%rax");
+            self.store(ty);
+        }
+
+        self.mov("%rbp", "%rax");
+        self.sub(e.offset, "%rax");
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    /// 腕を上から順に照合し、一致しなければ次の腕へ飛ぶ
+    fn gen_match_expr(&mut self, e: &MatchExpr<Expr>) -> CResult {
+        let end_label = self.get_serial_label("match_end");
+
+        // 照合する値は腕の本体を評価し終えるまでスタックに残しておく
+        self.gen_expr(&e.expr)?;
+
+        let offset = e.expr.ty.is_aggregate().then_some(0);
+
+        for arm in &e.arms {
+            let next_label = self.get_serial_label("match_next");
+
+            self.gen_pattern(&arm.pattern, &e.expr.ty, offset, &next_label)?;
+
+            if let Some(guard) = &arm.guard {
+                self.gen_expr(guard)?;
+                self.pop("%rax");
+                self.cmp(0, "%rax");
+                self.je(&next_label);
+            }
+
+            // どの腕を通っても値が1つだけ積まれるように、本体の値は%raxに移してから合流する
+            self.gen_expr(&arm.body)?;
+            self.pop("%rax");
+            self.jmp(&end_label);
+            self.gen_label(next_label);
+        }
+
+        // 腕が値を網羅していることは型検査で確かめているので、ここへは来ない
+        self.gen_label(end_label);
+        self.pop("%rdi");
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    /// 値がパターンに一致しなければ`fail_label`へ飛び、一致すればパターンの変数に値を書き込む
+    ///
+    /// 照合する値はスタックの先頭にある。`offset`が`None`ならその値自体を、
+    /// `Some`ならその値が指す領域の中の、オフセットの位置にある値を照合する
+    fn gen_pattern(
+        &mut self,
+        pattern: &Pattern<Expr>,
+        ty: &Type,
+        offset: Option<isize>,
+        fail_label: &str,
+    ) -> CResult {
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding { ident, .. } => {
+                let TypedExpressionKind::Ident(ref binding) = ident.kind else {
+                    return Err(CompileError::ExpectedIdent {
+                        actual: ident.clone(),
+                    });
+                };
+
+                self.gen_pattern_value(offset, ty);
+                self.mov("%rax", "%rdi");
+                self.gen_var_addr(binding.offset);
+                self.store(ty);
+            }
+            Pattern::Literal { value, .. } => {
+                self.gen_expr(value)?;
+                self.pop("%rdi");
+                self.gen_pattern_value(offset, ty);
+                self.cmp("%rdi", "%rax");
+                self.jne(fail_label);
+            }
+            Pattern::Variant {
+                variant, fields, ..
+            } => {
+                let (tag, payload) = ty.variant(variant).ok_or(CompileError::Unimplemented)?;
+                let base = offset.unwrap_or(0);
+
+                self.gen_pattern_value(Some(base), &Type::int());
+                self.cmp(tag, "%rax");
+                self.jne(fail_label);
+
+                for (field, (field_offset, field_ty)) in fields.iter().zip(payload) {
+                    self.gen_pattern(field, field_ty, Some(base + field_offset), fail_label)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// スタックの先頭にある照合する値から、パターンと照合する値を%raxに読み込む
+    fn gen_pattern_value(&mut self, offset: Option<isize>, ty: &Type) {
+        self.mov("(%rsp)", "%rax");
+
+        if let Some(offset) = offset {
+            self.add(offset, "%rax");
+            self.load(ty);
+        }
+    }
+
     fn gen_return_expr(&mut self, e: &ReturnExpr<Expr>) -> CResult {
         self.gen_expr(&e.value)?;
         self.gen_epilogue();
@@ -403,30 +902,244 @@ impl Compiler {
         Ok(())
     }
 
+    fn gen_logical_expr(&mut self, e: &BinaryExpr<Expr>) -> CResult {
+        let end_label = self.get_serial_label("logic_end");
+
+        self.gen_expr(&e.lhs)?;
+        self.pop("%rax");
+        self.cmp(0, "%rax");
+
+        // 左辺だけで結果が決まる場合は右辺を飛ばし、左辺の値をそのまま結果にする
+        if e.kind == BinaryExprKind::And {
+            self.je(&end_label);
+        } else {
+            self.jne(&end_label);
+        }
+
+        self.gen_expr(&e.rhs)?;
+        self.pop("%rax");
+        self.gen_label(end_label);
+        self.cmp(0, "%rax");
+        self.unary_op("setne", "%al");
+        self.movzb("%al", "%rax");
+        self.push("%rax");
+
+        Ok(())
+    }
+
     fn gen_if_expr(&mut self, e: &wervc_ast::IfExpr<Expr>) -> CResult {
         self.gen_expr(&e.condition)?;
         self.pop("%rax");
         self.cmp(0, "%rax");
 
-        if let Some(alternative) = &e.alternative {
-            let else_label = self.get_if_else_label();
-            let end_label = self.get_if_end_label();
+        let else_label = self.get_if_else_label();
+        let end_label = self.get_if_end_label();
 
-            self.je(&else_label);
-            self.gen_expr(&e.consequence)?;
+        // どちらの分岐を通っても値が1つだけ積まれるように、分岐の値は%raxに移してから合流する
+        self.je(&else_label);
+        self.gen_expr(&e.consequence)?;
+        self.pop("%rax");
+        self.jmp(&end_label);
+        self.gen_label(else_label);
 
-            self.jmp(&end_label);
-            self.gen_label(else_label);
+        if let Some(alternative) = &e.alternative {
             self.gen_expr(alternative)?;
-            self.gen_label(end_label);
+            self.pop("%rax");
         } else {
-            let end_label = self.get_if_end_label();
+            self.mov(0, "%rax");
+        }
 
-            self.je(&end_label);
-            self.gen_expr(&e.consequence)?;
-            self.gen_label(end_label);
+        self.gen_label(end_label);
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    fn gen_while_expr(&mut self, e: &WhileExpr<Expr>) -> CResult {
+        let begin_label = self.get_serial_label("while");
+        let end_label = self.get_serial_label("while_end");
+
+        self.loop_labels.push(LoopLabels {
+            label: e.label.clone(),
+            continue_label: begin_label.clone(),
+            break_label: end_label.clone(),
+            depth: self.depth,
+        });
+
+        self.gen_label(&begin_label);
+        self.gen_expr(&e.condition)?;
+        self.pop("%rax");
+        self.cmp(0, "%rax");
+        self.je(&end_label);
+        self.gen_expr(&e.body)?;
+        self.pop("%rax");
+        self.jmp(&begin_label);
+        self.gen_label(end_label);
+
+        self.loop_labels.pop();
+
+        // while式自体の値として0を積む
+        self.push(0);
+
+        Ok(())
+    }
+
+    /// 範囲の終端や配列のアドレスと添字はスタックに積んだままループする
+    fn gen_for_expr(&mut self, e: &ForExpr<Expr>) -> CResult {
+        let begin_label = self.get_serial_label("for");
+        let next_label = self.get_serial_label("for_next");
+        let end_label = self.get_serial_label("for_end");
+
+        // ループの間スタックに置いておく値の数
+        let kept = match &e.iterable {
+            Iterable::Range { start, end, .. } => {
+                self.gen_left_val(&e.var)?;
+                self.gen_expr(start)?;
+                self.pop("%rdi");
+                self.pop("%rax");
+                self.store(&e.var.ty);
+                self.gen_expr(end)?;
+
+                1
+            }
+            Iterable::Array(array) => {
+                self.gen_expr(array)?;
+                self.push(0);
+
+                2
+            }
+        };
+
+        self.loop_labels.push(LoopLabels {
+            label: e.label.clone(),
+            continue_label: next_label.clone(),
+            break_label: end_label.clone(),
+            depth: self.depth,
+        });
+
+        self.gen_label(&begin_label);
+
+        match &e.iterable {
+            Iterable::Range { inclusive, .. } => {
+                self.gen_expr(&e.var)?;
+                self.pop("%rax");
+                self.cmp("(%rsp)", "%rax");
+                self.unary_op(if *inclusive { "jg" } else { "jge" }, &end_label);
+            }
+            Iterable::Array(array) => {
+                let TypeKind::Array { length, .. } = array.ty.unaliased().kind else {
+                    return Err(CompileError::Unimplemented);
+                };
+
+                self.mov("(%rsp)", "%rax");
+                self.cmp(length, "%rax");
+                self.unary_op("jge", &end_label);
+
+                // 要素のアドレスは 配列の先頭 + 添字 * 要素のサイズ
+                self.gen_left_val(&e.var)?;
+                self.mov("8(%rsp)", "%rax");
+                self.imul(e.var.ty.calc_size(), "%rax");
+                self.add("16(%rsp)", "%rax");
+                self.load(&e.var.ty);
+                self.mov("%rax", "%rdi");
+                self.pop("%rax");
+                self.store(&e.var.ty);
+            }
+        }
+
+        self.gen_expr(&e.body)?;
+        self.pop("%rax");
+        self.gen_label(&next_label);
+
+        match &e.iterable {
+            Iterable::Range { .. } => {
+                self.gen_left_val(&e.var)?;
+                self.pop("%rax");
+                self.binary_op("addq", 1, "(%rax)");
+            }
+            Iterable::Array(_) => self.binary_op("addq", 1, "(%rsp)"),
+        }
+
+        self.jmp(&begin_label);
+        self.gen_label(end_label);
+
+        self.loop_labels.pop();
+
+        for _ in 0..kept {
+            self.pop("%rax");
         }
 
+        // for式自体の値として0を積む
+        self.push(0);
+
+        Ok(())
+    }
+
+    /// `break`の値は%raxに入れてループの終わりへ運ぶ
+    fn gen_loop_expr(&mut self, e: &LoopExpr<Expr>) -> CResult {
+        let begin_label = self.get_serial_label("loop");
+        let end_label = self.get_serial_label("loop_end");
+
+        self.loop_labels.push(LoopLabels {
+            label: e.label.clone(),
+            continue_label: begin_label.clone(),
+            break_label: end_label.clone(),
+            depth: self.depth,
+        });
+
+        self.gen_label(&begin_label);
+        self.gen_expr(&e.body)?;
+        self.pop("%rax");
+        self.jmp(&begin_label);
+        self.gen_label(end_label);
+
+        self.loop_labels.pop();
+
+        self.push("%rax");
+
+        Ok(())
+    }
+
+    fn gen_break_expr(&mut self, e: &BreakExpr<Expr>) -> CResult {
+        if let Some(value) = &e.value {
+            self.gen_expr(value)?;
+            self.pop("%rax");
+        } else {
+            self.mov(0, "%rax");
+        }
+
+        self.gen_loop_jump(e.span, &e.label, |labels| labels.break_label.clone())
+    }
+
+    /// ループに入ってから積んだ値を捨てて、ループの飛び先へジャンプする
+    ///
+    /// `label`が`None`なら最も内側のループへ飛ぶ
+    fn gen_loop_jump(
+        &mut self,
+        span: Span,
+        label: &Option<String>,
+        target: impl Fn(&LoopLabels) -> String,
+    ) -> CResult {
+        let Some(labels) = self
+            .loop_labels
+            .iter()
+            .rev()
+            .find(|labels| label.is_none() || labels.label == *label)
+        else {
+            return Err(CompileError::OutsideLoop(span));
+        };
+        let label = target(labels);
+        let size = (self.depth - labels.depth) as isize * 8;
+
+        if size > 0 {
+            self.add(size, "%rsp");
+        }
+
+        self.jmp(label);
+
+        // ここには到達しないが、式として値を1つ積んだものとして扱う
+        self.push(0);
+
         Ok(())
     }
 
@@ -437,109 +1150,280 @@ impl Compiler {
         Ok(())
     }
 
-    fn gen_call_expr(&mut self, e: &CallExpr<Expr>) -> CResult {
-        match &e.func.kind {
-            TypedExpressionKind::Ident(func_name) => {
-                let mut register_num = 0;
+    /// 定義済みの関数は名前で直接呼び出し、それ以外は関数のアドレスを求めて間接的に呼び出す
+    fn gen_call_expr(&mut self, e: &CallExpr<Expr>, ty: &Type) -> CResult {
+        let label = match &e.func.kind {
+            TypedExpressionKind::Ident(ident) => self.functions.get(&ident.offset).cloned(),
+            _ => None,
+        };
 
-                for arg in &e.args {
-                    self.gen_expr(arg)?;
-                    register_num += 1;
-                }
+        if label.is_none() {
+            self.gen_expr(&e.func)?;
+        }
 
-                for i in 0..register_num {
-                    self.pop(X86_64_ARG_REGISTERS[register_num - i - 1]);
-                }
+        // System V ABIに従い、浮動小数点数の引数はxmmレジスタ、それ以外は汎用レジスタで渡す
+        let mut registers = Vec::new();
+        let mut float_num = 0;
 
-                self.mov(0, "%rax");
+        for arg in &e.args {
+            self.gen_expr(arg)?;
 
-                // rspを16バイト境界に揃える
-                if self.depth % 2 == 0 {
-                    self.call(&func_name.name);
-                } else {
-                    self.sub(8, "%rsp");
-                    self.call(&func_name.name);
-                    self.add(8, "%rsp");
-                }
+            let register = if arg.ty.unaliased().kind == TypeKind::Float {
+                float_num += 1;
+                X86_64_FLOAT_ARG_REGISTERS.get(float_num - 1)
+            } else {
+                X86_64_ARG_REGISTERS.get(registers.len() - float_num)
+            };
 
-                self.push("%rax");
+            registers.push(*register.ok_or(CompileError::TooManyArgs(arg.span()))?);
+        }
+
+        for register in registers.into_iter().rev() {
+            if register.starts_with("%xmm") {
+                self.pop("%rax");
+                self.movq("%rax", register);
+            } else {
+                self.pop(register);
             }
-            _ => {
-                return Err(CompileError::Unimplemented);
+        }
+
+        let target = match label {
+            Some(label) => label,
+            None => {
+                self.pop("%r10");
+                "*%r10".to_string()
+            }
+        };
+
+        // 可変長引数の関数のために、xmmレジスタで渡した引数の数を%alに入れておく
+        self.mov(float_num as isize, "%rax");
+
+        // rspを16バイト境界に揃える
+        if ty.is_aggregate() {
+            // 戻り値を書き込む一時領域のアドレスは、スタックに積んで渡す
+            let padded = self.depth.is_multiple_of(2);
+
+            if padded {
+                self.sub(8, "%rsp");
+            }
+
+            self.mov("%rbp", "%r11");
+            self.sub(e.offset, "%r11");
+            self.push("%r11");
+            self.call(&target);
+            self.pop("%rdi");
+
+            if padded {
+                self.add(8, "%rsp");
             }
+        } else if self.depth.is_multiple_of(2) {
+            self.call(&target);
+        } else {
+            self.sub(8, "%rsp");
+            self.call(&target);
+            self.add(8, "%rsp");
         }
 
+        if ty.unaliased().kind == TypeKind::Float {
+            self.movq("%xmm0", "%rax");
+        }
+
+        self.push("%rax");
+
         Ok(())
     }
 
     fn gen_function_def_expr(&mut self, e: &FunctionDefExpr<Expr>) -> CResult {
-        let func_name = match e.name.kind {
-            TypedExpressionKind::Ident(ref i) => &i.name,
-            _ => {
-                return Err(CompileError::ExpectedIdent {
-                    actual: *e.name.clone(),
-                });
-            }
+        let TypedExpressionKind::Ident(ref func_name) = e.name.kind else {
+            return Err(CompileError::ExpectedIdent {
+                actual: e.name.clone(),
+            });
         };
 
+        // 同じ名前の関数が複数あってもラベルが重ならないよう、オフセットをつける
+        let label = format!("{}.{}", func_name.name, func_name.offset);
+
+        // 本体から自分自身を呼び出せるよう、先に登録しておく
+        self.functions.insert(func_name.offset, label.clone());
+
+        self.gen_function(&label, &e.params, &e.return_ty, &e.body)?;
+
+        // 関数定義式自体の値として関数のアドレスを積む
+        self.gen_function_address(&label);
+
+        Ok(())
+    }
+
+    fn gen_lambda_expr(&mut self, e: &LambdaExpr<Expr>) -> CResult {
+        let label = self.get_serial_label("lambda");
+
+        self.gen_function(&label, &e.params, &e.return_ty, &e.body)?;
+        self.gen_function_address(&label);
+
+        Ok(())
+    }
+
+    fn gen_function_address(&mut self, label: &str) {
+        self.binary_op("lea", format!("{}(%rip)", label), "%rax");
+        self.push("%rax");
+    }
+
+    /// 関数の本体を、生成中のコードとは別の出力に書き出す
+    fn gen_function(
+        &mut self,
+        label: &str,
+        params: &[(Expr, Type)],
+        return_ty: &Type,
+        body: &Expr,
+    ) -> CResult {
+        let output_index = self.cur_output_index;
+        let depth = std::mem::take(&mut self.depth);
+        let loop_labels = std::mem::take(&mut self.loop_labels);
+        let outer_return_ty = std::mem::replace(&mut self.return_ty, return_ty.clone());
+
         self.add_output();
         self.change_output_to_end();
-        self.add_code(format!(".globl {}", func_name));
-        self.gen_label(func_name);
+        self.gen_label(label);
         self.push("%rbp");
         self.mov("%rsp", "%rbp");
 
-        let mut max_offset = 0;
+        let (mut int_num, mut float_num) = (0, 0);
 
-        for (i, (param, _)) in e.params.iter().enumerate() {
+        for (param, param_ty) in params {
             if let TypedExpressionKind::Ident(param_ident) = &param.kind {
-                max_offset = max_offset.max(param_ident.offset);
+                // 構造体と列挙型とタプルは呼び出し元のアドレスが渡されるので、自分の領域にコピーする
+                let too_many_args = CompileError::TooManyArgs(param_ident.span);
+
+                if param_ty.is_aggregate() {
+                    let register = X86_64_ARG_REGISTERS.get(int_num).ok_or(too_many_args)?;
+
+                    self.mov(*register, "%rdi");
+                    self.mov("%rbp", "%rax");
+                    self.sub(param_ident.offset, "%rax");
+                    self.store(param_ty);
+                    int_num += 1;
+
+                    continue;
+                }
 
                 // パラメータのオフセットを計算
                 // 積むデータのサイズ分オフセットをずらす
                 self.sub(param_ident.offset - 8, "%rsp");
-                self.push(X86_64_ARG_REGISTERS[i]);
+
+                if param_ty.unaliased().kind == TypeKind::Float {
+                    let register = X86_64_FLOAT_ARG_REGISTERS
+                        .get(float_num)
+                        .ok_or(too_many_args)?;
+
+                    self.movq(*register, "%rax");
+                    self.push("%rax");
+                    float_num += 1;
+                } else {
+                    self.push(*X86_64_ARG_REGISTERS.get(int_num).ok_or(too_many_args)?);
+                    int_num += 1;
+                }
+
                 self.mov("%rbp", "%rsp");
             } else {
                 return Err(CompileError::ExpectedIdent {
-                    actual: param.clone(),
+                    actual: Box::new(param.clone()),
                 });
             }
         }
 
-        self.sub(max_offset, "%rsp");
-        self.gen_expr(&e.body)?;
+        // オフセットはプログラム全体で通し番号になっているので、本体の変数も含めて確保する
+        self.sub(self.total_stack_size, "%rsp");
+
+        // ここでスタックは16バイト境界に揃っている
+        self.depth = 0;
+        self.gen_expr(body)?;
         self.gen_epilogue();
-        self.change_output_to_head();
+
+        self.cur_output_index = output_index;
+        self.depth = depth;
+        self.loop_labels = loop_labels;
+        self.return_ty = outer_return_ty;
 
         Ok(())
     }
 
     fn gen_epilogue(&mut self) {
         self.pop("%rax");
+
+        // 構造体などの戻り値は、呼び出し元が渡した一時領域にコピーし、そのアドレスを返す
+        if self.return_ty.is_aggregate() {
+            self.mov("%rax", "%rdi");
+            self.mov("16(%rbp)", "%rax");
+            self.store(&self.return_ty.clone());
+        }
+        // 浮動小数点数を返す関数のために、戻り値を%xmm0にも入れておく
+        self.movq("%rax", "%xmm0");
         self.mov("%rbp", "%rsp");
         self.pop("%rbp");
         self.ret();
     }
 
     fn gen_let_expr(&mut self, e: &LetExpr<Expr>) -> CResult {
-        if let Some(value) = &e.value {
+        if let (TypedExpressionKind::TupleExpr(target), Some(value)) = (&e.name.kind, &e.value) {
+            // 値はスタックに残したまま、要素ごとに変数へ書き込む
+            self.gen_expr(value)?;
+            self.gen_destructure(target, &e.ty, 0)?;
+        } else if let Some(value) = &e.value {
             self.gen_left_val(&e.name)?;
             self.gen_expr(value)?;
             self.pop("%rdi");
             self.pop("%rax");
-            self.mov("%rdi", "(%rax)");
+            self.store(&e.ty);
             self.push("%rdi");
-            self.push("%rax");
         } else {
             self.gen_left_val(&e.name)?;
-            self.pop("%rax");
-            self.push("%rax");
-            self.push("%rax");
         }
 
         Ok(())
     }
+
+    /// スタックの先頭にあるタプルの、`base`の位置から始まる要素を`let`の左辺の変数に書き込む
+    fn gen_destructure(&mut self, target: &TupleExpr<Expr>, ty: &Type, base: isize) -> CResult {
+        for (i, element) in target.elements.iter().enumerate() {
+            let (offset, element_ty) = ty
+                .field(&i.to_string())
+                .ok_or(CompileError::Unimplemented)?;
+
+            match &element.kind {
+                TypedExpressionKind::TupleExpr(inner) => {
+                    self.gen_destructure(inner, element_ty, base + offset)?;
+                }
+                TypedExpressionKind::Ident(ident) => {
+                    self.gen_pattern_value(Some(base + offset), element_ty);
+                    self.mov("%rax", "%rdi");
+                    self.gen_var_addr(ident.offset);
+                    self.store(element_ty);
+                }
+                _ => {
+                    return Err(CompileError::ExpectedIdent {
+                        actual: Box::new(element.clone()),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 文字列をアセンブラの .string ディレクティブで扱える形にエスケープする
+fn escape_asm_string(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'\\' => "\\\\".to_string(),
+            b'"' => "\\\"".to_string(),
+            b'\n' => "\\n".to_string(),
+            b'\t' => "\\t".to_string(),
+            0x20..=0x7e => (b as char).to_string(),
+            _ => format!("\\{:03o}", b),
+        })
+        .collect()
 }
 
 impl Default for Compiler {
@@ -36,6 +36,12 @@ impl<K: Eq + PartialEq + Hash + Clone, V: Clone> Environment<K, V> {
         }
     }
 
+    /// スコープの深さを返す
+    /// 一番外側のスコープは0になる
+    pub fn depth(&self) -> usize {
+        self.outer.as_ref().map_or(0, |outer| outer.depth() + 1)
+    }
+
     /// スコープを深くする
     pub fn create_deeper_scope(&mut self) -> Environment<K, V> {
         self.outer = Some(Box::new(self.clone()));
@@ -1,21 +1,45 @@
-use std::collections::HashMap;
-use wervc_object::Object;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+use wervc_object::{Captured, CapturedSlot, Object, Slot};
 
 #[derive(Clone)]
 pub struct Environment {
-    store: HashMap<String, Object>,
+    store: HashMap<String, Slot>,
     outer: Option<Box<Environment>>,
+    /// `store`のうち、関数の定義で束縛した名前
+    functions: HashSet<String>,
+    /// 関数の本体を評価している環境かどうか
+    in_function: bool,
+    /// 捕捉した環境のうち、外側の関数の中で定義された変数の名前
+    outer_locals: HashSet<String>,
 }
 
 impl Environment {
     pub fn new(outer: Option<Box<Environment>>) -> Environment {
+        let in_function = outer.as_ref().is_some_and(|outer| outer.in_function);
+
         Environment {
             store: HashMap::new(),
             outer,
+            functions: HashSet::new(),
+            in_function,
+            outer_locals: HashSet::new(),
+        }
+    }
+
+    /// 関数を呼び出したときの、引数を束縛する環境を生成する
+    pub fn function(captured: Captured) -> Environment {
+        Environment {
+            in_function: true,
+            ..Environment::new(Some(Box::new(Environment::from(captured))))
         }
     }
 
     pub fn set_outer(&mut self, outer: Environment) {
+        self.in_function = outer.in_function;
         self.outer = Some(Box::new(outer));
     }
 
@@ -23,31 +47,100 @@ impl Environment {
         self.outer.map(|env| *env)
     }
 
-    pub fn get(&self, key: &str) -> Option<&Object> {
+    fn slot(&self, key: &str) -> Option<&Slot> {
         if let Some(v) = self.store.get(key) {
             return Some(v);
         }
 
         if let Some(outer) = &self.outer {
-            return outer.get(key);
+            return outer.slot(key);
         }
 
         None
     }
 
-    pub fn insert(&mut self, key: String, value: Object) -> Option<Object> {
-        self.store.insert(key, value)
+    pub fn get(&self, key: &str) -> Option<Object> {
+        self.slot(key).map(|slot| slot.borrow().clone())
+    }
+
+    /// 外側の関数の中で定義された変数を指しているかどうか
+    pub fn is_outer_local(&self, key: &str) -> bool {
+        if self.outer_locals.contains(key) {
+            return true;
+        }
+
+        if self.store.contains_key(key) {
+            return false;
+        }
+
+        self.outer
+            .as_ref()
+            .is_some_and(|outer| outer.is_outer_local(key))
+    }
+
+    /// 新しい領域を確保して変数を定義する
+    /// 同じ名前の変数があっても、その領域は書き換えない
+    pub fn insert(&mut self, key: String, value: Object) {
+        self.functions.remove(&key);
+        self.store.insert(key, Rc::new(RefCell::new(value)));
+    }
+
+    /// 関数の定義として名前を束縛する
+    ///
+    /// 関数はコンパイル後にラベルで呼び出すので、内側の関数からも参照できる
+    pub fn insert_function(&mut self, key: String, value: Object) {
+        self.insert(key.clone(), value);
+        self.functions.insert(key);
     }
 
+    /// 定義済みの変数の領域に値を書き込み、元の値を返す
     pub fn update(&mut self, key: String, value: Object) -> Option<Object> {
-        let result = self.store.insert(key.clone(), value.clone());
+        self.slot(&key).map(|slot| slot.replace(value))
+    }
+
+    /// 現在見えている変数の領域を取り出す
+    pub fn capture(&self) -> Captured {
+        let mut captured = self
+            .outer
+            .as_ref()
+            .map(|outer| outer.capture())
+            .unwrap_or_default();
+
+        for (key, slot) in &self.store {
+            let outer_local = self.outer_locals.contains(key)
+                || self.in_function && !self.functions.contains(key);
 
-        if result.is_none() {
-            if let Some(outer) = &mut self.outer {
-                return outer.update(key, value);
-            }
+            captured.0.insert(
+                key.clone(),
+                CapturedSlot {
+                    slot: Rc::clone(slot),
+                    outer_local,
+                },
+            );
         }
 
-        result
+        captured
+    }
+}
+
+impl From<Captured> for Environment {
+    fn from(captured: Captured) -> Self {
+        let outer_locals = captured
+            .0
+            .iter()
+            .filter(|(_, captured)| captured.outer_local)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let store = captured
+            .0
+            .into_iter()
+            .map(|(key, captured)| (key, captured.slot))
+            .collect();
+
+        Environment {
+            store,
+            outer_locals,
+            ..Environment::new(None)
+        }
     }
 }
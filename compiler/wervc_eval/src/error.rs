@@ -1,11 +1,49 @@
-use wervc_ast::Expression;
+use wervc_ast::{Expression, Span};
 use wervc_object::Object;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub enum EvalError {
-    UnexpectedObject(Object),
-    UndefinedVariable(String),
-    IdentRequired { actual: Expression },
-    UnmatchedArgsLen { expected: usize, actual: usize },
-    OutOfRange,
+    UnexpectedObject(Object, Span),
+    UndefinedVariable(String, Span),
+    UndefinedField(String, Span),
+    NoMatchingArm(Object, Span),
+    IdentRequired {
+        actual: Box<Expression>,
+    },
+    UnmatchedArgsLen {
+        expected: usize,
+        actual: usize,
+        span: Span,
+    },
+    OutOfRange(Span),
+    DivisionByZero(Span),
+    AssignToImmutable(String, Span),
+    AddrOfImmutable(String, Span),
+    /// 制限された数の式を評価しても終わらなかった
+    OutOfFuel(Span),
+    /// 初期値のない`let`
+    Uninitialized(Span),
+    /// 外側の関数の変数の参照
+    CapturedLocal(String, Span),
+}
+
+impl EvalError {
+    /// エラーの原因となったソースコード上の範囲を返す
+    pub fn span(&self) -> Span {
+        match self {
+            EvalError::UnexpectedObject(_, span)
+            | EvalError::UndefinedVariable(_, span)
+            | EvalError::UndefinedField(_, span)
+            | EvalError::NoMatchingArm(_, span)
+            | EvalError::UnmatchedArgsLen { span, .. }
+            | EvalError::OutOfRange(span)
+            | EvalError::DivisionByZero(span)
+            | EvalError::AssignToImmutable(_, span)
+            | EvalError::AddrOfImmutable(_, span)
+            | EvalError::OutOfFuel(span)
+            | EvalError::Uninitialized(span)
+            | EvalError::CapturedLocal(_, span) => *span,
+            EvalError::IdentRequired { actual } => actual.span(),
+        }
+    }
 }
@@ -7,13 +7,23 @@ mod test;
 use builtin::{call_builtin, is_builtin};
 use environment::Environment;
 use error::EvalError;
+use std::{cell::Cell, ops::ControlFlow, rc::Rc};
 use wervc_ast::{
-    Array, BinaryExpr, BinaryExprKind, BlockExpr, Boolean, CallExpr, Expression, FunctionDefExpr,
-    Ident, IfExpr, Integer, LetExpr, Node, ReturnExpr, Statement, UnaryExpr, UnaryExprKind,
+    ty::TypeKind, Array, BinaryExpr, BinaryExprKind, BlockExpr, Boolean, BreakExpr, CallExpr, Char,
+    EnumVariantExpr, Expression, FieldExpr, Float, ForExpr, FunctionDefExpr, Ident, IfExpr,
+    Integer, Iterable, LambdaExpr, LetExpr, LoopExpr, MatchArm, MatchExpr, Node, Pattern,
+    ReturnExpr, Statement, Str, StructLiteral, TupleExpr, UnaryExpr, UnaryExprKind, WhileExpr,
 };
 use wervc_object::Object::{self, *};
 
 type EResult = Result<Object, EvalError>;
+/// 評価する式の数を制限しているときに、入れ子にできる関数呼び出しの深さ
+///
+/// デバッグビルドでは呼び出し1段あたりスタックを数十KiB使うので、
+/// 制限した評価は`FUELED_STACK_SIZE`のスタックを持つスレッドで行う
+pub const MAX_FUELED_CALL_DEPTH: usize = 256;
+/// 関数呼び出しを`MAX_FUELED_CALL_DEPTH`段まで入れ子にしても溢れないスタックの大きさ
+pub const FUELED_STACK_SIZE: usize = 64 * 1024 * 1024;
 /// 式の種類を必要とする型に対して与える型
 ///
 /// TODO: あとで型付きの式に置き換える
@@ -21,6 +31,12 @@ type Expr = Expression;
 
 pub struct Evaluator {
     env: Environment,
+    /// 評価できる式の残りの数
+    ///
+    /// 内側の評価器とも共有し、`None`なら制限しない
+    fuel: Option<Rc<Cell<usize>>>,
+    /// 評価中の関数呼び出しの深さ
+    call_depth: usize,
 }
 impl Default for Evaluator {
     fn default() -> Self {
@@ -32,6 +48,27 @@ impl Evaluator {
     pub fn new() -> Evaluator {
         Evaluator {
             env: Environment::new(None),
+            fuel: None,
+            call_depth: 0,
+        }
+    }
+
+    /// 評価する式の数を`fuel`までに制限した評価器を生成する
+    ///
+    /// 定数の初期値のように、必ず終わらせたい評価に使う
+    pub fn with_fuel(fuel: usize) -> Evaluator {
+        Evaluator {
+            fuel: Some(Rc::new(Cell::new(fuel))),
+            ..Evaluator::new()
+        }
+    }
+
+    /// 内側のスコープ用に、制限を引き継いだ評価器を生成する
+    fn inner(&self) -> Evaluator {
+        Evaluator {
+            fuel: self.fuel.clone(),
+            call_depth: self.call_depth,
+            ..Evaluator::new()
         }
     }
 
@@ -65,7 +102,7 @@ impl Evaluator {
         for stmt in stmts {
             let value = self.eval_stmt(stmt)?;
 
-            if value.is_return() {
+            if value.is_escaping() {
                 return Ok(value);
             }
 
@@ -80,7 +117,7 @@ impl Evaluator {
             Statement::ExprStmt(e) => {
                 let value = self.eval_expr(e)?;
 
-                if value.is_return() {
+                if value.is_escaping() {
                     return Ok(value);
                 }
 
@@ -91,19 +128,45 @@ impl Evaluator {
     }
 
     fn eval_expr(&mut self, expr: Expression) -> EResult {
+        if let Some(fuel) = &self.fuel {
+            if fuel.get() == 0 {
+                return Err(EvalError::OutOfFuel(expr.span()));
+            }
+
+            fuel.set(fuel.get() - 1);
+        }
+
         match expr {
             Expression::Array(e) => self.eval_array(e),
             Expression::UnaryExpr(e) => self.eval_unary_expr(e),
             Expression::ReturnExpr(e) => self.eval_return_expr(e),
             Expression::Boolean(e) => self.eval_boolean(e),
             Expression::IfExpr(e) => self.eval_if_expr(e),
+            Expression::WhileExpr(e) => self.eval_while_expr(e),
+            Expression::ForExpr(e) => self.eval_for_expr(e),
+            Expression::LoopExpr(e) => self.eval_loop_expr(e),
+            Expression::BreakExpr(e) => self.eval_break_expr(e),
+            Expression::ContinueExpr(e) => Ok(Continue { label: e.label }),
             Expression::Ident(e) => self.eval_ident(e),
             Expression::BinaryExpr(e) => self.eval_binary_expr(e),
             Expression::LetExpr(e) => self.eval_let_expr(e),
             Expression::BlockExpr(e) => self.eval_block_expr(e),
             Expression::CallExpr(e) => self.eval_call_expr(e),
             Expression::Integer(e) => self.eval_integer(e),
+            Expression::Float(e) => self.eval_float(e),
+            Expression::Str(e) => self.eval_str(e),
+            Expression::Char(e) => self.eval_char(e),
             Expression::FunctionDefExpr(e) => self.eval_function_def_expr(e),
+            Expression::LambdaExpr(e) => self.eval_lambda_expr(e),
+            Expression::StructDefExpr(_) => Ok(Unit),
+            Expression::StructLiteral(e) => self.eval_struct_literal(e),
+            Expression::FieldExpr(e) => self.eval_field_expr(e),
+            Expression::TupleExpr(e) => self.eval_tuple_expr(e),
+            Expression::EnumDefExpr(_)
+            | Expression::TypeAliasExpr(_)
+            | Expression::ConstExpr(_) => Ok(Unit),
+            Expression::EnumVariantExpr(e) => self.eval_enum_variant_expr(e),
+            Expression::MatchExpr(e) => self.eval_match_expr(e),
         }
     }
 
@@ -117,20 +180,225 @@ impl Evaluator {
         Ok(Array(result))
     }
 
+    fn eval_struct_literal(&mut self, literal: StructLiteral<Expr>) -> EResult {
+        let TypeKind::Struct { name, fields } = literal.ty.kind else {
+            unreachable!("struct literal of non-struct type: {:?}", literal.ty);
+        };
+        let mut values = Vec::new();
+
+        // 値は書かれた順に評価する
+        for (field, value) in literal.fields {
+            let value = self.eval_expr(value)?;
+
+            if value.is_escaping() {
+                return Ok(value);
+            }
+
+            values.push((field, value));
+        }
+
+        // フィールドは宣言された順に並べる
+        let fields = fields
+            .iter()
+            .map(|(field, _)| {
+                let value = values
+                    .iter()
+                    .position(|(name, _)| name == field)
+                    .map(|i| values.swap_remove(i).1)
+                    .ok_or_else(|| EvalError::UndefinedField(field.clone(), literal.span))?;
+
+                Ok((field.clone(), value))
+            })
+            .collect::<Result<_, EvalError>>()?;
+
+        Ok(Struct {
+            name: name.into_string(),
+            fields,
+        })
+    }
+
+    fn eval_field_expr(&mut self, field_expr: FieldExpr<Expr>) -> EResult {
+        let span = field_expr.expr.span();
+        let value = self.eval_expr(*field_expr.expr)?;
+
+        if value.is_escaping() {
+            return Ok(value);
+        }
+
+        if !matches!(value, Struct { .. } | Tuple(_)) {
+            return Err(EvalError::UnexpectedObject(value, span));
+        }
+
+        let mut value = value;
+
+        value
+            .field_mut(&field_expr.field)
+            .map(|field| std::mem::replace(field, Unit))
+            .ok_or(EvalError::UndefinedField(field_expr.field, field_expr.span))
+    }
+
+    fn eval_tuple_expr(&mut self, tuple: TupleExpr<Expr>) -> EResult {
+        let mut values = Vec::new();
+
+        for element in tuple.elements {
+            let value = self.eval_expr(element)?;
+
+            if value.is_escaping() {
+                return Ok(value);
+            }
+
+            values.push(value);
+        }
+
+        Ok(Tuple(values))
+    }
+
+    fn eval_enum_variant_expr(&mut self, variant_expr: EnumVariantExpr<Expr>) -> EResult {
+        let TypeKind::Enum { name, .. } = variant_expr.ty.kind else {
+            unreachable!("variant of non-enum type: {:?}", variant_expr.ty);
+        };
+        let mut values = Vec::new();
+
+        for arg in variant_expr.args {
+            let value = self.eval_expr(arg)?;
+
+            if value.is_escaping() {
+                return Ok(value);
+            }
+
+            values.push(value);
+        }
+
+        Ok(Enum {
+            name: name.into_string(),
+            variant: variant_expr.variant,
+            values,
+        })
+    }
+
+    fn eval_match_expr(&mut self, match_expr: MatchExpr<Expr>) -> EResult {
+        let span = match_expr.expr.span();
+        let value = self.eval_expr(*match_expr.expr)?;
+
+        if value.is_escaping() {
+            return Ok(value);
+        }
+
+        for arm in match_expr.arms {
+            // パターンで束縛する変数は腕ごとに新しいスコープへ束縛する
+            let mut inner = self.inner();
+
+            inner.set_outer(self.env.clone());
+
+            let result = inner.eval_match_arm(arm, &value);
+
+            self.set_env(inner.env.outer().unwrap());
+
+            if let Some(result) = result? {
+                return Ok(result);
+            }
+        }
+
+        Err(EvalError::NoMatchingArm(value, span))
+    }
+
+    /// パターンとガードが値に一致すれば腕の本体を評価し、一致しなければ`None`を返す
+    fn eval_match_arm(
+        &mut self,
+        arm: MatchArm<Expr>,
+        value: &Object,
+    ) -> Result<Option<Object>, EvalError> {
+        if !self.match_pattern(arm.pattern, value)? {
+            return Ok(None);
+        }
+
+        if let Some(guard) = arm.guard {
+            let span = guard.span();
+
+            match self.eval_expr(*guard)? {
+                Boolean(true) => {}
+                Boolean(false) => return Ok(None),
+                guard if guard.is_escaping() => return Ok(Some(guard)),
+                guard => return Err(EvalError::UnexpectedObject(guard, span)),
+            }
+        }
+
+        self.eval_expr(*arm.body).map(Some)
+    }
+
+    /// 値がパターンに一致するかを調べ、一致した部分の値をパターンの変数に束縛する
+    fn match_pattern(&mut self, pattern: Pattern<Expr>, value: &Object) -> Result<bool, EvalError> {
+        match pattern {
+            Pattern::Wildcard(_) => Ok(true),
+            Pattern::Binding { ident, .. } => {
+                let Expression::Ident(Ident { name, .. }) = *ident else {
+                    return Err(EvalError::IdentRequired { actual: ident });
+                };
+
+                self.env.insert(name, value.clone());
+
+                Ok(true)
+            }
+            Pattern::Literal { value: literal, .. } => Ok(self.eval_expr(*literal)? == *value),
+            Pattern::Variant {
+                variant, fields, ..
+            } => {
+                let Enum {
+                    variant: actual,
+                    values,
+                    ..
+                } = value
+                else {
+                    return Ok(false);
+                };
+
+                if *actual != variant {
+                    return Ok(false);
+                }
+
+                for (field, value) in fields.into_iter().zip(values) {
+                    if !self.match_pattern(field, value)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+        }
+    }
+
     fn eval_unary_expr(&mut self, unary: UnaryExpr<Expr>) -> EResult {
+        let span = unary.expr.span();
+
+        if let UnaryExprKind::Addr = unary.kind {
+            if let Some(Ident {
+                name,
+                mutable: false,
+                span,
+                ..
+            }) = self.addressed_binding(&unary.expr)
+            {
+                return Err(EvalError::AddrOfImmutable(name, span));
+            }
+        }
         let value = self.eval_expr(*unary.expr)?;
 
         match unary.kind {
-            UnaryExprKind::Minus => {
-                if let Integer(value) = value {
-                    return Ok(Integer(-value));
-                }
-            }
+            UnaryExprKind::Minus => match value {
+                Integer(value) => return Ok(Integer(value.wrapping_neg())),
+                Float(value) => return Ok(Float(-value)),
+                _ => {}
+            },
             UnaryExprKind::Not => {
                 if let Boolean(value) = value {
                     return Ok(Boolean(!value));
                 }
             }
+            UnaryExprKind::BitNot => {
+                if let Integer(value) = value {
+                    return Ok(Integer(!value));
+                }
+            }
             UnaryExprKind::Deref => {
                 if let Pointer(value) = value {
                     return Ok(*value);
@@ -141,7 +409,40 @@ impl Evaluator {
             }
         }
 
-        Err(EvalError::UnexpectedObject(value))
+        Err(EvalError::UnexpectedObject(value, span))
+    }
+
+    /// アドレスを取る式について、書き換えられる変数を返す
+    ///
+    /// フィールドや配列の要素を辿って、元の変数を探す
+    fn addressed_binding(&mut self, expr: &Expr) -> Option<Ident> {
+        match expr {
+            Expression::Ident(ident) => Some(ident.clone()),
+            Expression::FieldExpr(FieldExpr { expr, .. }) => self.addressed_binding(expr),
+            Expression::UnaryExpr(UnaryExpr {
+                kind: UnaryExprKind::Deref,
+                expr,
+                ..
+            }) => {
+                // `a[i]`は`*(a + i)`なので、足される側が配列ならその配列の要素になる
+                let base = match &**expr {
+                    Expression::BinaryExpr(BinaryExpr {
+                        kind: BinaryExprKind::Add | BinaryExprKind::Sub,
+                        lhs,
+                        ..
+                    }) => lhs,
+                    _ => expr,
+                };
+                let ident = self.addressed_binding(base)?;
+
+                // 辿った式は変数とフィールドだけなので、評価しても副作用はない
+                match self.eval_expr(*base.clone()) {
+                    Ok(Array(_)) => Some(ident),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
     }
 
     fn eval_return_expr(&mut self, return_expr: ReturnExpr<Expr>) -> EResult {
@@ -153,9 +454,10 @@ impl Evaluator {
     }
 
     fn eval_if_expr(&mut self, if_expr: IfExpr<Expr>) -> EResult {
+        let span = if_expr.condition.span();
         let condition = self.eval_expr(*if_expr.condition)?;
 
-        if condition.is_return() {
+        if condition.is_escaping() {
             return Ok(condition);
         }
 
@@ -169,83 +471,245 @@ impl Evaluator {
             return Ok(Unit);
         }
 
-        Err(EvalError::UnexpectedObject(condition))
+        Err(EvalError::UnexpectedObject(condition, span))
+    }
+
+    fn eval_while_expr(&mut self, while_expr: WhileExpr<Expr>) -> EResult {
+        let span = while_expr.condition.span();
+
+        loop {
+            let condition = self.eval_expr(*while_expr.condition.clone())?;
+
+            match condition {
+                Boolean(true) => {}
+                Boolean(false) => break,
+                condition if condition.is_escaping() => return Ok(condition),
+                condition => return Err(EvalError::UnexpectedObject(condition, span)),
+            }
+
+            let result = self.eval_expr(*while_expr.body.clone())?;
+
+            if let ControlFlow::Break(value) = Self::loop_control(&while_expr.label, result) {
+                return Ok(value);
+            }
+        }
+
+        Ok(Unit)
+    }
+
+    fn eval_loop_expr(&mut self, loop_expr: LoopExpr<Expr>) -> EResult {
+        loop {
+            let result = self.eval_expr(*loop_expr.body.clone())?;
+
+            if let ControlFlow::Break(value) = Self::loop_control(&loop_expr.label, result) {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn eval_break_expr(&mut self, break_expr: BreakExpr<Expr>) -> EResult {
+        let value = match break_expr.value {
+            Some(value) => self.eval_expr(*value)?,
+            None => Unit,
+        };
+
+        if value.is_escaping() {
+            return Ok(value);
+        }
+
+        Ok(Break {
+            label: break_expr.label,
+            value: Box::new(value),
+        })
+    }
+
+    /// ループの本体を評価した結果から、`label`のループを続けるか抜けるかを決める
+    ///
+    /// 抜ける場合はループの値か、さらに外側へ伝える`return`などを返す
+    fn loop_control(label: &Option<String>, result: Object) -> ControlFlow<Object> {
+        match result {
+            Break {
+                label: target,
+                value,
+            } if target.is_none() || target == *label => ControlFlow::Break(*value),
+            Continue { label: target } if target.is_none() || target == *label => {
+                ControlFlow::Continue(())
+            }
+            result if result.is_escaping() => ControlFlow::Break(result),
+            _ => ControlFlow::Continue(()),
+        }
+    }
+
+    fn eval_for_expr(&mut self, for_expr: ForExpr<Expr>) -> EResult {
+        let Expression::Ident(Ident { name, .. }) = *for_expr.var else {
+            return Err(EvalError::IdentRequired {
+                actual: for_expr.var,
+            });
+        };
+
+        let values: Box<dyn Iterator<Item = Object>> = match for_expr.iterable {
+            Iterable::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let start = match self.eval_expr(*start.clone())? {
+                    Integer(value) => value,
+                    value if value.is_escaping() => return Ok(value),
+                    value => return Err(EvalError::UnexpectedObject(value, start.span())),
+                };
+                let end = match self.eval_expr(*end.clone())? {
+                    Integer(value) => value,
+                    value if value.is_escaping() => return Ok(value),
+                    value => return Err(EvalError::UnexpectedObject(value, end.span())),
+                };
+
+                if inclusive {
+                    Box::new((start..=end).map(Integer))
+                } else {
+                    Box::new((start..end).map(Integer))
+                }
+            }
+            Iterable::Array(array) => match self.eval_expr(*array.clone())? {
+                Array(elements) => Box::new(elements.into_iter()),
+                value if value.is_escaping() => return Ok(value),
+                value => return Err(EvalError::UnexpectedObject(value, array.span())),
+            },
+        };
+
+        for value in values {
+            // ループ変数は繰り返しごとに新しいスコープへ束縛する
+            let mut inner = self.inner();
+
+            inner.set_outer(self.env.clone());
+            inner.env.insert(name.clone(), value);
+
+            let result = inner.eval_expr(*for_expr.body.clone());
+
+            self.set_env(inner.env.outer().unwrap());
+
+            if let ControlFlow::Break(value) = Self::loop_control(&for_expr.label, result?) {
+                return Ok(value);
+            }
+        }
+
+        Ok(Unit)
+    }
+
+    fn eval_lambda_expr(&mut self, lambda: LambdaExpr<Expr>) -> EResult {
+        let mut params = Vec::new();
+
+        for (param, _) in lambda.params {
+            let Expression::Ident(Ident { name, .. }) = param else {
+                return Err(EvalError::IdentRequired {
+                    actual: Box::new(param),
+                });
+            };
+
+            params.push(name);
+        }
+
+        Ok(Function {
+            params,
+            body: lambda.body,
+            env: self.env.capture(),
+        })
     }
 
     fn eval_function_def_expr(&mut self, func_def: FunctionDefExpr<Expr>) -> EResult {
         if let Expression::Ident(Ident { name, .. }) = *func_def.name {
-            let params = func_def
-                .params
-                .iter()
-                .map(|e| match e {
-                    (Expression::Ident(Ident { name, .. }), _) => name.clone(),
-                    _ => panic!("Unexpected eval error: ident required but got {:?}", e),
-                })
-                .collect();
+            let mut params = Vec::new();
+
+            for (param, _) in func_def.params {
+                let Expression::Ident(Ident { name, .. }) = param else {
+                    return Err(EvalError::IdentRequired {
+                        actual: Box::new(param),
+                    });
+                };
+
+                params.push(name);
+            }
+            // 再帰呼び出しのために、自分自身を捕捉できるよう先に領域を確保する
+            self.env.insert_function(name.clone(), Unit);
+
             let literal = Function {
                 params,
-                body: *func_def.body,
+                body: func_def.body,
+                env: self.env.capture(),
             };
 
-            self.env.insert(name, literal.clone());
+            self.env.update(name, literal.clone());
 
             return Ok(literal);
         }
 
-        panic!(
-            "Unexpected eval error: ident required but got {:?}",
-            func_def.name
-        )
+        Err(EvalError::IdentRequired {
+            actual: func_def.name,
+        })
     }
 
-    fn eval_call_expr(&mut self, CallExpr { func, args }: CallExpr<Expr>) -> EResult {
+    fn eval_call_expr(
+        &mut self,
+        CallExpr {
+            func, args, span, ..
+        }: CallExpr<Expr>,
+    ) -> EResult {
         if is_builtin(&func) {
             let mut objects = Vec::new();
 
             for arg in args {
                 let arg = self.eval_expr(arg)?;
 
-                if arg.is_return() {
+                if arg.is_escaping() {
                     return Ok(arg);
                 }
 
                 objects.push(arg);
             }
 
-            return Ok(call_builtin(&func, &objects).unwrap());
+            return call_builtin(&func, &objects).ok_or(EvalError::IdentRequired { actual: func });
         }
 
+        let func_span = func.span();
         let func = self.eval_expr(*func)?;
 
-        if func.is_return() {
+        if func.is_escaping() {
             return Ok(func);
         }
 
-        if let Function { params, body } = &func {
+        if let Function { params, body, env } = &func {
             if args.len() != params.len() {
                 return Err(EvalError::UnmatchedArgsLen {
                     expected: params.len(),
                     actual: args.len(),
+                    span,
                 });
             }
 
-            let mut env = Environment::new(Some(Box::new(self.env.clone())));
+            // 関数の本体は呼び出し元ではなく、定義された時点の環境で評価する
+            let mut env = Environment::function(env.clone());
 
             for (arg, param) in args.into_iter().zip(params) {
                 let arg = self.eval_expr(arg)?;
 
-                if arg.is_return() {
+                if arg.is_escaping() {
                     return Ok(arg);
                 }
 
                 env.insert(param.clone(), arg);
             }
 
-            let mut inner = Evaluator::new();
+            let mut inner = self.inner();
+
+            inner.call_depth += 1;
+
+            if inner.fuel.is_some() && inner.call_depth > MAX_FUELED_CALL_DEPTH {
+                return Err(EvalError::OutOfFuel(span));
+            }
 
             inner.set_env(env);
 
-            let result = inner.eval_expr(body.clone())?;
+            let result = inner.eval_expr(*body.clone())?;
 
             if let Return(result) = result {
                 return Ok(*result);
@@ -254,12 +718,12 @@ impl Evaluator {
             return Ok(result);
         }
 
-        Err(EvalError::UnexpectedObject(func))
+        Err(EvalError::UnexpectedObject(func, func_span))
     }
 
     fn eval_block_expr(&mut self, block_expr: BlockExpr<Expr>) -> EResult {
         // 内側のスコープ用に評価器を生成
-        let mut inner = Evaluator::new();
+        let mut inner = self.inner();
 
         // 内側の環境のouterにブロックの外側のenvをクローン
         inner.set_outer(self.env.clone());
@@ -273,139 +737,235 @@ impl Evaluator {
     }
 
     fn eval_let_expr(&mut self, let_expr: LetExpr<Expr>) -> EResult {
-        if let Expression::Ident(Ident { name, .. }) = *let_expr.name {
-            let value = self.eval_expr(*let_expr.value.unwrap())?;
-
-            if value.is_return() {
-                return Ok(value);
-            }
-
-            self.env.insert(name, value.clone());
+        let Some(value) = let_expr.value else {
+            return Err(EvalError::Uninitialized(let_expr.span));
+        };
+        let value = self.eval_expr(*value)?;
 
+        if value.is_escaping() {
             return Ok(value);
         }
 
-        panic!(
-            "Unexpected eval error: ident required but got {:?}",
-            let_expr.name
-        )
+        self.bind(*let_expr.name, value.clone())?;
+
+        Ok(value)
+    }
+
+    /// `let`の左辺の変数に値を束縛する
+    ///
+    /// 左辺がタプルの場合は、値を要素ごとに分けて束縛する
+    fn bind(&mut self, target: Expr, value: Object) -> Result<(), EvalError> {
+        match (target, value) {
+            (Expression::Ident(Ident { name, .. }), value) => {
+                self.env.insert(name, value);
+
+                Ok(())
+            }
+            (Expression::TupleExpr(tuple), Tuple(values))
+                if tuple.elements.len() == values.len() =>
+            {
+                for (element, value) in tuple.elements.into_iter().zip(values) {
+                    self.bind(element, value)?;
+                }
+
+                Ok(())
+            }
+            (Expression::TupleExpr(tuple), value) => {
+                Err(EvalError::UnexpectedObject(value, tuple.span))
+            }
+            (target, _) => Err(EvalError::IdentRequired {
+                actual: Box::new(target),
+            }),
+        }
     }
 
     fn eval_ident(&mut self, ident: Ident) -> EResult {
+        // 外側の関数の変数は、コンパイル後と同じく参照できない
+        if self.env.is_outer_local(&ident.name) {
+            return Err(EvalError::CapturedLocal(ident.name, ident.span));
+        }
+
         if let Some(value) = self.env.get(&ident.name) {
-            return Ok(value.clone());
+            return Ok(value);
         }
 
-        Err(EvalError::UndefinedVariable(ident.name))
+        Err(EvalError::UndefinedVariable(ident.name, ident.span))
     }
 
     fn eval_binary_expr(&mut self, binary_expr: BinaryExpr<Expr>) -> EResult {
+        let span = binary_expr.rhs.span();
         let lhs = self.eval_expr(*binary_expr.lhs.clone())?;
 
-        if lhs.is_return() {
+        if lhs.is_escaping() {
             return Ok(lhs);
         }
 
+        // 左辺だけで結果が決まる場合は右辺を評価しない
+        match (binary_expr.kind, &lhs) {
+            (BinaryExprKind::And, Boolean(false)) | (BinaryExprKind::Or, Boolean(true)) => {
+                return Ok(lhs);
+            }
+            (BinaryExprKind::And | BinaryExprKind::Or, Boolean(_)) => {}
+            (BinaryExprKind::And | BinaryExprKind::Or, _) => {
+                return Err(EvalError::UnexpectedObject(lhs, binary_expr.lhs.span()));
+            }
+            _ => {}
+        }
+
         let rhs = self.eval_expr(*binary_expr.rhs.clone())?;
 
-        if rhs.is_return() {
+        if rhs.is_escaping() {
             return Ok(rhs);
         }
 
         let value = match binary_expr.kind {
             BinaryExprKind::Eq => Boolean(lhs == rhs),
             BinaryExprKind::Ne => Boolean(lhs != rhs),
-            BinaryExprKind::Add => {
-                if let Integer(lhs) = lhs {
-                    if let Integer(rhs) = rhs {
-                        return Ok(Integer(lhs + rhs));
-                    }
+            BinaryExprKind::Add => match (lhs, rhs) {
+                // コンパイル後のadd/sub/imulと同じく、溢れたら折り返す
+                (Integer(lhs), Integer(rhs)) => Integer(lhs.wrapping_add(rhs)),
+                (Float(lhs), Float(rhs)) => Float(lhs + rhs),
+                (Char(lhs), Integer(rhs)) => Char(wrap_char((lhs as isize).wrapping_add(rhs))),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Sub => match (lhs, rhs) {
+                (Integer(lhs), Integer(rhs)) => Integer(lhs.wrapping_sub(rhs)),
+                (Float(lhs), Float(rhs)) => Float(lhs - rhs),
+                (Char(lhs), Integer(rhs)) => Char(wrap_char((lhs as isize).wrapping_sub(rhs))),
+                (Char(lhs), Char(rhs)) => Integer(lhs as isize - rhs as isize),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Mul => match (lhs, rhs) {
+                (Integer(lhs), Integer(rhs)) => Integer(lhs.wrapping_mul(rhs)),
+                (Float(lhs), Float(rhs)) => Float(lhs * rhs),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Div => match (lhs, rhs) {
+                (Integer(_), Integer(0)) => return Err(EvalError::DivisionByZero(span)),
+                // isize::MIN / -1 は溢れるので、シフトと同じく折り返す
+                (Integer(lhs), Integer(rhs)) => Integer(lhs.wrapping_div(rhs)),
+                (Float(lhs), Float(rhs)) => Float(lhs / rhs),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Rem
+            | BinaryExprKind::BitAnd
+            | BinaryExprKind::BitOr
+            | BinaryExprKind::BitXor
+            | BinaryExprKind::Shl
+            | BinaryExprKind::Shr => match (lhs, rhs) {
+                (Integer(_), Integer(0)) if binary_expr.kind == BinaryExprKind::Rem => {
+                    return Err(EvalError::DivisionByZero(span))
                 }
+                (Integer(lhs), Integer(rhs)) => Integer(match binary_expr.kind {
+                    BinaryExprKind::Rem => lhs.wrapping_rem(rhs),
+                    BinaryExprKind::BitAnd => lhs & rhs,
+                    BinaryExprKind::BitOr => lhs | rhs,
+                    BinaryExprKind::BitXor => lhs ^ rhs,
+                    // シフト量はコンパイル後のsal/sarと同じく下位6ビットだけを使う
+                    BinaryExprKind::Shl => lhs.wrapping_shl(rhs as u32),
+                    _ => lhs.wrapping_shr(rhs as u32),
+                }),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Lt => match (lhs, rhs) {
+                (Integer(lhs), Integer(rhs)) => Boolean(lhs < rhs),
+                (Float(lhs), Float(rhs)) => Boolean(lhs < rhs),
+                (Char(lhs), Char(rhs)) => Boolean(lhs < rhs),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Le => match (lhs, rhs) {
+                (Integer(lhs), Integer(rhs)) => Boolean(lhs <= rhs),
+                (Float(lhs), Float(rhs)) => Boolean(lhs <= rhs),
+                (Char(lhs), Char(rhs)) => Boolean(lhs <= rhs),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Gt => match (lhs, rhs) {
+                (Integer(lhs), Integer(rhs)) => Boolean(lhs > rhs),
+                (Float(lhs), Float(rhs)) => Boolean(lhs > rhs),
+                (Char(lhs), Char(rhs)) => Boolean(lhs > rhs),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Ge => match (lhs, rhs) {
+                (Integer(lhs), Integer(rhs)) => Boolean(lhs >= rhs),
+                (Float(lhs), Float(rhs)) => Boolean(lhs >= rhs),
+                (Char(lhs), Char(rhs)) => Boolean(lhs >= rhs),
+                (_, rhs) => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::And | BinaryExprKind::Or => match rhs {
+                Boolean(_) => rhs,
+                rhs => return Err(EvalError::UnexpectedObject(rhs, span)),
+            },
+            BinaryExprKind::Assign => {
+                self.assign(*binary_expr.lhs, rhs.clone())?;
 
-                return Err(EvalError::UnexpectedObject(rhs));
+                rhs
             }
-            BinaryExprKind::Sub => {
-                if let Integer(lhs) = lhs {
-                    if let Integer(rhs) = rhs {
-                        return Ok(Integer(lhs - rhs));
-                    }
-                }
+        };
 
-                return Err(EvalError::UnexpectedObject(rhs));
-            }
-            BinaryExprKind::Mul => {
-                if let Integer(lhs) = lhs {
-                    if let Integer(rhs) = rhs {
-                        return Ok(Integer(lhs * rhs));
-                    }
-                }
+        Ok(value)
+    }
 
-                return Err(EvalError::UnexpectedObject(rhs));
+    /// 代入式の左辺に値を書き込む
+    ///
+    /// フィールドへの代入は、フィールドを書き換えた構造体やタプルを外側の左辺へ代入し直す
+    fn assign(&mut self, lhs: Expr, value: Object) -> Result<(), EvalError> {
+        match lhs {
+            Expression::Ident(Ident {
+                name,
+                mutable: false,
+                span,
+                ..
+            }) => Err(EvalError::AssignToImmutable(name, span)),
+            Expression::Ident(Ident { name, span, .. }) if self.env.is_outer_local(&name) => {
+                Err(EvalError::CapturedLocal(name, span))
             }
-            BinaryExprKind::Div => {
-                if let Integer(lhs) = lhs {
-                    if let Integer(rhs) = rhs {
-                        return Ok(Integer(lhs / rhs));
-                    }
-                }
+            Expression::Ident(Ident { name, span, .. }) => {
+                self.env
+                    .update(name.clone(), value)
+                    .ok_or(EvalError::UndefinedVariable(name, span))?;
 
-                return Err(EvalError::UnexpectedObject(rhs));
+                Ok(())
             }
-            BinaryExprKind::Lt => {
-                if let Integer(lhs) = lhs {
-                    if let Integer(rhs) = rhs {
-                        return Ok(Boolean(lhs < rhs));
-                    }
-                }
+            Expression::FieldExpr(FieldExpr { expr, field, span }) => {
+                let expr_span = expr.span();
+                let mut object = self.eval_expr(*expr.clone())?;
 
-                return Err(EvalError::UnexpectedObject(rhs));
-            }
-            BinaryExprKind::Le => {
-                if let Integer(lhs) = lhs {
-                    if let Integer(rhs) = rhs {
-                        return Ok(Boolean(lhs <= rhs));
-                    }
+                if !matches!(object, Struct { .. } | Tuple(_)) {
+                    return Err(EvalError::UnexpectedObject(object, expr_span));
                 }
 
-                return Err(EvalError::UnexpectedObject(rhs));
-            }
-            BinaryExprKind::Gt => {
-                if let Integer(lhs) = lhs {
-                    if let Integer(rhs) = rhs {
-                        return Ok(Boolean(lhs > rhs));
-                    }
-                }
+                let slot = object
+                    .field_mut(&field)
+                    .ok_or(EvalError::UndefinedField(field, span))?;
 
-                return Err(EvalError::UnexpectedObject(rhs));
-            }
-            BinaryExprKind::Ge => {
-                if let Integer(lhs) = lhs {
-                    if let Integer(rhs) = rhs {
-                        return Ok(Boolean(lhs >= rhs));
-                    }
-                }
+                *slot = value;
 
-                return Err(EvalError::UnexpectedObject(rhs));
+                self.assign(*expr, object)
             }
-            BinaryExprKind::Assign => {
-                if let Expression::Ident(Ident { name, .. }) = *binary_expr.lhs {
-                    self.env
-                        .update(name.clone(), rhs.clone())
-                        .ok_or_else(|| EvalError::UndefinedVariable(name.clone()))?;
+            lhs => Err(EvalError::IdentRequired {
+                actual: Box::new(lhs),
+            }),
+        }
+    }
 
-                    return Ok(rhs);
-                }
+    fn eval_integer(&mut self, integer: Integer) -> EResult {
+        Ok(Integer(integer.value))
+    }
 
-                return Err(EvalError::IdentRequired {
-                    actual: *binary_expr.lhs,
-                });
-            }
-        };
+    fn eval_float(&mut self, float: Float) -> EResult {
+        Ok(Float(float.value))
+    }
 
-        Ok(value)
+    fn eval_str(&mut self, str: Str) -> EResult {
+        Ok(Str(str.value))
     }
 
-    fn eval_integer(&mut self, integer: Integer) -> EResult {
-        Ok(Integer(integer.value))
+    fn eval_char(&mut self, char: Char) -> EResult {
+        Ok(Char(char.value))
     }
 }
+
+/// 文字の演算結果を1バイトに収める。コンパイル後のバイナリと同じく256で折り返す
+fn wrap_char(value: isize) -> u8 {
+    value.rem_euclid(256) as u8
+}
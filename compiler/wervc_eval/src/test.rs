@@ -1,7 +1,12 @@
-use crate::{error::EvalError, EResult, Evaluator};
-use wervc_ast::{BinaryExpr, BinaryExprKind, Expression, Ident, Integer};
-use wervc_object::Object::{self, *};
+use crate::{error::EvalError, EResult, Evaluator, FUELED_STACK_SIZE};
+use std::{cell::RefCell, rc::Rc, thread};
+use wervc_ast::{BinaryExpr, BinaryExprKind, Expression, Ident, Integer, Span};
+use wervc_object::{
+    Captured, CapturedSlot,
+    Object::{self, *},
+};
 use wervc_parser::parser::Parser;
+use wervc_span::assert_eq_ignoring_spans;
 
 fn loop_assert_unwrap<T, const N: usize>(inputs: [T; N], expects: [Object; N])
 where
@@ -12,10 +17,29 @@ where
         let program = parser.parse_program().unwrap();
         let mut evaluator = Evaluator::new();
 
-        assert_eq!(expect, evaluator.eval(program).unwrap());
+        assert_eq_ignoring_spans!(expect, evaluator.eval(program).unwrap());
     }
 }
 
+/// 関数が捕捉した変数の領域を、名前だけで作る
+///
+/// 捕捉した値は`Debug`で表示されないので、中身は何でもよい
+fn captured(names: &[&str]) -> Captured {
+    Captured(
+        names
+            .iter()
+            .map(|name| {
+                let slot = CapturedSlot {
+                    slot: Rc::new(RefCell::new(Unit)),
+                    outer_local: false,
+                };
+
+                (name.to_string(), slot)
+            })
+            .collect(),
+    )
+}
+
 fn loop_assert<T, const N: usize>(inputs: [T; N], expects: [EResult; N])
 where
     T: ToString,
@@ -25,18 +49,48 @@ where
         let program = parser.parse_program().unwrap();
         let mut evaluator = Evaluator::new();
 
-        assert_eq!(expect, evaluator.eval(program));
+        assert_eq_ignoring_spans!(expect, evaluator.eval(program));
     }
 }
 
 #[test]
 fn eval_error_test() {
-    let inputs = ["10 = 10", "if 1 1"];
+    let inputs = [
+        "10 = 10",
+        "if 1 1",
+        "match 2 { 1 => 1 }",
+        "let x: int = 1; x = 2",
+        "let f(n: int): int = { n = n + 1; n }; f(1)",
+        "let x: int = 1; &x",
+        "let add(n: int): (int) -> int = fn(x: int): int => x + n; add(1)(2)",
+        "struct P { x: int, y: int }; let s: P = P { x: 1, y: 2 }; &s.x",
+        "let t: (int, (int, int)) = (1, (2, 3)); &t.1.0",
+        "10 / 0",
+        "let n: int = 0; 10 % n",
+    ];
     let expects = [
         Err(EvalError::IdentRequired {
-            actual: Expression::Integer(Integer { value: 10 }),
+            actual: Box::new(Expression::Integer(Integer {
+                value: 10,
+                span: Span::default(),
+            })),
         }),
-        Err(EvalError::UnexpectedObject(Integer(1))),
+        Err(EvalError::UnexpectedObject(Integer(1), Span::default())),
+        Err(EvalError::NoMatchingArm(Integer(2), Span::default())),
+        Err(EvalError::AssignToImmutable(
+            "x".to_string(),
+            Span::default(),
+        )),
+        Err(EvalError::AssignToImmutable(
+            "n".to_string(),
+            Span::default(),
+        )),
+        Err(EvalError::AddrOfImmutable("x".to_string(), Span::default())),
+        Err(EvalError::CapturedLocal("n".to_string(), Span::default())),
+        Err(EvalError::AddrOfImmutable("s".to_string(), Span::default())),
+        Err(EvalError::AddrOfImmutable("t".to_string(), Span::default())),
+        Err(EvalError::DivisionByZero(Span::default())),
+        Err(EvalError::DivisionByZero(Span::default())),
     ];
 
     loop_assert(inputs, expects);
@@ -50,6 +104,285 @@ fn eval_integer_test() {
     loop_assert_unwrap(inputs, expects);
 }
 
+#[test]
+fn eval_str_test() {
+    let inputs = [
+        r#""hello""#,
+        r#""tab\tand\nnewline""#,
+        r#"let s: str = "werv"; s"#,
+        r#""a" == "a""#,
+        r#""a" != "b""#,
+    ];
+    let expects = [
+        Str("hello".to_string()),
+        Str("tab\tand\nnewline".to_string()),
+        Str("werv".to_string()),
+        Boolean(true),
+        Boolean(true),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_while_test() {
+    let inputs = [
+        "let mut i: int = 0; let mut s: int = 0; while i < 10 { s = s + i; i = i + 1; }; s",
+        "let mut i: int = 0; while true { i = i + 1; if i == 7 { break }; }; i",
+        "let mut i: int = 0; let mut s: int = 0; while i < 10 { i = i + 1; if i % 2 == 0 { continue }; s = s + i; }; s",
+        "let f(n: int): int = { let mut i: int = 0; while true { if i * i >= n { return i }; i = i + 1; }; 0 }; f(50)",
+        "let mut i: int = 0; let mut c: int = 0; while i < 3 { let mut j: int = 0; while j < 3 { j = j + 1; if j == 2 { break }; c = c + 1; }; i = i + 1; }; c",
+        "while false { 1 }",
+    ];
+    let expects = [
+        Integer(45),
+        Integer(7),
+        Integer(25),
+        Integer(8),
+        Integer(3),
+        Unit,
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_for_test() {
+    let inputs = [
+        "let mut s: int = 0; for i in 0..5 { s = s + i; }; s",
+        "let mut s: int = 0; for i in 1..=10 { s = s + i; }; s",
+        "let mut s: int = 0; for i in 0..10 { if i == 3 { continue }; if i == 6 { break }; s = s + i; }; s",
+        "let mut s: int = 0; for x in [3, 4, 5] { s = s * 10 + x; }; s",
+        "let mut c: int = 0; for i in 0..3 { for j in 0..=i { c = c + 1; }; }; c",
+        "let f(n: int): int = { for i in 0..10 { if i * i > n { return i }; }; 0 }; f(50)",
+        "let i: int = 7; for i in 0..3 { }; i",
+        "for i in 5..0 { 1 }",
+    ];
+    let expects = [
+        Integer(10),
+        Integer(55),
+        Integer(12),
+        Integer(345),
+        Integer(6),
+        Integer(8),
+        Integer(7),
+        Unit,
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_loop_test() {
+    let inputs = [
+        "let mut i: int = 0; loop { i = i + 1; if i == 5 { break } }; i",
+        "let mut i: int = 0; loop { i = i + 1; if i * i > 50 { break i * 2 } }",
+        "let mut c: int = 0; 'outer: for i in 0..5 { for j in 0..5 { if j == 3 { continue 'outer }; if i == 3 { break 'outer }; c = c + 1; }; }; c",
+        "'a: loop { let mut i: int = 0; while true { i = i + 1; if i == 4 { break 'a i * 10 } } }",
+        "let mut n: int = 0; 'o: while n < 10 { n = n + 1; loop { if n % 2 == 0 { continue 'o }; break }; n = n + 100 }; n",
+        "let f(mut n: int): int = { loop { if n > 3 { return n }; n = n + 1 }; 0 }; f(0)",
+    ];
+    let expects = [
+        Integer(5),
+        Integer(16),
+        Integer(9),
+        Integer(40),
+        Integer(101),
+        Integer(4),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_lambda_test() {
+    let inputs = [
+        "let f: (int) -> int = fn(x: int): int => x + 1; f(41)",
+        "let apply(f: (int) -> int, x: int): int = f(x); apply(fn(x: int): int => x * 3, 5)",
+        "let double(x: int): int = x * 2; let apply(f: (int) -> int, x: int): int = f(x); apply(double, 21)",
+        "let n: int = 40; let adder(): (int) -> int = fn(x: int): int => x + n; adder()(2)",
+        "(fn(x: int) => x - 1)(8)",
+    ];
+    let expects = [
+        Integer(42),
+        Integer(15),
+        Integer(42),
+        Integer(42),
+        Integer(7),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_struct_test() {
+    let inputs = [
+        "struct P { x: int, y: int }; let p: P = P { y: 2, x: 1 }; p.x * 10 + p.y",
+        "struct P { x: int, y: int }; let mut p: P = P { x: 1, y: 2 }; p.x = 5; p.x + p.y",
+        "struct P { x: int, y: int }; let p: P = P { x: 1, y: 2 }; let mut q: P = p; q.x = 9; p.x",
+        "struct P { x: int, y: int }; struct L { a: P, b: P }; let mut l: L = L { a: P { x: 1, y: 2 }, b: P { x: 3, y: 4 } }; l.b.x = 10; l.a.y + l.b.x",
+        "struct P { x: int, y: int }; P { x: 1, y: 2 }",
+    ];
+    let expects = [
+        Integer(12),
+        Integer(7),
+        Integer(1),
+        Integer(12),
+        Struct {
+            name: "P".to_string(),
+            fields: vec![("x".to_string(), Integer(1)), ("y".to_string(), Integer(2))],
+        },
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_const_test() {
+    let inputs = [
+        "const N: int = 4 * 16; N",
+        "const N: int = 3; const M: int = N * N; M - N",
+        "const B: bool = 1 < 2; if B { 1 } else { 2 }",
+        "const N: int = 2; match 2 { N => 10, _ => 20 }",
+        "const N: int = 2; let f(N: int): int = N; f(5) + N",
+    ];
+    let expects = [Integer(64), Integer(6), Integer(1), Integer(10), Integer(7)];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_tuple_test() {
+    let inputs = [
+        "let divmod(a: int, b: int): (int, int) = (a / b, a % b); let (q, r): (int, int) = divmod(17, 5); q * 10 + r",
+        "let mut t: (int, (int, bool)) = (1, (2, true)); t.1.0 = 7; t.0 + t.1.0",
+        "let (a, (b, c)): (int, (int, bool)) = (1, (2, false)); if c { 0 } else { a + b }",
+        "(1, true)",
+    ];
+    let expects = [
+        Integer(32),
+        Integer(8),
+        Integer(3),
+        Tuple(vec![Integer(1), Boolean(true)]),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_match_test() {
+    let inputs = [
+        "enum S { Circle(int), Rect(int, int), Empty }; let area(s: S): int = match s { S::Circle(r) => 3 * r * r, S::Rect(w, h) => w * h, S::Empty => 0 }; area(S::Circle(2)) + area(S::Rect(3, 4))",
+        "enum S { Rect(int, int) }; match S::Rect(2, 5) { S::Rect(w, h) if w > h => w, S::Rect(_, h) => h }",
+        "enum O { Some(int), None }; enum W { A(O), B }; match W::A(O::None) { W::A(O::Some(n)) => n, W::A(O::None) => 1, W::B => 2 }",
+        "match 3 { 1 => 10, 3 => 30, n => n }",
+        "match 'b' { 'a' => 1, c => c - 'a' }",
+        "enum O { Some(int), None }; O::Some(3)",
+    ];
+    let expects = [
+        Integer(24),
+        Integer(5),
+        Integer(1),
+        Integer(30),
+        Integer(1),
+        Enum {
+            name: "O".to_string(),
+            variant: "Some".to_string(),
+            values: vec![Integer(3)],
+        },
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_shadowing_test() {
+    let inputs = [
+        "let x: int = 7; let x: bool = false; x",
+        "let x: int = 1; let x: int = x + 1; x",
+        "let x: int = 3; { let x: int = 5; x }; x",
+        "let x: int = 1; let f(): int = x; let x: int = 2; f() * 10 + x",
+        "let x: int = 1; let g(y: int): int = { let f(): int = x; let x: int = 100; f() + y }; g(5)",
+        "let mut x: int = 1; let f(): int = x; x = 3; f()",
+        "let mut x: int = 1; let set(): int = x = 4; set(); x",
+    ];
+    let expects = [
+        Boolean(false),
+        Integer(2),
+        Integer(3),
+        Integer(12),
+        Integer(6),
+        Integer(3),
+        Integer(4),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_assign_chain_test() {
+    let inputs = [
+        "let mut a: int = 1; let mut b: int = 2; a = b = 3; a + b",
+        "let mut a: int = 1; let mut b: int = 2; a = b = a + b",
+    ];
+    let expects = [Integer(6), Integer(3)];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_logical_test() {
+    let inputs = [
+        "true && false",
+        "true || false",
+        "false || true && false",
+        "1 < 2 && 3 < 4",
+        "let mut x: int = 0; false && { x = 1; true }; x",
+        "let mut x: int = 0; true && { x = 1; true }; x",
+        "let mut x: int = 0; true || { x = 1; true }; x",
+        "let mut x: int = 0; false || { x = 1; true }; x",
+        // 右辺が評価されなければ型の誤りにも気付かない
+        "false && 1",
+    ];
+    let expects = [
+        Boolean(false),
+        Boolean(true),
+        Boolean(false),
+        Boolean(true),
+        Integer(0),
+        Integer(1),
+        Integer(0),
+        Integer(1),
+        Boolean(false),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_char_test() {
+    let inputs = [
+        "'a'",
+        r"'\n'",
+        "let c: char = 'a'; c + 2",
+        "'z' - 'a'",
+        "'a' + 255",
+        "'a' < 'b'",
+        "'a' == 'a'",
+    ];
+    let expects = [
+        Char(b'a'),
+        Char(b'\n'),
+        Char(b'c'),
+        Integer(25),
+        Char(b'`'),
+        Boolean(true),
+        Boolean(true),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
 #[test]
 fn eval_arithmetic_test() {
     let inputs = [
@@ -84,6 +417,76 @@ fn eval_arithmetic_test() {
     loop_assert_unwrap(inputs, expects);
 }
 
+#[test]
+fn eval_bitwise_test() {
+    let inputs = [
+        "17 % 5",
+        "-17 % 5",
+        "0xF0 & 0x3C",
+        "0xF0 | 0x0F",
+        "0xFF ^ 0x0F",
+        "1 << 4",
+        "-64 >> 2",
+        "1 << 65",
+        "~5",
+        "1 + 2 << 1 == 6",
+        "(-9223372036854775807 - 1) / -1",
+        "(-9223372036854775807 - 1) % -1",
+        "9223372036854775807 + 1",
+        "(-9223372036854775807 - 1) - 1",
+        "9223372036854775807 * 2",
+        "-(-9223372036854775807 - 1)",
+    ];
+    let expects = [
+        Integer(2),
+        Integer(-2),
+        Integer(0x30),
+        Integer(0xFF),
+        Integer(0xF0),
+        Integer(16),
+        Integer(-16),
+        Integer(2),
+        Integer(-6),
+        Boolean(true),
+        Integer(isize::MIN),
+        Integer(0),
+        Integer(isize::MIN),
+        Integer(isize::MAX),
+        Integer(-2),
+        Integer(isize::MIN),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
+#[test]
+fn eval_float_test() {
+    let inputs = [
+        "1.5",
+        "1.5 + 2.25",
+        "1.0 - 2.5",
+        "1.5 * 2.0",
+        "1.0 / 4.0",
+        "-1.5",
+        "0.1 + 0.2 == 0.3",
+        "1.0 < 2.0",
+        "let half(x: float): float = x / 2.0; half(5.0)",
+    ];
+    let expects = [
+        Float(1.5),
+        Float(3.75),
+        Float(-1.5),
+        Float(3.0),
+        Float(0.25),
+        Float(-1.5),
+        Boolean(false),
+        Boolean(true),
+        Float(2.5),
+    ];
+
+    loop_assert_unwrap(inputs, expects);
+}
+
 #[test]
 fn eval_let_expr_test() {
     let inputs = [
@@ -126,30 +529,43 @@ fn eval_let_expr_test() {
         Integer(10),
         Function {
             params: vec!["x".to_string()],
-            body: Expression::Ident(Ident {
+            body: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
-            }),
+                mutable: false,
+                span: Span::default(),
+            })),
+            env: captured(&["id"]),
         },
         Integer(10),
         Function {
             params: vec!["x".to_string(), "y".to_string()],
-            body: Expression::BinaryExpr(BinaryExpr {
+            body: Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Add,
                 lhs: Box::new(Expression::Ident(Ident {
                     name: "x".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
                 rhs: Box::new(Expression::Ident(Ident {
                     name: "y".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
-            }),
+                span: Span::default(),
+            })),
+            env: captured(&["add"]),
         },
         Integer(12),
         Function {
             params: vec![],
-            body: Expression::Integer(Integer { value: 1 }),
+            body: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            env: captured(&["one"]),
         },
         Integer(1),
         Integer(55),
@@ -176,9 +592,9 @@ fn eval_block_expr_test() {
 #[test]
 fn eval_assign_expr_test() {
     let inputs = [
-        "let x: int = 10; x = 20; x",
-        "let x: int = 10; { x = 20; x }",
-        "let x: int = 10; { x = 20; }; x",
+        "let mut x: int = 10; x = 20; x",
+        "let mut x: int = 10; { x = 20; x }",
+        "let mut x: int = 10; { x = 20; }; x",
     ];
     let expects = [Integer(20), Integer(20), Integer(20)];
 
@@ -289,3 +705,46 @@ fn eval_array_test() {
 
     loop_assert_unwrap(inputs, expects);
 }
+
+#[test]
+fn eval_error_span_test() {
+    let inputs = ["if 1 1", "let x: int = 1;\n-true"];
+    let expects = [(3, 4, 1, 4), (17, 21, 2, 2)];
+
+    for (input, expect) in inputs.into_iter().zip(expects) {
+        let program = Parser::new(input).parse_program().unwrap();
+        let span = Evaluator::new().eval(program).unwrap_err().span();
+
+        assert_eq!(expect, (span.start, span.end, span.line, span.column));
+    }
+}
+
+#[test]
+fn eval_fuel_test() {
+    let inputs = [
+        "loop {}",
+        "let f(n: int): int = f(n + 1); f(0)",
+        "let mut s: int = 0; for i in 0..10 { s = s + i; }; s",
+        "let f(n: int): int = if n == 0 { 0 } else { f(n - 1) + 1 }; f(255)",
+        "let f(n: int): int = if n == 0 { 0 } else { f(n - 1) + 1 }; f(256)",
+    ];
+    // 使い切った場合は`None`
+    let expects = [None, None, Some(45), Some(255), None];
+
+    for (input, expect) in inputs.into_iter().zip(expects) {
+        let program = Parser::new(input).parse_program().unwrap();
+        // 評価器の値はスレッドをまたげないので、スレッドの中で整数に直す
+        let result = thread::Builder::new()
+            .stack_size(FUELED_STACK_SIZE)
+            .spawn(move || match Evaluator::with_fuel(100_000).eval(program) {
+                Ok(Integer(value)) => Some(value),
+                Err(EvalError::OutOfFuel(_)) => None,
+                result => panic!("unexpected result: {:?}", result),
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(expect, result, "input: {}", input);
+    }
+}
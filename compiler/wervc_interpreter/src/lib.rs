@@ -1,13 +1,26 @@
 use wervc_eval::{error::EvalError, Evaluator};
+use wervc_lexer::error::LexError;
 use wervc_object::Object;
 use wervc_parser::parser::{error::ParserError, Parser};
 
 #[derive(Debug)]
 pub enum InterpreterError {
+    LexError(LexError),
     ParserError(ParserError),
+    /// 構文解析で見つかったすべてのエラー。字句エラーも含む
+    ParserErrors(Vec<InterpreterError>),
     EvalError(EvalError),
 }
 
+impl From<ParserError> for InterpreterError {
+    fn from(value: ParserError) -> Self {
+        match value {
+            ParserError::LexError(e) => InterpreterError::LexError(e),
+            e => InterpreterError::ParserError(e),
+        }
+    }
+}
+
 pub struct Interpreter {
     evaluator: Evaluator,
 }
@@ -27,9 +40,19 @@ impl Interpreter {
 
     pub fn run(&mut self, input: &str) -> Result<Object, InterpreterError> {
         let mut parser = Parser::new(input);
-        let program = parser
-            .parse_program()
-            .map_err(InterpreterError::ParserError)?;
+        let program = parser.parse_partial_program();
+
+        // 1つずつ直さなくて済むように、見つかったエラーをまとめて返す
+        if !parser.errors().is_empty() {
+            return Err(InterpreterError::ParserErrors(
+                parser
+                    .errors()
+                    .iter()
+                    .cloned()
+                    .map(InterpreterError::from)
+                    .collect(),
+            ));
+        }
 
         self.evaluator
             .eval(program)
@@ -0,0 +1,40 @@
+use crate::token::{Token, TokenKind};
+use wervc_span::Span;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LexError {
+    /// どのトークンの始まりにもならない文字
+    UnexpectedChar(char, Span),
+    UnterminatedComment(Span),
+    UnterminatedStr(Span),
+    UnterminatedChar(Span),
+}
+
+impl LexError {
+    /// 字句解析に失敗したことを表すトークンから、対応するエラーを生成する
+    pub fn from_token(token: &Token) -> Option<LexError> {
+        let span = token.span;
+
+        match token.kind {
+            TokenKind::Unknown => {
+                let ch = token.literal.chars().next().unwrap_or('\0');
+
+                Some(LexError::UnexpectedChar(ch, span))
+            }
+            TokenKind::UnterminatedComment => Some(LexError::UnterminatedComment(span)),
+            TokenKind::UnterminatedStr => Some(LexError::UnterminatedStr(span)),
+            TokenKind::UnterminatedChar => Some(LexError::UnterminatedChar(span)),
+            _ => None,
+        }
+    }
+
+    /// エラーの原因となったソースコード上の範囲を返す
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedChar(_, span)
+            | LexError::UnterminatedComment(span)
+            | LexError::UnterminatedStr(span)
+            | LexError::UnterminatedChar(span) => *span,
+        }
+    }
+}
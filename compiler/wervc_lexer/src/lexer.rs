@@ -1,16 +1,29 @@
-use crate::token::{
-    Token,
-    TokenKind::{self, *},
+use crate::{
+    error::LexError,
+    token::{
+        Token,
+        TokenKind::{self, *},
+    },
 };
+use unicode_xid::UnicodeXID;
+use wervc_span::Span;
 
 #[cfg(test)]
 mod test;
 
 pub struct Lexer {
     input: String,
+    /// 現在の文字の、入力の先頭からのバイト位置
     position: usize,
+    /// 次の文字のバイト位置
     read_position: usize,
     ch: char,
+    line: usize,
+    column: usize,
+    /// 空白やコメントもトークンとして返すかどうか
+    lossless: bool,
+    /// これまでに見つかった字句エラー
+    errors: Vec<LexError>,
 }
 
 impl Lexer {
@@ -20,6 +33,10 @@ impl Lexer {
             position: 0,
             read_position: 0,
             ch: '\0',
+            line: 1,
+            column: 0,
+            lossless: false,
+            errors: Vec::new(),
         };
 
         lexer.read_char();
@@ -27,86 +44,226 @@ impl Lexer {
         lexer
     }
 
+    /// 空白やコメントもトリビアトークンとして返すレキサーを生成する
+    ///
+    /// EOF以外のトークンのリテラルを順に連結すると、元の入力と完全に一致する
+    pub fn lossless(input: impl ToString) -> Lexer {
+        let mut lexer = Lexer::new(input);
+
+        lexer.lossless = true;
+
+        lexer
+    }
+
     fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = '\0';
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            self.ch = self.input.chars().nth(self.read_position).unwrap();
+            self.column += 1;
         }
 
         self.position = self.read_position;
-        self.read_position += 1;
+
+        // 入力の終端ではそれ以上読み進めない
+        match self.input[self.read_position..].chars().next() {
+            Some(ch) => {
+                self.ch = ch;
+                self.read_position += ch.len_utf8();
+            }
+            None => self.ch = '\0',
+        }
+    }
+
+    /// 入力の終端に達したか
+    ///
+    /// 終端では`ch`が`'\0'`になるが、入力に書かれたNUL文字と区別するために位置で判定する
+    fn is_eof(&self) -> bool {
+        self.position >= self.input.len()
     }
 
     fn peek_char(&self) -> char {
-        if self.read_position >= self.input.len() {
-            '\0'
-        } else {
-            self.input.chars().nth(self.read_position).unwrap()
-        }
+        self.input[self.read_position..]
+            .chars()
+            .next()
+            .unwrap_or('\0')
     }
 
     pub fn next_token(&mut self) -> Token {
-        self.eat_whitespace();
+        loop {
+            let (start, line, column) = (self.position, self.line, self.column);
+            let (kind, literal) = match (self.ch, self.peek_char()) {
+                _ if self.is_whitespace() => {
+                    self.eat_whitespace();
+                    (Whitespace, String::new())
+                }
+                ('/', '/') => {
+                    self.eat_line_comment();
+                    (LineComment, String::new())
+                }
+                ('/', '*') => {
+                    if self.eat_block_comment() {
+                        (BlockComment, String::new())
+                    } else {
+                        // 閉じられないまま入力の終端に達したブロックコメント
+                        (UnterminatedComment, String::new())
+                    }
+                }
+                _ => self.read_token(),
+            };
+
+            if kind.is_trivia() && !self.lossless {
+                continue;
+            }
+
+            let span = Span::new(start, self.position, line, column);
+            let literal = match kind {
+                Whitespace | LineComment | BlockComment | UnterminatedComment => {
+                    self.input[start..self.position].to_string()
+                }
+                _ => literal,
+            };
 
+            let token = Token::with_span(kind, literal, span);
+
+            if let Some(error) = LexError::from_token(&token) {
+                self.errors.push(error);
+            }
+
+            return token;
+        }
+    }
+
+    /// これまでに読み進めた範囲で見つかった字句エラーを返す
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    fn read_token(&mut self) -> (TokenKind, String) {
         let kind = match self.ch {
             _ if self.is_number() => {
-                let literal = self.read_number();
-
-                return Token::new(Number, literal);
+                return self.read_number();
             }
             _ if self.is_ident() => {
                 let literal = self.read_ident();
-                let kind = TokenKind::lookup_ident(&literal);
 
-                return Token::new(kind, literal);
+                return (TokenKind::lookup_ident(&literal), literal);
+            }
+            '"' => {
+                return match self.read_quoted('"') {
+                    (true, literal) => (Str, literal),
+                    (false, literal) => (UnterminatedStr, literal),
+                };
+            }
+            '\'' if self.starts_label() => {
+                self.read_char();
+
+                return (Label, format!("'{}", self.read_ident()));
+            }
+            '\'' => {
+                return match self.read_quoted('\'') {
+                    (true, literal) => (Char, literal),
+                    (false, literal) => (UnterminatedChar, literal),
+                };
             }
             '=' if self.peek_char() == '=' => {
                 self.read_char();
                 self.read_char();
-                return Token::new(Eq, "==");
+                return (Eq, "==".to_string());
+            }
+            '=' if self.peek_char() == '>' => {
+                self.read_char();
+                self.read_char();
+                return (FatArrow, "=>".to_string());
             }
             '=' => Assign,
             '<' if self.peek_char() == '=' => {
                 self.read_char();
                 self.read_char();
-                return Token::new(Le, "<=");
+                return (Le, "<=".to_string());
+            }
+            '<' if self.peek_char() == '<' => {
+                self.read_char();
+                self.read_char();
+                return (Shl, "<<".to_string());
             }
             '<' => Lt,
             '>' if self.peek_char() == '=' => {
                 self.read_char();
                 self.read_char();
-                return Token::new(Ge, ">=");
+                return (Ge, ">=".to_string());
+            }
+            '>' if self.peek_char() == '>' => {
+                self.read_char();
+                self.read_char();
+                return (Shr, ">>".to_string());
             }
             '>' => Gt,
             '!' if self.peek_char() == '=' => {
                 self.read_char();
                 self.read_char();
-                return Token::new(Ne, "!=");
+                return (Ne, "!=".to_string());
             }
             '!' => Bang,
             '+' => Plus,
+            '-' if self.peek_char() == '>' => {
+                self.read_char();
+                self.read_char();
+                return (Arrow, "->".to_string());
+            }
             '-' => Minus,
             '*' => Asterisk,
             '/' => Slash,
+            '%' => Percent,
+            '~' => Tilde,
+            '&' if self.peek_char() == '&' => {
+                self.read_char();
+                self.read_char();
+                return (AndAnd, "&&".to_string());
+            }
             '&' => Ampersand,
+            '|' if self.peek_char() == '|' => {
+                self.read_char();
+                self.read_char();
+                return (OrOr, "||".to_string());
+            }
+            '|' => Pipe,
+            '^' => Caret,
+            ':' if self.peek_char() == ':' => {
+                self.read_char();
+                self.read_char();
+                return (ColonColon, "::".to_string());
+            }
             ':' => Colon,
             ';' => SemiColon,
             ',' => Comma,
+            '.' if self.peek_char() == '.' => {
+                self.read_char();
+                self.read_char();
+
+                if self.ch == '=' {
+                    self.read_char();
+                    return (DotDotEq, "..=".to_string());
+                }
+
+                return (DotDot, "..".to_string());
+            }
+            '.' => Dot,
             '(' => LParen,
             ')' => RParen,
             '{' => LBrace,
             '}' => RBrace,
             '[' => LBracket,
             ']' => RBracket,
-            '\0' => EOF,
+            // 入力の終端ではそれ以上読み進めない
+            '\0' if self.is_eof() => return (EOF, "\0".to_string()),
             _ => Unknown,
         };
         let ch = self.ch;
 
         self.read_char();
 
-        Token::new(kind, ch)
+        (kind, ch.to_string())
     }
 
     fn eat_whitespace(&mut self) {
@@ -119,14 +276,108 @@ impl Lexer {
         self.ch.is_whitespace()
     }
 
-    fn read_number(&mut self) -> String {
+    /// `//`から行末までを読み飛ばす
+    fn eat_line_comment(&mut self) {
+        while self.ch != '\n' && !self.is_eof() {
+            self.read_char();
+        }
+    }
+
+    /// `/*`から対応する`*/`までを読み飛ばす。ブロックコメントは入れ子にできる
+    ///
+    /// 対応する`*/`が見つからずに入力の終端に達した場合はfalseを返す
+    fn eat_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+
+        loop {
+            match (self.ch, self.peek_char()) {
+                _ if self.is_eof() => return false,
+                ('/', '*') => {
+                    self.read_char();
+                    self.read_char();
+                    depth += 1;
+                }
+                ('*', '/') => {
+                    self.read_char();
+                    self.read_char();
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return true;
+                    }
+                }
+                _ => self.read_char(),
+            }
+        }
+    }
+
+    /// `quote`で囲まれたリテラルを、両端の`quote`を含めて読み取る
+    ///
+    /// エスケープシーケンスはそのまま残し、解釈はパーサーに任せる
+    /// 閉じる`quote`が見つからずに入力の終端に達した場合は、最初の要素がfalseになる
+    fn read_quoted(&mut self, quote: char) -> (bool, String) {
         let position = self.position;
 
-        while self.is_number() {
+        self.read_char();
+
+        let terminated = loop {
+            match self.ch {
+                _ if self.is_eof() => break false,
+                '\\' => {
+                    self.read_char();
+
+                    if !self.is_eof() {
+                        self.read_char();
+                    }
+                }
+                ch if ch == quote => {
+                    self.read_char();
+                    break true;
+                }
+                _ => self.read_char(),
+            }
+        };
+
+        (terminated, self.input[position..self.position].to_string())
+    }
+
+    /// 数値リテラルを読み取る
+    ///
+    /// `0x`・`0o`・`0b`の接頭辞や`_`による区切りを含めて読み取り、解釈はパーサーに任せる
+    /// 整数部の直後に`.`と数字が続く場合は浮動小数点数リテラルとして、指数部まで読み取る
+    fn read_number(&mut self) -> (TokenKind, String) {
+        let position = self.position;
+
+        self.eat_number_chars();
+
+        // `0..n`のような範囲と区別するため、`.`の直後が数字の場合のみ小数部とみなす
+        if self.ch != '.' || !self.peek_char().is_ascii_digit() {
+            return (Number, self.input[position..self.position].to_string());
+        }
+
+        self.read_char();
+
+        while self.is_number() || self.ch == '_' {
             self.read_char();
         }
 
-        self.input[position..self.position].to_string()
+        if matches!(self.ch, 'e' | 'E') {
+            self.read_char();
+
+            if matches!(self.ch, '+' | '-') {
+                self.read_char();
+            }
+        }
+
+        self.eat_number_chars();
+
+        (Float, self.input[position..self.position].to_string())
+    }
+
+    fn eat_number_chars(&mut self) {
+        while self.ch.is_ascii_alphanumeric() || self.ch == '_' {
+            self.read_char();
+        }
     }
 
     fn is_number(&self) -> bool {
@@ -136,14 +387,44 @@ impl Lexer {
     fn read_ident(&mut self) -> String {
         let position = self.position;
 
-        while self.is_number() || self.is_ident() {
+        while self.ch.is_xid_continue() {
             self.read_char();
         }
 
         self.input[position..self.position].to_string()
     }
 
+    /// `'outer`のようなラベルの始まりか。`'a'`のように識別子の後に`'`が続く場合は文字リテラルとみなす
+    fn starts_label(&self) -> bool {
+        let mut chars = self.input[self.read_position..].chars();
+
+        if !chars
+            .next()
+            .is_some_and(|ch| ch.is_xid_start() || ch == '_')
+        {
+            return false;
+        }
+
+        chars.find(|ch| !ch.is_xid_continue()) != Some('\'')
+    }
+
+    /// 識別子の先頭になれる文字か。Unicodeの XID_Start と`_`を受け付ける
     fn is_ident(&self) -> bool {
-        self.ch.is_ascii_alphabetic() || self.ch == '_'
+        self.ch.is_xid_start() || self.ch == '_'
+    }
+}
+
+/// EOFに達するまでトークンを返すイテレータ。EOFトークン自体は返さない
+impl Iterator for Lexer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.next_token();
+
+        if token.kind == EOF {
+            None
+        } else {
+            Some(token)
+        }
     }
 }
@@ -1,28 +1,56 @@
 use crate::{
+    error::LexError,
     lexer::Lexer,
-    token::{
-        Token,
-        TokenKind::{self, *},
-    },
+    token::TokenKind::{self, *},
 };
+use wervc_span::Span;
 
 fn loop_assert<const N: usize>(inputs: [impl ToString; N], expects: [Vec<(TokenKind, &str)>; N]) {
     for (input, expects) in inputs.into_iter().zip(expects) {
         let mut lexer = Lexer::new(input);
 
+        // 位置はlexer_span_testで確かめるので、種類と文字列だけを比べる
         for (kind, literal) in expects {
-            assert_eq!(Token::new(kind, literal), lexer.next_token());
+            let token = lexer.next_token();
+
+            assert_eq!((kind, literal), (token.kind, token.literal.as_str()));
         }
     }
 }
 
 #[test]
 fn lexer_number_test() {
-    let inputs = ["0", "42", "1234567890;"];
+    let inputs = [
+        "0",
+        "42",
+        "1234567890;",
+        "0xFF",
+        "0o755",
+        "0b1010",
+        "1_000_000",
+    ];
     let expects = [
         vec![(Number, "0"), (EOF, "\0")],
         vec![(Number, "42"), (EOF, "\0")],
         vec![(Number, "1234567890"), (SemiColon, ";"), (EOF, "\0")],
+        vec![(Number, "0xFF"), (EOF, "\0")],
+        vec![(Number, "0o755"), (EOF, "\0")],
+        vec![(Number, "0b1010"), (EOF, "\0")],
+        vec![(Number, "1_000_000"), (EOF, "\0")],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_float_test() {
+    let inputs = ["1.5", "0.25e-3", "1_000.0E10", "1.x", "0..5"];
+    let expects = [
+        vec![(Float, "1.5"), (EOF, "\0")],
+        vec![(Float, "0.25e-3"), (EOF, "\0")],
+        vec![(Float, "1_000.0E10"), (EOF, "\0")],
+        vec![(Number, "1"), (Dot, "."), (Ident, "x"), (EOF, "\0")],
+        vec![(Number, "0"), (DotDot, ".."), (Number, "5"), (EOF, "\0")],
     ];
 
     loop_assert(inputs, expects);
@@ -49,6 +77,55 @@ fn lexer_arithmetic_test() {
     loop_assert(inputs, expects);
 }
 
+#[test]
+fn lexer_bitwise_test() {
+    let inputs = [
+        "a % b & c | d ^ ~e",
+        "1 << 2 >> 3 <= 4 >= 5",
+        "a && b || c & &d",
+    ];
+    let expects = [
+        vec![
+            (Ident, "a"),
+            (Percent, "%"),
+            (Ident, "b"),
+            (Ampersand, "&"),
+            (Ident, "c"),
+            (Pipe, "|"),
+            (Ident, "d"),
+            (Caret, "^"),
+            (Tilde, "~"),
+            (Ident, "e"),
+            (EOF, "\0"),
+        ],
+        vec![
+            (Number, "1"),
+            (Shl, "<<"),
+            (Number, "2"),
+            (Shr, ">>"),
+            (Number, "3"),
+            (Le, "<="),
+            (Number, "4"),
+            (Ge, ">="),
+            (Number, "5"),
+            (EOF, "\0"),
+        ],
+        vec![
+            (Ident, "a"),
+            (AndAnd, "&&"),
+            (Ident, "b"),
+            (OrOr, "||"),
+            (Ident, "c"),
+            (Ampersand, "&"),
+            (Ampersand, "&"),
+            (Ident, "d"),
+            (EOF, "\0"),
+        ],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
 #[test]
 fn lexer_let_expr_test() {
     let inputs = ["let x: int = 5;", "let y = 10"];
@@ -181,6 +258,225 @@ fn lexer_relation_test() {
     loop_assert(inputs, expects);
 }
 
+#[test]
+fn lexer_while_test() {
+    let inputs = ["while x { break; continue }"];
+    let expects = [vec![
+        (While, "while"),
+        (Ident, "x"),
+        (LBrace, "{"),
+        (Break, "break"),
+        (SemiColon, ";"),
+        (Continue, "continue"),
+        (RBrace, "}"),
+        (EOF, "\0"),
+    ]];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_for_test() {
+    let inputs = ["for i in 0..10 { }", "for i in 1..=n {}", "1.5..2"];
+    let expects = [
+        vec![
+            (For, "for"),
+            (Ident, "i"),
+            (In, "in"),
+            (Number, "0"),
+            (DotDot, ".."),
+            (Number, "10"),
+            (LBrace, "{"),
+            (RBrace, "}"),
+            (EOF, "\0"),
+        ],
+        vec![
+            (For, "for"),
+            (Ident, "i"),
+            (In, "in"),
+            (Number, "1"),
+            (DotDotEq, "..="),
+            (Ident, "n"),
+            (LBrace, "{"),
+            (RBrace, "}"),
+            (EOF, "\0"),
+        ],
+        vec![(Float, "1.5"), (DotDot, ".."), (Number, "2"), (EOF, "\0")],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_loop_test() {
+    let inputs = ["'outer: loop { break 'outer 'a' }", "continue 'a_1;"];
+    let expects = [
+        vec![
+            (Label, "'outer"),
+            (Colon, ":"),
+            (Loop, "loop"),
+            (LBrace, "{"),
+            (Break, "break"),
+            (Label, "'outer"),
+            (Char, "'a'"),
+            (RBrace, "}"),
+            (EOF, "\0"),
+        ],
+        vec![
+            (Continue, "continue"),
+            (Label, "'a_1"),
+            (SemiColon, ";"),
+            (EOF, "\0"),
+        ],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_lambda_test() {
+    let inputs = ["fn(x: int): int => x - 1", "(int) -> int"];
+    let expects = [
+        vec![
+            (Fn, "fn"),
+            (LParen, "("),
+            (Ident, "x"),
+            (Colon, ":"),
+            (Ident, "int"),
+            (RParen, ")"),
+            (Colon, ":"),
+            (Ident, "int"),
+            (FatArrow, "=>"),
+            (Ident, "x"),
+            (Minus, "-"),
+            (Number, "1"),
+            (EOF, "\0"),
+        ],
+        vec![
+            (LParen, "("),
+            (Ident, "int"),
+            (RParen, ")"),
+            (Arrow, "->"),
+            (Ident, "int"),
+            (EOF, "\0"),
+        ],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_struct_test() {
+    let inputs = ["struct P { x: int }", "p.x = 1"];
+    let expects = [
+        vec![
+            (Struct, "struct"),
+            (Ident, "P"),
+            (LBrace, "{"),
+            (Ident, "x"),
+            (Colon, ":"),
+            (Ident, "int"),
+            (RBrace, "}"),
+            (EOF, "\0"),
+        ],
+        vec![
+            (Ident, "p"),
+            (Dot, "."),
+            (Ident, "x"),
+            (Assign, "="),
+            (Number, "1"),
+            (EOF, "\0"),
+        ],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_enum_test() {
+    let inputs = ["enum O { Some(int) }", "match o { O::Some(_) => 1 }"];
+    let expects = [
+        vec![
+            (Enum, "enum"),
+            (Ident, "O"),
+            (LBrace, "{"),
+            (Ident, "Some"),
+            (LParen, "("),
+            (Ident, "int"),
+            (RParen, ")"),
+            (RBrace, "}"),
+            (EOF, "\0"),
+        ],
+        vec![
+            (Match, "match"),
+            (Ident, "o"),
+            (LBrace, "{"),
+            (Ident, "O"),
+            (ColonColon, "::"),
+            (Ident, "Some"),
+            (LParen, "("),
+            (Ident, "_"),
+            (RParen, ")"),
+            (FatArrow, "=>"),
+            (Number, "1"),
+            (RBrace, "}"),
+            (EOF, "\0"),
+        ],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_type_alias_test() {
+    let inputs = ["type Index = int;"];
+    let expects = [vec![
+        (Type, "type"),
+        (Ident, "Index"),
+        (Assign, "="),
+        (Ident, "int"),
+        (SemiColon, ";"),
+        (EOF, "\0"),
+    ]];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_mut_test() {
+    let inputs = ["let mut x: int = 1;"];
+    let expects = [vec![
+        (Let, "let"),
+        (Mut, "mut"),
+        (Ident, "x"),
+        (Colon, ":"),
+        (Ident, "int"),
+        (Assign, "="),
+        (Number, "1"),
+        (SemiColon, ";"),
+        (EOF, "\0"),
+    ]];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_const_test() {
+    let inputs = ["const N: int = 4;"];
+    let expects = [vec![
+        (Const, "const"),
+        (Ident, "N"),
+        (Colon, ":"),
+        (Ident, "int"),
+        (Assign, "="),
+        (Number, "4"),
+        (SemiColon, ";"),
+        (EOF, "\0"),
+    ]];
+
+    loop_assert(inputs, expects);
+}
+
 #[test]
 fn lexer_return_test() {
     let inputs = ["return 10;", "return 10 + 20;"];
@@ -235,3 +531,276 @@ fn lexer_array_test() {
 
     loop_assert(inputs, expects);
 }
+
+#[test]
+fn lexer_unicode_test() {
+    let inputs = [
+        "let 変数: int = 1;",
+        "_café1 + é",
+        r#""héllo, 世界""#,
+        "'あ' @ €",
+    ];
+    let expects = [
+        vec![
+            (Let, "let"),
+            (Ident, "変数"),
+            (Colon, ":"),
+            (Ident, "int"),
+            (Assign, "="),
+            (Number, "1"),
+            (SemiColon, ";"),
+            (EOF, "\0"),
+        ],
+        vec![(Ident, "_café1"), (Plus, "+"), (Ident, "é"), (EOF, "\0")],
+        vec![(Str, r#""héllo, 世界""#), (EOF, "\0")],
+        vec![(Char, "'あ'"), (Unknown, "@"), (Unknown, "€"), (EOF, "\0")],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_large_input_test() {
+    // 1文字ごとに入力を先頭から走査すると、この大きさの入力では終わらなくなる
+    let lines = 50_000;
+    let mut lexer = Lexer::new("let 値: int = 1; // コメント\n".repeat(lines));
+    let mut count = 0;
+
+    while lexer.next_token().kind != EOF {
+        count += 1;
+    }
+
+    assert_eq!(count, lines * 7);
+}
+
+#[test]
+fn lexer_span_test() {
+    let inputs = ["let x: int = 10;", "foo\n  bar ==\n1", "\"あい\" 変数"];
+    let expects = [
+        vec![
+            (0, 3, 1, 1),
+            (4, 5, 1, 5),
+            (5, 6, 1, 6),
+            (7, 10, 1, 8),
+            (11, 12, 1, 12),
+            (13, 15, 1, 14),
+            (15, 16, 1, 16),
+            (16, 16, 1, 17),
+        ],
+        vec![
+            (0, 3, 1, 1),
+            (6, 9, 2, 3),
+            (10, 12, 2, 7),
+            (13, 14, 3, 1),
+            (14, 14, 3, 2),
+        ],
+        // 位置はバイト単位、列は文字単位で数える
+        vec![(0, 8, 1, 1), (9, 15, 1, 6), (15, 15, 1, 8)],
+    ];
+
+    for (input, expects) in inputs.into_iter().zip(expects) {
+        let mut lexer = Lexer::new(input);
+
+        for expect in expects {
+            let Span {
+                start,
+                end,
+                line,
+                column,
+            } = lexer.next_token().span;
+
+            assert_eq!(expect, (start, end, line, column), "input: {:?}", input);
+        }
+    }
+}
+
+#[test]
+fn lexer_comment_test() {
+    let inputs = [
+        "1 // comment\n+ 2",
+        "// only comment",
+        "1 /* block */ + /* multi\nline */ 2",
+        "/* outer /* inner */ still comment */ 3",
+        "4 / 2 // 4 / 2",
+        "1 /* unterminated",
+        "/* /* nested */",
+    ];
+    let expects = [
+        vec![(Number, "1"), (Plus, "+"), (Number, "2"), (EOF, "\0")],
+        vec![(EOF, "\0")],
+        vec![(Number, "1"), (Plus, "+"), (Number, "2"), (EOF, "\0")],
+        vec![(Number, "3"), (EOF, "\0")],
+        vec![(Number, "4"), (Slash, "/"), (Number, "2"), (EOF, "\0")],
+        vec![
+            (Number, "1"),
+            (UnterminatedComment, "/* unterminated"),
+            (EOF, "\0"),
+        ],
+        vec![(UnterminatedComment, "/* /* nested */"), (EOF, "\0")],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_iterator_test() {
+    let tokens = Lexer::new("let x: int = 1; // comment")
+        .map(|token| (token.kind, token.literal))
+        .collect::<Vec<_>>();
+    let expects = [
+        (Let, "let"),
+        (Ident, "x"),
+        (Colon, ":"),
+        (Ident, "int"),
+        (Assign, "="),
+        (Number, "1"),
+        (SemiColon, ";"),
+    ]
+    .map(|(kind, literal)| (kind, literal.to_string()));
+
+    assert_eq!(tokens, expects);
+}
+
+#[test]
+fn lexer_lossless_test() {
+    let input = "let x: int = 1 // line\n  /* block /* nested */ */ + 2;\t'a' @";
+    let tokens = Lexer::lossless(input)
+        .map(|token| (token.kind, token.literal))
+        .collect::<Vec<_>>();
+    let expects = [
+        (Let, "let"),
+        (Whitespace, " "),
+        (Ident, "x"),
+        (Colon, ":"),
+        (Whitespace, " "),
+        (Ident, "int"),
+        (Whitespace, " "),
+        (Assign, "="),
+        (Whitespace, " "),
+        (Number, "1"),
+        (Whitespace, " "),
+        (LineComment, "// line"),
+        (Whitespace, "\n  "),
+        (BlockComment, "/* block /* nested */ */"),
+        (Whitespace, " "),
+        (Plus, "+"),
+        (Whitespace, " "),
+        (Number, "2"),
+        (SemiColon, ";"),
+        (Whitespace, "\t"),
+        (Char, "'a'"),
+        (Whitespace, " "),
+        (Unknown, "@"),
+    ]
+    .map(|(kind, literal)| (kind, literal.to_string()));
+
+    assert_eq!(tokens, expects);
+}
+
+#[test]
+fn lexer_lossless_roundtrip_test() {
+    let inputs = [
+        "",
+        "  \n\t ",
+        "let 変数: int = 0x_FF; /* unterminated",
+        "let f(x: float): float = x * 1.5e3; // 末尾のコメント",
+        r#""abc\"def" 'x' "unterminated"#,
+        "1 \0 2 // \0 comment\n\"a\0b\" /* \0 */",
+    ];
+
+    for input in inputs {
+        let tokens = Lexer::lossless(input).collect::<Vec<_>>();
+        let output = tokens
+            .iter()
+            .map(|token| token.literal.as_str())
+            .collect::<String>();
+
+        assert_eq!(output, input);
+
+        for token in tokens {
+            assert_eq!(token.literal, input[token.span.start..token.span.end]);
+        }
+    }
+}
+
+#[test]
+fn lexer_error_test() {
+    let inputs = ["1 @ 2", "let $x = '1", "/* /* */", "ok # \"str", "1 \0 2"];
+    let expects = [
+        vec![LexError::UnexpectedChar('@', Span::new(2, 3, 1, 3))],
+        vec![
+            LexError::UnexpectedChar('$', Span::new(4, 5, 1, 5)),
+            LexError::UnterminatedChar(Span::new(9, 11, 1, 10)),
+        ],
+        vec![LexError::UnterminatedComment(Span::new(0, 8, 1, 1))],
+        vec![
+            LexError::UnexpectedChar('#', Span::new(3, 4, 1, 4)),
+            LexError::UnterminatedStr(Span::new(5, 9, 1, 6)),
+        ],
+        // 入力に書かれたNUL文字は終端ではなく、不明な文字として扱う
+        vec![LexError::UnexpectedChar('\0', Span::new(2, 3, 1, 3))],
+    ];
+
+    for (input, expects) in inputs.into_iter().zip(expects) {
+        let mut lexer = Lexer::new(input);
+
+        while lexer.next_token().kind != EOF {}
+
+        assert_eq!(lexer.errors(), expects);
+
+        for (error, expect) in lexer.errors().iter().zip(expects) {
+            assert_eq!(error.span().start, expect.span().start);
+            assert_eq!(error.span().end, expect.span().end);
+        }
+    }
+}
+
+#[test]
+fn lexer_str_test() {
+    let inputs = [
+        r#""hello""#,
+        r#"println("a\"b\n", "")"#,
+        r#"let s: str = "multi
+line";"#,
+        r#""unterminated\""#,
+    ];
+    let expects = [
+        vec![(Str, r#""hello""#), (EOF, "\0")],
+        vec![
+            (Ident, "println"),
+            (LParen, "("),
+            (Str, r#""a\"b\n""#),
+            (Comma, ","),
+            (Str, r#""""#),
+            (RParen, ")"),
+            (EOF, "\0"),
+        ],
+        vec![
+            (Let, "let"),
+            (Ident, "s"),
+            (Colon, ":"),
+            (Ident, "str"),
+            (Assign, "="),
+            (Str, "\"multi\nline\""),
+            (SemiColon, ";"),
+            (EOF, "\0"),
+        ],
+        vec![(UnterminatedStr, r#""unterminated\""#), (EOF, "\0")],
+    ];
+
+    loop_assert(inputs, expects);
+}
+
+#[test]
+fn lexer_char_test() {
+    let inputs = [r"'a'", r"c == '\n'", r"'\''", r"'1", r"'x"];
+    let expects = [
+        vec![(Char, "'a'"), (EOF, "\0")],
+        vec![(Ident, "c"), (Eq, "=="), (Char, r"'\n'"), (EOF, "\0")],
+        vec![(Char, r"'\''"), (EOF, "\0")],
+        vec![(UnterminatedChar, "'1"), (EOF, "\0")],
+        vec![(Label, "'x"), (EOF, "\0")],
+    ];
+
+    loop_assert(inputs, expects);
+}
@@ -1,13 +1,25 @@
+use wervc_span::Span;
+
 #[derive(Debug, PartialEq, Eq, Default, Clone)]
 pub struct Token {
     pub kind: TokenKind,
     pub literal: String,
+    pub span: Span,
 }
 impl Token {
     pub fn new(kind: TokenKind, literal: impl ToString) -> Token {
         Token {
             kind,
             literal: literal.to_string(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn with_span(kind: TokenKind, literal: impl ToString, span: Span) -> Token {
+        Token {
+            kind,
+            literal: literal.to_string(),
+            span,
         }
     }
 }
@@ -15,19 +27,41 @@ impl Token {
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub enum TokenKind {
     Unknown,
+    UnterminatedComment,
+    UnterminatedStr,
+    UnterminatedChar,
     #[default]
     EOF,
 
+    // 空白やコメントなど、構文上は意味を持たないトリビア
+    Whitespace,
+    LineComment,
+    BlockComment,
+
     Number,
+    Float,
+    Str,
+    Char,
     Ident,
+    Label,
 
     Assign,
     Plus,
     Minus,
     Asterisk,
     Slash,
+    Percent,
     Bang,
+    Tilde,
     Ampersand,
+    Pipe,
+    Caret,
+    Shl,
+    Shr,
+    AndAnd,
+    OrOr,
+    Arrow,
+    FatArrow,
 
     Eq,
     Ne,
@@ -37,8 +71,12 @@ pub enum TokenKind {
     Ge,
 
     Colon,
+    ColonColon,
     SemiColon,
     Comma,
+    Dot,
+    DotDot,
+    DotDotEq,
 
     LParen,
     RParen,
@@ -53,6 +91,19 @@ pub enum TokenKind {
     True,
     False,
     Return,
+    While,
+    Break,
+    Continue,
+    For,
+    In,
+    Loop,
+    Fn,
+    Struct,
+    Enum,
+    Match,
+    Type,
+    Mut,
+    Const,
 }
 
 impl TokenKind {
@@ -64,7 +115,39 @@ impl TokenKind {
             "true" => Self::True,
             "false" => Self::False,
             "return" => Self::Return,
+            "while" => Self::While,
+            "break" => Self::Break,
+            "continue" => Self::Continue,
+            "for" => Self::For,
+            "in" => Self::In,
+            "loop" => Self::Loop,
+            "fn" => Self::Fn,
+            "struct" => Self::Struct,
+            "enum" => Self::Enum,
+            "match" => Self::Match,
+            "type" => Self::Type,
+            "mut" => Self::Mut,
+            "const" => Self::Const,
             _ => Self::Ident,
         }
     }
+
+    /// 空白やコメントのように、構文解析では読み飛ばされるトークンか
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            Self::Whitespace | Self::LineComment | Self::BlockComment
+        )
+    }
+
+    /// 字句解析に失敗したことを表すトークンか
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Self::Unknown
+                | Self::UnterminatedComment
+                | Self::UnterminatedStr
+                | Self::UnterminatedChar
+        )
+    }
 }
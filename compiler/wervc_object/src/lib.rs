@@ -1,21 +1,94 @@
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 use wervc_ast::Expression;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// 変数の値を格納する領域
+///
+/// `let`のたびに新しい領域を確保するので、シャドーイングされた変数の値も残り続ける
+pub type Slot = Rc<RefCell<Object>>;
+
+/// 関数が定義された時点で見えていた変数の領域
+#[derive(Clone, Default)]
+pub struct Captured(pub HashMap<String, CapturedSlot>);
+
+/// 関数が捕捉した変数
+#[derive(Clone)]
+pub struct CapturedSlot {
+    pub slot: Slot,
+    /// 外側の関数の中で定義された変数かどうか
+    ///
+    /// コンパイル後はその関数のスタック上にあるので、関数の定義を除いて参照できない
+    pub outer_local: bool,
+}
+
+impl PartialEq for Captured {
+    // 再帰関数は自分自身を捕捉しているので、中身をたどらずに等しいとみなす
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Captured {
+    // 再帰関数は自分自身を捕捉しているので、変数名だけを表示する
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Object {
     Integer(isize),
+    Float(f64),
     Boolean(bool),
+    Str(String),
+    Char(u8),
     Function {
         params: Vec<String>,
-        body: Expression,
+        body: Box<Expression>,
+        env: Captured,
     },
     Array(Vec<Object>),
+    /// 宣言された順の (フィールド名, 値)
+    Struct {
+        name: String,
+        fields: Vec<(String, Object)>,
+    },
+    Tuple(Vec<Object>),
+    /// 列挙型のバリアントと、そのペイロードの値
+    Enum {
+        name: String,
+        variant: String,
+        values: Vec<Object>,
+    },
     Return(Box<Object>),
+    /// `label`が`None`なら最も内側のループを抜ける
+    Break {
+        label: Option<String>,
+        value: Box<Object>,
+    },
+    Continue {
+        label: Option<String>,
+    },
     Pointer(Box<Object>),
     Unit,
 }
 impl Object {
-    pub fn is_return(&self) -> bool {
-        matches!(self, Self::Return(_))
+    /// 構造体のフィールドか、番号で指定したタプルの要素を返す
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Object> {
+        match self {
+            Self::Struct { fields, .. } => fields
+                .iter_mut()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            Self::Tuple(values) => values.get_mut(name.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+    /// `return`や`break`のように、評価を打ち切って外側へ抜けることを表す値か
+    pub fn is_escaping(&self) -> bool {
+        matches!(
+            self,
+            Self::Return(_) | Self::Break { .. } | Self::Continue { .. }
+        )
     }
 }
 
@@ -26,7 +99,10 @@ impl std::fmt::Display for Object {
             "{}",
             match self {
                 Self::Integer(i) => i.to_string(),
+                Self::Float(f) => format!("{:?}", f),
                 Self::Boolean(b) => b.to_string(),
+                Self::Str(s) => s.clone(),
+                Self::Char(c) => (*c as char).to_string(),
                 Self::Function { .. } => "[Function]".to_string(),
                 Self::Array(values) => format!(
                     "[{}]",
@@ -36,7 +112,46 @@ impl std::fmt::Display for Object {
                         .collect::<Vec<String>>()
                         .join(", ")
                 ),
+                Self::Tuple(values) if values.len() == 1 => format!("({},)", values[0]),
+                Self::Tuple(values) => format!(
+                    "({})",
+                    values
+                        .iter()
+                        .map(|o| o.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                Self::Struct { name, fields } => format!(
+                    "{} {{ {} }}",
+                    name,
+                    fields
+                        .iter()
+                        .map(|(field, value)| format!("{}: {}", field, value))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                Self::Enum {
+                    name,
+                    variant,
+                    values,
+                } if values.is_empty() => format!("{}::{}", name, variant),
+                Self::Enum {
+                    name,
+                    variant,
+                    values,
+                } => format!(
+                    "{}::{}({})",
+                    name,
+                    variant,
+                    values
+                        .iter()
+                        .map(|o| o.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
                 Self::Return(o) => o.to_string(),
+                Self::Break { .. } => "break".to_string(),
+                Self::Continue { .. } => "continue".to_string(),
                 Self::Pointer(p) => format!("{:p}", p),
                 Self::Unit => "()".to_string(),
             }
@@ -3,15 +3,20 @@ pub mod error;
 mod test;
 
 use self::error::ParserError;
+use std::{panic, thread};
 use wervc_ast::{
     ty::{Type, TypeKind},
-    Array, BinaryExpr, BinaryExprKind, BlockExpr, Boolean, CallExpr,
+    Array, BinaryExpr, BinaryExprKind, BlockExpr, Boolean, BreakExpr, CallExpr, Char, ConstExpr,
+    ContinueExpr, EnumDefExpr, EnumVariantExpr,
     Expression::{self},
-    FunctionDefExpr, Ident, IfExpr, Integer, LetExpr, Node, Program, ReturnExpr,
+    FieldExpr, Float, ForExpr, FunctionDefExpr, Ident, IfExpr, Integer, Iterable, LambdaExpr,
+    LetExpr, LoopExpr, MatchArm, MatchExpr, Node, Pattern, Program, ReturnExpr,
     Statement::{self},
-    UnaryExpr, UnaryExprKind,
+    Str, StructDefExpr, StructLiteral, TupleExpr, TypeAliasExpr, UnaryExpr, UnaryExprKind,
+    WhileExpr,
 };
 use wervc_environment::Environment;
+use wervc_eval::{Evaluator, FUELED_STACK_SIZE};
 use wervc_lexer::{
     lexer::Lexer,
     token::{
@@ -19,22 +24,162 @@ use wervc_lexer::{
         TokenKind::{self, *},
     },
 };
+use wervc_object::Object;
+use wervc_span::Span;
+
+/// 定数の初期値を評価するときに、評価できる式の数
+///
+/// 終わらない初期値でコンパイルが止まらないようにする
+const CONST_EVAL_FUEL: usize = 100_000;
+
+/// 定数の値
+///
+/// リテラルで書ける値だけを持ち、初期値を評価したスレッドから受け取れるようにする
+#[derive(Debug, Clone)]
+enum ConstValue {
+    Integer(isize),
+    Float(f64),
+    Boolean(bool),
+    Str(String),
+    Char(u8),
+}
+
+impl ConstValue {
+    fn from_object(object: Object) -> Option<ConstValue> {
+        Some(match object {
+            Object::Integer(value) => ConstValue::Integer(value),
+            Object::Float(value) => ConstValue::Float(value),
+            Object::Boolean(value) => ConstValue::Boolean(value),
+            Object::Str(value) => ConstValue::Str(value),
+            Object::Char(value) => ConstValue::Char(value),
+            _ => return None,
+        })
+    }
+
+    fn ty(&self) -> TypeKind {
+        match self {
+            ConstValue::Integer(_) => TypeKind::Int,
+            ConstValue::Float(_) => TypeKind::Float,
+            ConstValue::Boolean(_) => TypeKind::Bool,
+            ConstValue::Str(_) => TypeKind::Str,
+            ConstValue::Char(_) => TypeKind::Char,
+        }
+    }
+
+    /// `span`に書かれたリテラルとして返す
+    fn to_literal(&self, span: Span) -> Expression {
+        match self {
+            ConstValue::Integer(value) => Expression::Integer(Integer {
+                value: *value,
+                span,
+            }),
+            ConstValue::Float(value) => Expression::Float(Float {
+                value: *value,
+                span,
+            }),
+            ConstValue::Boolean(value) => Expression::Boolean(Boolean {
+                value: *value,
+                span,
+            }),
+            ConstValue::Str(value) => Expression::Str(Str {
+                value: value.clone(),
+                span,
+            }),
+            ConstValue::Char(value) => Expression::Char(Char {
+                value: *value,
+                span,
+            }),
+        }
+    }
+}
 
 pub struct Parser {
     lexer: Lexer,
     cur_token: Token,
+    /// 直前に読み進めたトークンの位置
+    prev_span: Span,
     local_vars: Environment<String, Ident>,
+    /// 宣言された構造体や列挙型の型。変数と同じスコープに従う
+    types: Environment<String, Type>,
+    /// 宣言された定数の値。変数も`None`として登録し、同じ名前の外側の定数を隠す
+    consts: Environment<String, Option<ConstValue>>,
+    /// 解析中の式を囲んでいるループのラベル。内側のループほど後ろに並ぶ
+    loop_labels: Vec<Option<String>>,
+    errors: Vec<ParserError>,
 }
 
 type PResult<T> = Result<T, ParserError>;
 
+/// 二項演算子の結合の向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Assoc {
+    Left,
+    Right,
+}
+
+struct BinaryOperator {
+    token: TokenKind,
+    kind: BinaryExprKind,
+    /// 大きいほど強く結合する
+    prec: u8,
+    assoc: Assoc,
+}
+
+impl BinaryOperator {
+    const fn new(token: TokenKind, kind: BinaryExprKind, prec: u8, assoc: Assoc) -> Self {
+        BinaryOperator {
+            token,
+            kind,
+            prec,
+            assoc,
+        }
+    }
+}
+
+/// 二項演算子の優先順位と結合の向きの表
+const BINARY_OPERATORS: &[BinaryOperator] = &[
+    BinaryOperator::new(Assign, BinaryExprKind::Assign, 1, Assoc::Right),
+    BinaryOperator::new(OrOr, BinaryExprKind::Or, 2, Assoc::Left),
+    BinaryOperator::new(AndAnd, BinaryExprKind::And, 3, Assoc::Left),
+    BinaryOperator::new(Eq, BinaryExprKind::Eq, 4, Assoc::Left),
+    BinaryOperator::new(Ne, BinaryExprKind::Ne, 4, Assoc::Left),
+    BinaryOperator::new(Lt, BinaryExprKind::Lt, 4, Assoc::Left),
+    BinaryOperator::new(Le, BinaryExprKind::Le, 4, Assoc::Left),
+    BinaryOperator::new(Gt, BinaryExprKind::Gt, 4, Assoc::Left),
+    BinaryOperator::new(Ge, BinaryExprKind::Ge, 4, Assoc::Left),
+    BinaryOperator::new(Pipe, BinaryExprKind::BitOr, 5, Assoc::Left),
+    BinaryOperator::new(Caret, BinaryExprKind::BitXor, 6, Assoc::Left),
+    BinaryOperator::new(Ampersand, BinaryExprKind::BitAnd, 7, Assoc::Left),
+    BinaryOperator::new(Shl, BinaryExprKind::Shl, 8, Assoc::Left),
+    BinaryOperator::new(Shr, BinaryExprKind::Shr, 8, Assoc::Left),
+    BinaryOperator::new(Plus, BinaryExprKind::Add, 9, Assoc::Left),
+    BinaryOperator::new(Minus, BinaryExprKind::Sub, 9, Assoc::Left),
+    BinaryOperator::new(Asterisk, BinaryExprKind::Mul, 10, Assoc::Left),
+    BinaryOperator::new(Slash, BinaryExprKind::Div, 10, Assoc::Left),
+    BinaryOperator::new(Percent, BinaryExprKind::Rem, 10, Assoc::Left),
+];
+
+/// 前置の単項演算子の表
+const UNARY_OPERATORS: &[(TokenKind, UnaryExprKind)] = &[
+    (Bang, UnaryExprKind::Not),
+    (Tilde, UnaryExprKind::BitNot),
+    (Asterisk, UnaryExprKind::Deref),
+    (Ampersand, UnaryExprKind::Addr),
+    (Minus, UnaryExprKind::Minus),
+];
+
 impl Parser {
     pub fn new(input: impl ToString) -> Parser {
         let lexer = Lexer::new(input);
         let mut parser = Parser {
             lexer,
             cur_token: Token::default(),
+            prev_span: Span::default(),
             local_vars: Environment::default(),
+            types: Environment::default(),
+            consts: Environment::default(),
+            loop_labels: Vec::new(),
+            errors: Vec::new(),
         };
 
         parser.next_token();
@@ -42,8 +187,24 @@ impl Parser {
         parser
     }
 
+    /// 次のトークンに進む
+    ///
+    /// 字句エラーのトークンはレキサーがエラーとして記録しているので、構文解析では読み飛ばす
     fn next_token(&mut self) {
-        self.cur_token = self.lexer.next_token()
+        self.prev_span = self.cur_token.span;
+
+        loop {
+            self.cur_token = self.lexer.next_token();
+
+            if !self.cur_token.kind.is_error() {
+                break;
+            }
+        }
+    }
+
+    /// `start`から直前に読み進めたトークンまでの範囲を返す
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.prev_span)
     }
 
     fn peek(&self, kind: TokenKind) -> bool {
@@ -64,6 +225,7 @@ impl Parser {
             return Err(ParserError::UnexpectedToken {
                 expected: kind,
                 actual: self.cur_token.kind,
+                span: self.cur_token.span,
             });
         }
 
@@ -74,57 +236,162 @@ impl Parser {
         Ok(token)
     }
 
-    fn create_ident(&mut self, ident: &Expression) -> PResult<Expression> {
+    /// 変数を定義する。`let mut`で定義された変数だけが`mutable`になる
+    fn create_ident(&mut self, ident: &Expression, mutable: bool) -> PResult<Expression> {
         let Expression::Ident(ident) = ident else {
-            return Err(ParserError::UnexpectedExpr(ident.clone()))
+            return Err(ParserError::UnexpectedExpr(Box::new(ident.clone())));
         };
         let name = ident.name.clone();
-        let ident = Ident { name, offset: 0 };
+        let ident = Ident {
+            name,
+            offset: 0,
+            mutable,
+            span: ident.span,
+        };
 
         self.local_vars
             .register_item(ident.name.clone(), ident.clone());
+        self.consts.register_item(ident.name.clone(), None);
 
         Ok(Expression::Ident(ident))
     }
 
     fn find_ident(&self, ident: &Expression) -> PResult<Expression> {
         let Expression::Ident(ident) = ident else {
-            return Err(ParserError::UnexpectedExpr(ident.clone()))
+            return Err(ParserError::UnexpectedExpr(Box::new(ident.clone())));
         };
         let name = ident.name.clone();
 
-        if let Some(ident) = self.local_vars.get_item(&name) {
-            Ok(Expression::Ident(ident.clone()))
+        // 定数は、評価した値のリテラルに置き換える
+        if let Some(value) = self.find_const(&name, ident.span) {
+            return Ok(value);
+        }
+
+        if let Some(found) = self.local_vars.get_item(&name) {
+            // 参照している箇所の位置を保持する
+            Ok(Expression::Ident(Ident {
+                span: ident.span,
+                ..found.clone()
+            }))
         } else {
-            Err(ParserError::UndefinedIdent(name))
+            Err(ParserError::UndefinedIdent(name, ident.span))
         }
     }
 
+    /// 定数の値を、参照している箇所に書かれたリテラルとして返す
+    fn find_const(&self, name: &String, span: Span) -> Option<Expression> {
+        let value = self.consts.get_item(name)?.as_ref()?;
+
+        Some(value.to_literal(span))
+    }
+
     fn enter_scope(&mut self) {
         self.local_vars.create_deeper_scope();
+        self.types.create_deeper_scope();
+        self.consts.create_deeper_scope();
     }
 
     fn leave_scope(&mut self) {
         self.local_vars.create_shallow_scope();
+        self.types.create_shallow_scope();
+        self.consts.create_shallow_scope();
+    }
+
+    /// これまでに見つかったエラーを返す
+    pub fn errors(&self) -> &[ParserError] {
+        &self.errors
     }
 
     /// program = stmt*
+    ///
+    /// エラーがあった場合は最初のエラーを返す
     pub fn parse_program(&mut self) -> PResult<Node<Expression>> {
+        let program = self.parse_partial_program();
+
+        if let Some(error) = self.errors.first() {
+            return Err(error.clone());
+        }
+
+        Ok(program)
+    }
+
+    /// エラーがあっても最後まで解析し、解析できた文だけからなるプログラムを返す
+    /// 見つかったエラーは`errors`で取得できる
+    pub fn parse_partial_program(&mut self) -> Node<Expression> {
+        let program = self.parse_stmts();
+
+        // 字句エラーは構文エラーの原因になっているはずなので、先頭に並べる
+        let lex_errors = self
+            .lexer
+            .errors()
+            .iter()
+            .cloned()
+            .map(ParserError::LexError)
+            .collect::<Vec<_>>();
+
+        self.errors.splice(0..0, lex_errors);
+
+        program
+    }
+
+    fn parse_stmts(&mut self) -> Node<Expression> {
         let mut statements = Vec::new();
         let mut is_returned = false;
 
         while !self.consume(EOF) {
-            let stmt = self.parse_stmt()?;
+            let Some(stmt) = self.parse_stmt_or_recover() else {
+                // 対応する'{'のない'}'は読み飛ばす
+                self.consume(RBrace);
+                continue;
+            };
 
             if is_returned {
-                return Err(ParserError::RequiredSemiColon);
+                self.errors
+                    .push(ParserError::RequiredSemiColon(self.prev_span));
             }
 
             is_returned = matches!(stmt, Statement::ExprReturnStmt(_));
             statements.push(stmt);
         }
 
-        Ok(Node::Program(Program { statements }))
+        Node::Program(Program { statements })
+    }
+
+    /// 文を解析し、エラーがあれば記録して次の文の先頭まで読み飛ばす
+    fn parse_stmt_or_recover(&mut self) -> Option<Statement<Expression>> {
+        let depth = self.local_vars.depth();
+        let loop_count = self.loop_labels.len();
+
+        match self.parse_stmt() {
+            Ok(stmt) => Some(stmt),
+            Err(error) => {
+                self.errors.push(error);
+                self.loop_labels.truncate(loop_count);
+
+                // 解析の途中で入ったスコープから抜ける
+                while self.local_vars.depth() > depth {
+                    self.leave_scope();
+                }
+
+                self.synchronize();
+
+                None
+            }
+        }
+    }
+
+    /// ';'の直後か、'}'・'let'・'const'の直前まで読み飛ばす
+    fn synchronize(&mut self) {
+        loop {
+            match self.cur_token.kind {
+                EOF | RBrace | Let | Const => return,
+                SemiColon => {
+                    self.next_token();
+                    return;
+                }
+                _ => self.next_token(),
+            }
+        }
     }
 
     /// stmt = expr ';'?
@@ -138,74 +405,111 @@ impl Parser {
         Ok(Statement::ExprReturnStmt(expr))
     }
 
-    /// expr = let_expr | if_expr | return_expr | assign
+    /// expr = let_expr | const_expr | struct_def | enum_def | type_alias | if_expr | match_expr | return_expr | labeled_expr
+    ///      | while_expr | for_expr | loop_expr | break_expr | continue_expr | binary
     fn parse_expr(&mut self) -> PResult<Expression> {
         if self.peek(Let) {
             return self.parse_let_expr();
         }
 
+        if self.peek(Const) {
+            return self.parse_const_expr();
+        }
+
+        if self.peek(TokenKind::Struct) {
+            return self.parse_struct_def();
+        }
+
+        if self.peek(TokenKind::Enum) {
+            return self.parse_enum_def();
+        }
+
+        if self.peek(TokenKind::Type) {
+            return self.parse_type_alias();
+        }
+
         if self.peek(If) {
             return self.parse_if_expr();
         }
 
+        if self.peek(Match) {
+            return self.parse_match_expr();
+        }
+
         if self.peek(Return) {
             return self.parse_return_expr();
         }
 
-        self.parse_assign()
-    }
-
-    /// let_expr = 'let' (ident ':' type | ident '(' (ident ':' type),* ')' ':' type) '=' expr
-    /// TODO: シャドーイングの実装
-    fn parse_let_expr(&mut self) -> PResult<Expression> {
-        self.expect(Let)?;
+        if self.peek(Label) {
+            return self.parse_labeled_expr();
+        }
 
-        let ident = self.parse_ident()?;
+        if self.peek(While) {
+            return self.parse_while_expr(None);
+        }
 
-        if self.consume(LParen) {
-            let name = Box::new(self.create_ident(&ident)?);
-            let mut params = Vec::new();
+        if self.peek(For) {
+            return self.parse_for_expr(None);
+        }
 
-            self.enter_scope();
+        if self.peek(Loop) {
+            return self.parse_loop_expr(None);
+        }
 
-            if self.consume(RParen) {
-                let mut return_ty = self.parse_type()?;
+        if self.peek(Break) {
+            return self.parse_break_expr();
+        }
 
-                // 戻り値の型が明記されなかった場合は、戻り値がない関数とみなす
-                if return_ty == Type::unknown() {
-                    return_ty = Type::never();
-                }
+        if self.peek(Continue) {
+            return self.parse_continue_expr();
+        }
 
-                self.expect(Assign)?;
+        self.parse_binary(0)
+    }
 
-                let body = Box::new(self.parse_expr()?);
+    /// let_expr = 'let' ('mut'? let_target ':' type | ident '(' (ident ':' type),* ')' ':' type) '=' expr
+    ///
+    /// 同じ名前の変数を定義すると、以降はその名前で新しい変数を参照する
+    /// `mut`をつけた変数だけが、代入したりアドレスを取ったりできる
+    fn parse_let_expr(&mut self) -> PResult<Expression> {
+        let start = self.expect(Let)?.span;
+        let mut_token = self.cur_token.clone();
+        let mutable = self.consume(Mut);
 
-                self.leave_scope();
+        if self.peek(LParen) {
+            let target = self.parse_let_target()?;
+            let ty = self.parse_type()?;
 
-                return Ok(Expression::FunctionDefExpr(FunctionDefExpr {
-                    name,
-                    params,
-                    return_ty,
-                    body,
-                }));
-            }
+            self.expect(Assign)?;
 
-            let ident = self.parse_ident()?;
-            let param = self.create_ident(&ident)?;
-            let ty = self.parse_type()?;
+            let value = Some(Box::new(self.parse_expr()?));
+            let name = Box::new(self.create_let_target(&target, mutable)?);
 
-            params.push((param, ty));
+            return Ok(Expression::LetExpr(LetExpr {
+                name,
+                value,
+                ty,
+                span: self.span_from(start),
+            }));
+        }
 
-            while self.consume(Comma) {
-                let ident = self.parse_ident()?;
-                let param = self.create_ident(&ident)?;
-                let ty = self.parse_type()?;
+        let ident = self.parse_ident()?;
 
-                params.push((param, ty));
+        if self.peek(LParen) {
+            // 関数は書き換えられないので`mut`はつけられない
+            if mutable {
+                return Err(ParserError::UnexpectedToken {
+                    expected: TokenKind::Ident,
+                    actual: Mut,
+                    span: mut_token.span,
+                });
             }
 
-            self.expect(RParen)?;
+            let name = Box::new(self.create_ident(&ident, false)?);
+
+            self.enter_scope();
 
+            let params = self.parse_params()?;
             let mut return_ty = self.parse_type()?;
 
             // 戻り値の型が明記されなかった場合は、戻り値がない関数とみなす
@@ -215,7 +519,7 @@ impl Parser {
 
             self.expect(Assign)?;
 
-            let body = Box::new(self.parse_expr()?);
+            let body = Box::new(self.parse_function_body()?);
 
             self.leave_scope();
 
@@ -224,351 +528,1111 @@ impl Parser {
                 params,
                 return_ty,
                 body,
+                span: self.span_from(start),
             }));
         }
 
         let ty = self.parse_type()?;
-        let name = Box::new(self.create_ident(&ident)?);
 
+        // 初期値の中では、同じ名前でもまだ外側の変数を参照する
         let value = if self.consume(Assign) {
             Some(Box::new(self.parse_expr()?))
         } else {
             None
         };
+        let name = Box::new(self.create_ident(&ident, mutable)?);
 
-        Ok(Expression::LetExpr(LetExpr { name, value, ty }))
+        Ok(Expression::LetExpr(LetExpr {
+            name,
+            value,
+            ty,
+            span: self.span_from(start),
+        }))
     }
 
-    /// if_expr = 'if' expr expr ('else' expr)?
-    fn parse_if_expr(&mut self) -> PResult<Expression> {
-        self.expect(If)?;
+    /// const_expr = 'const' ident ':' type_expr '=' expr
+    ///
+    /// 初期値はコンパイル時に評価し、定数を参照している箇所はその値のリテラルに置き換える
+    /// 初期値から参照できるのは、先に宣言された定数だけ
+    fn parse_const_expr(&mut self) -> PResult<Expression> {
+        let start = self.expect(Const)?.span;
+        let name = self.expect(TokenKind::Ident)?.literal;
 
-        let condition = Box::new(self.parse_expr()?);
-        let consequence = Box::new(self.parse_expr()?);
-        let alternative = if self.consume(Else) {
-            Some(Box::new(self.parse_expr()?))
-        } else {
-            None
-        };
+        self.expect(Colon)?;
 
-        Ok(Expression::IfExpr(IfExpr {
-            condition,
-            consequence,
-            alternative,
-        }))
-    }
+        let ty = self.parse_type_expr()?;
 
-    /// return_expr = 'return' expr
-    fn parse_return_expr(&mut self) -> PResult<Expression> {
-        self.expect(Return)?;
+        self.expect(Assign)?;
 
-        Ok(Expression::ReturnExpr(ReturnExpr {
-            value: Box::new(self.parse_expr()?),
-        }))
-    }
+        let init = self.parse_expr()?;
+        let span = init.span();
 
-    /// assign = relation ('=' relation)?
-    fn parse_assign(&mut self) -> PResult<Expression> {
-        let node = self.parse_relation()?;
+        let value = thread::Builder::new()
+            .stack_size(FUELED_STACK_SIZE)
+            .spawn(move || {
+                let value = Evaluator::with_fuel(CONST_EVAL_FUEL)
+                    .eval(Node::Expression(init))
+                    .ok()?;
 
-        if self.consume(TokenKind::Assign) {
-            return Ok(Expression::BinaryExpr(BinaryExpr {
-                kind: BinaryExprKind::Assign,
-                lhs: Box::new(node),
-                rhs: Box::new(self.parse_relation()?),
-            }));
+                ConstValue::from_object(value)
+            })
+            .expect("failed to spawn a thread to evaluate a constant")
+            .join()
+            .unwrap_or_else(|payload| panic::resume_unwind(payload))
+            .ok_or(ParserError::NotConstant(span))?;
+
+        // 参照している箇所はリテラルに置き換わるので、ここで型を合わせておく
+        if ty.unaliased().kind != value.ty() {
+            return Err(ParserError::ConstTypeMismatch(ty, span));
         }
 
-        Ok(node)
-    }
+        let literal = Box::new(value.to_literal(span));
 
-    /// relation = add ('==' add | '!=' add | '<' add | '<=' add | '>' add | '>=' add)*
-    fn parse_relation(&mut self) -> PResult<Expression> {
-        let mut node = self.parse_add()?;
+        self.consts.register_item(name.clone(), Some(value));
 
-        loop {
-            if self.consume(TokenKind::Eq) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Eq,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_add()?),
-                });
-            } else if self.consume(TokenKind::Ne) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Ne,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_add()?),
-                });
-            } else if self.consume(TokenKind::Lt) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Lt,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_add()?),
-                });
-            } else if self.consume(TokenKind::Le) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Le,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_add()?),
-                });
-            } else if self.consume(TokenKind::Gt) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Gt,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_add()?),
-                });
-            } else if self.consume(TokenKind::Ge) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Ge,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_add()?),
-                });
-            } else {
-                return Ok(node);
-            }
-        }
+        Ok(Expression::ConstExpr(ConstExpr {
+            name,
+            ty,
+            value: literal,
+            span: self.span_from(start),
+        }))
     }
 
-    /// add = mul ('+' mul | '-' mul)*
-    fn parse_add(&mut self) -> PResult<Expression> {
-        let mut node = self.parse_mul()?;
+    /// let_target = ident | '(' let_target (',' let_target)* ','? ')'
+    ///
+    /// 分割して束縛する変数の並びは、タプルの式として表す
+    fn parse_let_target(&mut self) -> PResult<Expression> {
+        let start = self.cur_token.span;
 
-        loop {
-            if self.consume(Plus) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Add,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_mul()?),
-                });
-            } else if self.consume(Minus) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Sub,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_mul()?),
-                });
-            } else {
-                return Ok(node);
-            }
+        if !self.consume(LParen) {
+            return self.parse_ident();
         }
-    }
 
-    /// mul = unary ('*' unary | '/' unary )*
-    fn parse_mul(&mut self) -> PResult<Expression> {
-        let mut node = self.parse_unary()?;
+        let mut elements = Vec::new();
 
-        loop {
-            if self.consume(Asterisk) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Mul,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_unary()?),
-                });
-            } else if self.consume(Slash) {
-                node = Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Div,
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_unary()?),
-                });
-            } else {
-                return Ok(node);
+        while !self.consume(RParen) {
+            elements.push(self.parse_let_target()?);
+
+            if !self.consume(Comma) {
+                self.expect(RParen)?;
+                break;
             }
         }
+
+        Ok(Expression::TupleExpr(TupleExpr {
+            elements,
+            offset: 0,
+            span: self.span_from(start),
+        }))
     }
 
-    /// unary = '!' unary | '*' unary | '&' unary | '-' index | index
-    fn parse_unary(&mut self) -> PResult<Expression> {
-        if self.consume(Bang) {
-            return Ok(Expression::UnaryExpr(UnaryExpr {
-                kind: UnaryExprKind::Not,
-                expr: Box::new(self.parse_unary()?),
-            }));
-        }
+    /// 分割して束縛する変数をすべて定義する
+    fn create_let_target(&mut self, target: &Expression, mutable: bool) -> PResult<Expression> {
+        let Expression::TupleExpr(tuple) = target else {
+            return self.create_ident(target, mutable);
+        };
+        let elements = tuple
+            .elements
+            .iter()
+            .map(|element| self.create_let_target(element, mutable))
+            .collect::<PResult<Vec<_>>>()?;
+
+        Ok(Expression::TupleExpr(TupleExpr {
+            elements,
+            ..tuple.clone()
+        }))
+    }
 
-        if self.consume(Asterisk) {
-            return Ok(Expression::UnaryExpr(UnaryExpr {
-                kind: UnaryExprKind::Deref,
-                expr: Box::new(self.parse_unary()?),
-            }));
-        }
+    /// params = '(' ('mut'? ident type (',' 'mut'? ident type)*)? ')'
+    ///
+    /// 仮引数は呼び出し元が用意した関数のスコープに定義する
+    fn parse_params(&mut self) -> PResult<Vec<(Expression, Type)>> {
+        let mut params = Vec::new();
 
-        if self.consume(Ampersand) {
-            return Ok(Expression::UnaryExpr(UnaryExpr {
-                kind: UnaryExprKind::Addr,
-                expr: Box::new(self.parse_unary()?),
-            }));
-        }
+        self.expect(LParen)?;
 
-        if self.consume(Minus) {
-            return Ok(Expression::UnaryExpr(UnaryExpr {
-                kind: UnaryExprKind::Minus,
-                expr: Box::new(self.parse_index()?),
-            }));
+        if self.consume(RParen) {
+            return Ok(params);
         }
 
-        self.parse_index()
-    }
-
-    /// index = call ('[' expr ']')*
-    fn parse_index(&mut self) -> PResult<Expression> {
-        let mut node = self.parse_call()?;
+        loop {
+            let mutable = self.consume(Mut);
+            let ident = self.parse_ident()?;
+            let param = self.create_ident(&ident, mutable)?;
+            let ty = self.parse_type()?;
 
-        while self.consume(LBracket) {
-            let index = Box::new(self.parse_expr()?);
-
-            node = Expression::UnaryExpr(UnaryExpr {
-                kind: UnaryExprKind::Deref,
-                expr: Box::new(Expression::BinaryExpr(BinaryExpr {
-                    kind: BinaryExprKind::Add,
-                    lhs: Box::new(node),
-                    rhs: index,
-                })),
-            });
+            params.push((param, ty));
 
-            self.expect(RBracket)?;
+            if !self.consume(Comma) {
+                break;
+            }
         }
 
-        Ok(node)
+        self.expect(RParen)?;
+
+        Ok(params)
     }
 
-    /// call = primary ('(' expr,* ')')?
-    fn parse_call(&mut self) -> PResult<Expression> {
-        let node = self.parse_primary()?;
+    /// lambda = 'fn' params (':' type)? '=>' expr
+    fn parse_lambda(&mut self) -> PResult<Expression> {
+        let start = self.expect(Fn)?.span;
 
-        if self.consume(LParen) {
-            let mut args = Vec::new();
+        self.enter_scope();
 
-            if self.consume(RParen) {
-                return Ok(Expression::CallExpr(CallExpr {
-                    func: Box::new(node),
-                    args,
-                }));
-            }
+        let params = self.parse_params()?;
+        let return_ty = self.parse_type()?;
 
-            args.push(self.parse_expr()?);
+        self.expect(FatArrow)?;
 
-            while self.consume(Comma) {
-                args.push(self.parse_expr()?);
-            }
+        let body = Box::new(self.parse_function_body()?);
 
-            self.expect(RParen)?;
+        self.leave_scope();
 
-            return Ok(Expression::CallExpr(CallExpr {
-                func: Box::new(node),
-                args,
-            }));
-        }
+        Ok(Expression::LambdaExpr(LambdaExpr {
+            params,
+            return_ty,
+            body,
+            span: self.span_from(start),
+        }))
+    }
 
-        Ok(node)
+    /// 関数の本体では、外側のループから抜けられないようにする
+    fn parse_function_body(&mut self) -> PResult<Expression> {
+        let loop_labels = std::mem::take(&mut self.loop_labels);
+        let body = self.parse_expr();
+
+        self.loop_labels = loop_labels;
+
+        body
     }
 
-    /// primary = '(' expr ')' | block_expr | array | integer | ident | bool
-    fn parse_primary(&mut self) -> PResult<Expression> {
-        if self.consume(LParen) {
-            let expr = self.parse_expr()?;
+    /// struct_def = 'struct' ident '{' (ident ':' type_expr (',' ident ':' type_expr)* ','?)? '}'
+    fn parse_struct_def(&mut self) -> PResult<Expression> {
+        let start = self.expect(TokenKind::Struct)?.span;
+        let name = self.expect(TokenKind::Ident)?.literal;
+        let mut fields: Vec<(String, Type)> = Vec::new();
 
-            self.expect(RParen)?;
+        self.expect(LBrace)?;
 
-            return Ok(expr);
-        }
+        while !self.consume(RBrace) {
+            let field = self.expect(TokenKind::Ident)?;
 
-        if self.peek(LBrace) {
-            return self.parse_block_expr();
-        }
+            if fields.iter().any(|(name, _)| *name == field.literal) {
+                return Err(ParserError::IdentAlreadyDefined(field.literal, field.span));
+            }
 
-        if self.peek(LBracket) {
-            return self.parse_array();
-        }
+            self.expect(Colon)?;
+            fields.push((field.literal, self.parse_type_expr()?));
 
-        if self.peek(Number) {
-            return self.parse_integer();
+            if !self.consume(Comma) {
+                self.expect(RBrace)?;
+                break;
+            }
         }
 
-        if self.peek(TokenKind::Ident) {
-            let ident = self.parse_ident()?;
+        let ty = Type::structure(&name, fields);
 
-            return self.find_ident(&ident);
-        }
+        self.types.register_item(name.clone(), ty.clone());
 
-        self.parse_bool()
+        Ok(Expression::StructDefExpr(StructDefExpr {
+            name,
+            ty,
+            span: self.span_from(start),
+        }))
     }
 
-    /// block_expr = '{' stmt* '}'
-    fn parse_block_expr(&mut self) -> PResult<Expression> {
-        self.enter_scope();
+    /// enum_def = 'enum' ident '{' (variant (',' variant)* ','?)? '}'
+    /// variant = ident ('(' type_expr (',' type_expr)* ')')?
+    fn parse_enum_def(&mut self) -> PResult<Expression> {
+        let start = self.expect(TokenKind::Enum)?.span;
+        let name = self.expect(TokenKind::Ident)?.literal;
+        let mut variants: Vec<(String, Vec<Type>)> = Vec::new();
 
         self.expect(LBrace)?;
 
-        let mut statements = Vec::new();
-
         while !self.consume(RBrace) {
-            if self.consume(EOF) {
-                return Err(ParserError::UnexpectedToken {
-                    expected: RBrace,
-                    actual: EOF,
-                });
+            let variant = self.expect(TokenKind::Ident)?;
+            let mut payload = Vec::new();
+
+            if variants.iter().any(|(name, _)| *name == variant.literal) {
+                return Err(ParserError::IdentAlreadyDefined(
+                    variant.literal,
+                    variant.span,
+                ));
             }
 
-            let stmt = self.parse_stmt()?;
+            if self.consume(LParen) {
+                payload.push(self.parse_type_expr()?);
 
-            statements.push(stmt);
-        }
+                while self.consume(Comma) {
+                    payload.push(self.parse_type_expr()?);
+                }
 
-        self.leave_scope();
+                self.expect(RParen)?;
+            }
 
-        Ok(Expression::BlockExpr(BlockExpr { statements }))
-    }
+            variants.push((variant.literal, payload));
 
-    /// array = '[' expr,* ']'
-    fn parse_array(&mut self) -> PResult<Expression> {
-        self.expect(LBracket)?;
+            if !self.consume(Comma) {
+                self.expect(RBrace)?;
+                break;
+            }
+        }
 
-        let mut elements = Vec::new();
+        let ty = Type::enumeration(&name, variants);
 
-        if self.consume(RBracket) {
-            return Ok(Expression::Array(Array { elements }));
-        }
+        self.types.register_item(name.clone(), ty.clone());
 
-        elements.push(self.parse_expr()?);
+        Ok(Expression::EnumDefExpr(EnumDefExpr {
+            name,
+            ty,
+            span: self.span_from(start),
+        }))
+    }
 
-        while self.consume(Comma) {
-            elements.push(self.parse_expr()?);
-        }
+    /// type_alias = 'type' ident '=' type_expr
+    fn parse_type_alias(&mut self) -> PResult<Expression> {
+        let start = self.expect(TokenKind::Type)?.span;
+        let name = self.expect(TokenKind::Ident)?.literal;
 
-        self.expect(RBracket)?;
+        self.expect(Assign)?;
 
-        Ok(Expression::Array(Array { elements }))
-    }
+        let ty = Type::alias(&name, Box::new(self.parse_type_expr()?));
 
-    /// integer = [0-9]*
-    fn parse_integer(&mut self) -> PResult<Expression> {
-        let token = self.expect(Number)?;
-        let value = token
-            .literal
-            .parse::<isize>()
-            .map_err(ParserError::ParseIntError)?;
+        self.types.register_item(name.clone(), ty.clone());
 
-        Ok(Expression::Integer(Integer { value }))
+        Ok(Expression::TypeAliasExpr(TypeAliasExpr {
+            name,
+            ty,
+            span: self.span_from(start),
+        }))
     }
 
-    /// ident = ([a-zA-Z] | '_') ([a-zA-Z0-9] | '_')*
-    fn parse_ident(&mut self) -> PResult<Expression> {
+    /// match_expr = 'match' expr '{' (match_arm (',' match_arm)* ','?)? '}'
+    ///
+    /// 本体がブロックの腕の後ろでは','を省略できる
+    fn parse_match_expr(&mut self) -> PResult<Expression> {
+        let start = self.expect(Match)?.span;
+        let expr = Box::new(self.parse_expr()?);
+        let mut arms = Vec::new();
+
+        self.expect(LBrace)?;
+
+        while !self.consume(RBrace) {
+            let arm = self.parse_match_arm()?;
+            let is_block = matches!(*arm.body, Expression::BlockExpr(_));
+
+            arms.push(arm);
+
+            if !self.consume(Comma) && !is_block {
+                self.expect(RBrace)?;
+                break;
+            }
+        }
+
+        Ok(Expression::MatchExpr(MatchExpr {
+            expr,
+            arms,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// match_arm = pattern ('if' expr)? '=>' expr
+    ///
+    /// パターンで束縛した変数は、その腕の中でのみ参照できる
+    fn parse_match_arm(&mut self) -> PResult<MatchArm<Expression>> {
+        let start = self.cur_token.span;
+
+        self.enter_scope();
+
+        let pattern = self.parse_pattern()?;
+        let guard = if self.consume(If) {
+            Some(Box::new(self.parse_expr()?))
+        } else {
+            None
+        };
+
+        self.expect(FatArrow)?;
+
+        let body = Box::new(self.parse_expr()?);
+
+        self.leave_scope();
+
+        Ok(MatchArm {
+            pattern,
+            guard,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    /// pattern = '_' | '-'? integer | char | bool | ident '::' ident ('(' pattern (',' pattern)* ')')?
+    ///         | ident
+    fn parse_pattern(&mut self) -> PResult<Pattern<Expression>> {
+        let start = self.cur_token.span;
+
+        if self.consume(Minus) {
+            let value = Box::new(Expression::UnaryExpr(UnaryExpr {
+                kind: UnaryExprKind::Minus,
+                expr: Box::new(self.parse_integer()?),
+                span: self.span_from(start),
+            }));
+
+            return Ok(Pattern::Literal {
+                value,
+                span: self.span_from(start),
+            });
+        }
+
+        if self.peek(Number) || self.peek(TokenKind::Char) || self.peek(True) || self.peek(False) {
+            let value = Box::new(self.parse_primary()?);
+
+            return Ok(Pattern::Literal {
+                value,
+                span: self.span_from(start),
+            });
+        }
+
+        let ident = self.parse_ident()?;
+        let Expression::Ident(Ident { name, .. }) = &ident else {
+            unreachable!();
+        };
+
+        if name == "_" {
+            return Ok(Pattern::Wildcard(start));
+        }
+
+        // 定数は、その値のリテラルとして比べる
+        if let Some(value) = self.find_const(name, start) {
+            if !matches!(
+                value,
+                Expression::Integer(_) | Expression::Char(_) | Expression::Boolean(_)
+            ) {
+                return Err(ParserError::UnexpectedExpr(Box::new(value)));
+            }
+
+            return Ok(Pattern::Literal {
+                value: Box::new(value),
+                span: start,
+            });
+        }
+
+        // 別名で書かれた列挙型も、元の列挙型として扱う
+        if let Some(ty) = self.types.get_item(name).map(|ty| ty.unaliased().clone()) {
+            if self.peek(ColonColon) {
+                let variant = self.parse_variant_name(&ty)?;
+                let mut fields = Vec::new();
+
+                if self.consume(LParen) {
+                    fields.push(self.parse_pattern()?);
+
+                    while self.consume(Comma) {
+                        fields.push(self.parse_pattern()?);
+                    }
+
+                    self.expect(RParen)?;
+                }
+
+                return Ok(Pattern::Variant {
+                    ty,
+                    variant,
+                    fields,
+                    span: self.span_from(start),
+                });
+            }
+        }
+
+        Ok(Pattern::Binding {
+            ident: Box::new(self.create_ident(&ident, false)?),
+            span: start,
+        })
+    }
+
+    /// '::' ident
+    ///
+    /// 列挙型に宣言されていないバリアントの名前はエラーにする
+    fn parse_variant_name(&mut self, ty: &Type) -> PResult<String> {
+        self.expect(ColonColon)?;
+
+        let variant = self.expect(TokenKind::Ident)?;
+
+        if ty.variant(&variant.literal).is_none() {
+            return Err(ParserError::UndefinedVariant(variant.literal, variant.span));
+        }
+
+        Ok(variant.literal)
+    }
+
+    /// if_expr = 'if' expr expr ('else' expr)?
+    fn parse_if_expr(&mut self) -> PResult<Expression> {
+        let start = self.expect(If)?.span;
+        let condition = Box::new(self.parse_expr()?);
+        let consequence = Box::new(self.parse_expr()?);
+        let alternative = if self.consume(Else) {
+            Some(Box::new(self.parse_expr()?))
+        } else {
+            None
+        };
+
+        Ok(Expression::IfExpr(IfExpr {
+            condition,
+            consequence,
+            alternative,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// return_expr = 'return' expr
+    fn parse_return_expr(&mut self) -> PResult<Expression> {
+        let start = self.expect(Return)?.span;
+        let value = Box::new(self.parse_expr()?);
+
+        Ok(Expression::ReturnExpr(ReturnExpr {
+            value,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// labeled_expr = label ':' (while_expr | for_expr | loop_expr)
+    fn parse_labeled_expr(&mut self) -> PResult<Expression> {
+        let label = self.expect(Label)?;
+
+        self.expect(Colon)?;
+
+        match self.cur_token.kind {
+            While => self.parse_while_expr(Some(label)),
+            For => self.parse_for_expr(Some(label)),
+            _ => self.parse_loop_expr(Some(label)),
+        }
+    }
+
+    /// ループの本体を解析する。本体の中では`label`を`break`・`continue`の飛び先にできる
+    fn parse_loop_body(&mut self, label: Option<String>) -> PResult<Expression> {
+        self.loop_labels.push(label);
+
+        let body = self.parse_expr();
+
+        self.loop_labels.pop();
+
+        body
+    }
+
+    /// while_expr = 'while' expr expr
+    fn parse_while_expr(&mut self, label: Option<Token>) -> PResult<Expression> {
+        let keyword = self.expect(While)?;
+        let start = label.as_ref().map_or(keyword.span, |label| label.span);
+        let label = label.map(|label| label.literal);
+        let condition = Box::new(self.parse_expr()?);
+        let body = Box::new(self.parse_loop_body(label.clone())?);
+
+        Ok(Expression::WhileExpr(WhileExpr {
+            label,
+            condition,
+            body,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// loop_expr = 'loop' expr
+    fn parse_loop_expr(&mut self, label: Option<Token>) -> PResult<Expression> {
+        let keyword = self.expect(Loop)?;
+        let start = label.as_ref().map_or(keyword.span, |label| label.span);
+        let label = label.map(|label| label.literal);
+        let body = Box::new(self.parse_loop_body(label.clone())?);
+
+        Ok(Expression::LoopExpr(LoopExpr {
+            label,
+            body,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// for_expr = 'for' ident 'in' (expr ('..' | '..=') expr | expr) expr
+    ///
+    /// ループ変数は本体だけを囲む新しいスコープに定義する
+    fn parse_for_expr(&mut self, label: Option<Token>) -> PResult<Expression> {
+        let keyword = self.expect(For)?;
+        let start = label.as_ref().map_or(keyword.span, |label| label.span);
+        let label = label.map(|label| label.literal);
+        let ident = self.parse_ident()?;
+
+        self.expect(In)?;
+
+        let first = Box::new(self.parse_expr()?);
+        let iterable = if self.consume(DotDot) {
+            Iterable::Range {
+                start: first,
+                end: Box::new(self.parse_expr()?),
+                inclusive: false,
+            }
+        } else if self.consume(DotDotEq) {
+            Iterable::Range {
+                start: first,
+                end: Box::new(self.parse_expr()?),
+                inclusive: true,
+            }
+        } else {
+            Iterable::Array(first)
+        };
+
+        self.enter_scope();
+
+        let var = self.create_ident(&ident, false);
+        let body = self.parse_loop_body(label.clone());
+
+        self.leave_scope();
+
+        Ok(Expression::ForExpr(ForExpr {
+            label,
+            var: Box::new(var?),
+            iterable,
+            body: Box::new(body?),
+            span: self.span_from(start),
+        }))
+    }
+
+    /// break_expr = 'break' label? expr?
+    fn parse_break_expr(&mut self) -> PResult<Expression> {
+        let start = self.expect(Break)?.span;
+
+        if self.loop_labels.is_empty() {
+            return Err(ParserError::BreakOutsideLoop(start));
+        }
+
+        let label = self.parse_jump_label()?;
+        let value = if self.at_expr_end() {
+            None
+        } else {
+            Some(Box::new(self.parse_expr()?))
+        };
+
+        Ok(Expression::BreakExpr(BreakExpr {
+            label,
+            value,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// continue_expr = 'continue' label?
+    fn parse_continue_expr(&mut self) -> PResult<Expression> {
+        let start = self.expect(Continue)?.span;
+
+        if self.loop_labels.is_empty() {
+            return Err(ParserError::ContinueOutsideLoop(start));
+        }
+
+        let label = self.parse_jump_label()?;
+
+        Ok(Expression::ContinueExpr(ContinueExpr {
+            label,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// `break`・`continue`の飛び先のラベルを読む。囲んでいるループのラベルでなければエラー
+    fn parse_jump_label(&mut self) -> PResult<Option<String>> {
+        if !self.peek(Label) {
+            return Ok(None);
+        }
+
+        let label = self.expect(Label)?;
+
+        if !self.loop_labels.contains(&Some(label.literal.clone())) {
+            return Err(ParserError::UndefinedLabel(label.literal, label.span));
+        }
+
+        Ok(Some(label.literal))
+    }
+
+    /// 式がここで終わっているか。値を省略できる`break`の後で使う
+    fn at_expr_end(&self) -> bool {
+        matches!(
+            self.cur_token.kind,
+            SemiColon | RBrace | RParen | RBracket | Comma | Else | EOF
+        )
+    }
+
+    fn binary_expr(kind: BinaryExprKind, lhs: Expression, rhs: Expression) -> Expression {
+        let span = lhs.span().to(rhs.span());
+
+        Expression::BinaryExpr(BinaryExpr {
+            kind,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+            span,
+        })
+    }
+
+    /// binary = unary (binary_op binary)*
+    ///
+    /// 優先順位が`min_prec`以上の二項演算子だけを読み進める
+    fn parse_binary(&mut self, min_prec: u8) -> PResult<Expression> {
+        let first = self.cur_token.clone();
+        let mut node = self.parse_unary()?;
+
+        // 定数はリテラルに置き換わっているので、名前のトークンだけを読んだかどうかで見分ける
+        let mut bare_const = first.kind == TokenKind::Ident
+            && self.prev_span.start == first.span.start
+            && self.find_const(&first.literal, first.span).is_some();
+
+        while let Some(op) = BINARY_OPERATORS
+            .iter()
+            .find(|op| self.peek(op.token) && op.prec >= min_prec)
+        {
+            self.next_token();
+
+            if bare_const && matches!(op.kind, BinaryExprKind::Assign) {
+                return Err(ParserError::AssignToConst(first.literal, first.span));
+            }
+
+            // 右結合なら同じ優先順位の演算子を右辺に含める
+            let rhs = match op.assoc {
+                Assoc::Left => self.parse_binary(op.prec + 1)?,
+                Assoc::Right => self.parse_binary(op.prec)?,
+            };
+
+            node = Self::binary_expr(op.kind, node, rhs);
+            bare_const = false;
+        }
+
+        Ok(node)
+    }
+
+    /// unary = ('!' | '~' | '*' | '&' | '&&' | '-') unary | postfix
+    fn parse_unary(&mut self) -> PResult<Expression> {
+        let start = self.cur_token.span;
+
+        if self.consume(AndAnd) {
+            // 前置の'&&'は'&'が2つ並んだものとして扱う
+            let expr = Box::new(self.parse_unary()?);
+            let span = self.span_from(start);
+
+            return Ok(Expression::UnaryExpr(UnaryExpr {
+                kind: UnaryExprKind::Addr,
+                expr: Box::new(Expression::UnaryExpr(UnaryExpr {
+                    kind: UnaryExprKind::Addr,
+                    expr,
+                    span,
+                })),
+                span,
+            }));
+        }
+
+        let Some(&(_, kind)) = UNARY_OPERATORS.iter().find(|(token, _)| self.peek(*token)) else {
+            return self.parse_postfix();
+        };
+
+        self.next_token();
+
+        let expr = Box::new(self.parse_unary()?);
+
+        Ok(Expression::UnaryExpr(UnaryExpr {
+            kind,
+            expr,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// postfix = primary (call | index)*
+    fn parse_postfix(&mut self) -> PResult<Expression> {
+        let mut node = self.parse_primary()?;
+
+        loop {
+            node = match self.cur_token.kind {
+                LParen => self.parse_call(node)?,
+                LBracket => self.parse_index(node)?,
+                Dot => self.parse_field(node)?,
+                _ => return Ok(node),
+            };
+        }
+    }
+
+    /// field = '.' (ident | integer)
+    ///
+    /// `t.0.1`の`0.1`は浮動小数点数として字句解析されるので、2つの要素の参照に分ける
+    fn parse_field(&mut self, expr: Expression) -> PResult<Expression> {
+        self.expect(Dot)?;
+
+        let field = match self.cur_token.kind {
+            Number | TokenKind::Float => self.cur_token.clone(),
+            _ => self.expect(TokenKind::Ident)?,
+        };
+
+        if field.kind != TokenKind::Ident {
+            self.next_token();
+        }
+
+        let span = expr.span().to(field.span);
+        let mut expr = expr;
+
+        for field in field.literal.split('.') {
+            expr = Expression::FieldExpr(FieldExpr {
+                expr: Box::new(expr),
+                field: field.to_string(),
+                span,
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// call = '(' expr,* ')'
+    fn parse_call(&mut self, func: Expression) -> PResult<Expression> {
+        self.expect(LParen)?;
+
+        let mut args = Vec::new();
+
+        if !self.consume(RParen) {
+            args.push(self.parse_expr()?);
+
+            while self.consume(Comma) {
+                args.push(self.parse_expr()?);
+            }
+
+            self.expect(RParen)?;
+        }
+
+        Ok(Expression::CallExpr(CallExpr {
+            offset: 0,
+            span: self.span_from(func.span()),
+            func: Box::new(func),
+            args,
+        }))
+    }
+
+    /// index = '[' expr ']'
+    ///
+    /// `a[i]`は`*(a + i)`として扱う
+    /// `a[i][j]`は`*(*(a + i) + j)`となり、`a + i`は内側の配列の大きさ単位で進むので、
+    /// 多次元配列は行優先で並ぶ
+    fn parse_index(&mut self, node: Expression) -> PResult<Expression> {
+        self.expect(LBracket)?;
+
+        let index = self.parse_expr()?;
+
+        self.expect(RBracket)?;
+
+        let span = self.span_from(node.span());
+
+        Ok(Expression::UnaryExpr(UnaryExpr {
+            kind: UnaryExprKind::Deref,
+            expr: Box::new(Self::binary_expr(BinaryExprKind::Add, node, index)),
+            span,
+        }))
+    }
+
+    /// primary = '(' expr ')' | tuple | block_expr | lambda | array | integer | float | str | char
+    ///         | struct_literal | enum_variant | ident | bool
+    fn parse_primary(&mut self) -> PResult<Expression> {
+        if self.peek(LParen) {
+            return self.parse_paren_expr();
+        }
+
+        if self.peek(LBrace) {
+            return self.parse_block_expr();
+        }
+
+        if self.peek(Fn) {
+            return self.parse_lambda();
+        }
+
+        if self.peek(LBracket) {
+            return self.parse_array();
+        }
+
+        if self.peek(Number) {
+            return self.parse_integer();
+        }
+
+        if self.peek(TokenKind::Float) {
+            return self.parse_float();
+        }
+
+        if self.peek(TokenKind::Str) {
+            return self.parse_str();
+        }
+
+        if self.peek(TokenKind::Char) {
+            return self.parse_char();
+        }
+
+        if self.peek(TokenKind::Ident) {
+            let ident = self.parse_ident()?;
+
+            // 構造体の名前の直後に'{'が続く場合だけ構造体リテラルとみなす
+            // 列挙型の名前の直後に'::'が続く場合はバリアントの値とみなす
+            // 別名で書かれた場合も、元の構造体や列挙型として扱う
+            if let Expression::Ident(Ident { name, span, .. }) = &ident {
+                if let Some(ty) = self.types.get_item(name).map(|ty| ty.unaliased().clone()) {
+                    match ty.kind {
+                        TypeKind::Struct { .. } if self.peek(LBrace) => {
+                            return self.parse_struct_literal(ty, *span);
+                        }
+                        TypeKind::Enum { .. } if self.peek(ColonColon) => {
+                            return self.parse_enum_variant(ty, *span);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            return self.find_ident(&ident);
+        }
+
+        self.parse_bool()
+    }
+
+    /// tuple = '(' expr ',' (expr (',' expr)* ','?)? ')'
+    ///
+    /// カンマを含まない'(' expr ')'は括弧で囲んだ式として扱う
+    fn parse_paren_expr(&mut self) -> PResult<Expression> {
+        let start = self.expect(LParen)?.span;
+        let expr = self.parse_expr()?;
+
+        if self.consume(RParen) {
+            return Ok(expr);
+        }
+
+        let mut elements = vec![expr];
+
+        while self.consume(Comma) {
+            if self.peek(RParen) {
+                break;
+            }
+
+            elements.push(self.parse_expr()?);
+        }
+
+        self.expect(RParen)?;
+
+        Ok(Expression::TupleExpr(TupleExpr {
+            elements,
+            offset: 0,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// struct_literal = ident '{' (ident ':' expr (',' ident ':' expr)* ','?)? '}'
+    fn parse_struct_literal(&mut self, ty: Type, start: Span) -> PResult<Expression> {
+        let mut fields: Vec<(String, Expression)> = Vec::new();
+
+        self.expect(LBrace)?;
+
+        while !self.consume(RBrace) {
+            let field = self.expect(TokenKind::Ident)?;
+
+            if fields.iter().any(|(name, _)| *name == field.literal) {
+                return Err(ParserError::IdentAlreadyDefined(field.literal, field.span));
+            }
+
+            self.expect(Colon)?;
+            fields.push((field.literal, self.parse_expr()?));
+
+            if !self.consume(Comma) {
+                self.expect(RBrace)?;
+                break;
+            }
+        }
+
+        Ok(Expression::StructLiteral(StructLiteral {
+            ty,
+            fields,
+            offset: 0,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// enum_variant = ident '::' ident ('(' expr (',' expr)* ')')?
+    fn parse_enum_variant(&mut self, ty: Type, start: Span) -> PResult<Expression> {
+        let variant = self.parse_variant_name(&ty)?;
+        let mut args = Vec::new();
+
+        if self.consume(LParen) {
+            args.push(self.parse_expr()?);
+
+            while self.consume(Comma) {
+                args.push(self.parse_expr()?);
+            }
+
+            self.expect(RParen)?;
+        }
+
+        Ok(Expression::EnumVariantExpr(EnumVariantExpr {
+            ty,
+            variant,
+            args,
+            offset: 0,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// block_expr = '{' stmt* '}'
+    fn parse_block_expr(&mut self) -> PResult<Expression> {
+        self.enter_scope();
+
+        let start = self.expect(LBrace)?.span;
+
+        let mut statements = Vec::new();
+
+        while !self.consume(RBrace) {
+            if self.consume(EOF) {
+                return Err(ParserError::UnexpectedToken {
+                    expected: RBrace,
+                    actual: EOF,
+                    span: self.prev_span,
+                });
+            }
+
+            if let Some(stmt) = self.parse_stmt_or_recover() {
+                statements.push(stmt);
+            }
+        }
+
+        self.leave_scope();
+
+        Ok(Expression::BlockExpr(BlockExpr {
+            statements,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// array = '[' expr,* ']'
+    fn parse_array(&mut self) -> PResult<Expression> {
+        let start = self.expect(LBracket)?.span;
+        let mut elements = Vec::new();
+
+        if !self.consume(RBracket) {
+            elements.push(self.parse_expr()?);
+
+            while self.consume(Comma) {
+                elements.push(self.parse_expr()?);
+            }
+
+            self.expect(RBracket)?;
+        }
+
+        Ok(Expression::Array(Array {
+            elements,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// integer = [0-9][0-9_]* | '0x' [0-9a-fA-F_]+ | '0o' [0-7_]+ | '0b' [01_]+
+    fn parse_integer(&mut self) -> PResult<Expression> {
+        let token = self.expect(Number)?;
+        let literal = token.literal.replace('_', "");
+        let (digits, radix) = match literal.get(..2) {
+            Some("0x") => (&literal[2..], 16),
+            Some("0o") => (&literal[2..], 8),
+            Some("0b") => (&literal[2..], 2),
+            _ => (&literal[..], 10),
+        };
+
+        // from_str_radixは先頭の符号を受け付けてしまうので、数字以外が含まれていないか先に確かめる
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return Err(ParserError::InvalidInteger(token.literal, token.span));
+        }
+
+        let value = isize::from_str_radix(digits, radix)
+            .map_err(|_| ParserError::IntegerOverflow(token.literal.clone(), token.span))?;
+
+        Ok(Expression::Integer(Integer {
+            value,
+            span: token.span,
+        }))
+    }
+
+    /// float = [0-9][0-9_]* '.' [0-9][0-9_]* ([eE] [+-]? [0-9]+)?
+    fn parse_float(&mut self) -> PResult<Expression> {
+        let token = self.expect(TokenKind::Float)?;
+        let value = token
+            .literal
+            .replace('_', "")
+            .parse::<f64>()
+            .map_err(|_| ParserError::InvalidFloat(token.literal.clone(), token.span))?;
+
+        Ok(Expression::Float(Float {
+            value,
+            span: token.span,
+        }))
+    }
+
+    /// str = '"' ([^"\\] | escape)* '"'
+    fn parse_str(&mut self) -> PResult<Expression> {
+        let token = self.expect(TokenKind::Str)?;
+        let quoted = &token.literal[1..token.literal.len() - 1];
+
+        Ok(Expression::Str(Str {
+            value: Self::unescape(quoted, token.span)?,
+            span: token.span,
+        }))
+    }
+
+    /// char = '\'' ([^'\\] | escape) '\''
+    fn parse_char(&mut self) -> PResult<Expression> {
+        let token = self.expect(TokenKind::Char)?;
+        let quoted = &token.literal[1..token.literal.len() - 1];
+        let value = Self::unescape(quoted, token.span)?;
+
+        // コンパイル後は1バイトで扱うため、ASCII文字1つだけを受け付ける
+        match value.as_bytes() {
+            [value] if value.is_ascii() => Ok(Expression::Char(Char {
+                value: *value,
+                span: token.span,
+            })),
+            _ => Err(ParserError::InvalidChar(token.literal, token.span)),
+        }
+    }
+
+    /// escape =  '\\' ('n' | 't' | 'r' | '0' | '\\' | '\'' | '"')
+    fn unescape(literal: &str, span: Span) -> PResult<String> {
+        let mut value = String::new();
+        let mut chars = literal.chars();
+
+        while let Some(ch) = chars.next() {
+            if ch != '\\' {
+                value.push(ch);
+                continue;
+            }
+
+            value.push(match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some('\\') => '\\',
+                Some('\'') => '\'',
+                Some('"') => '"',
+                ch => return Err(ParserError::UnknownEscape(ch.unwrap_or('\0'), span)),
+            });
+        }
+
+        Ok(value)
+    }
+
+    /// ident = (XID_Start | '_') XID_Continue*
+    fn parse_ident(&mut self) -> PResult<Expression> {
         let token = self.expect(TokenKind::Ident)?;
 
         Ok(Expression::Ident(Ident {
             name: token.literal,
             offset: 0,
+            mutable: false,
+            span: token.span,
         }))
     }
 
     /// bool = 'true' | 'false'
     fn parse_bool(&mut self) -> PResult<Expression> {
+        let span = self.cur_token.span;
+
         if self.consume(True) {
-            return Ok(Expression::Boolean(Boolean { value: true }));
+            return Ok(Expression::Boolean(Boolean { value: true, span }));
         }
 
         self.expect(False)?;
 
-        Ok(Expression::Boolean(Boolean { value: false }))
+        Ok(Expression::Boolean(Boolean { value: false, span }))
     }
 
     fn parse_type(&mut self) -> PResult<Type> {
@@ -576,32 +1640,110 @@ impl Parser {
             return Ok(Type::unknown());
         }
 
+        self.parse_type_expr()
+    }
+
+    /// type_expr = '*'* (ident | paren_type) ('[' array_length ']')*
+    ///
+    /// 宣言された構造体などの名前は、その型として扱う
+    /// 組み込みの型でも宣言された型でもない名前はエラーにする
+    /// `*`は`[]`よりも強く結びつくので、`*int[4]`はポインタの配列になる
+    /// ポインタで配列を指す場合は`*(int[4])`のように括弧で囲む
+    /// `int[2][3]`は要素数3の配列を2つ並べた配列になる
+    fn parse_type_expr(&mut self) -> PResult<Type> {
         let mut ptr_cnt = 0;
 
         while self.consume(TokenKind::Asterisk) {
             ptr_cnt += 1;
         }
 
-        let type_name = self.expect(TokenKind::Ident)?.literal;
-        let mut ty = Type {
-            kind: TypeKind::from(type_name),
+        let mut ty = if self.peek(LParen) {
+            self.parse_paren_type()?
+        } else {
+            let type_name = self.expect(TokenKind::Ident)?;
+
+            match self.types.get_item(&type_name.literal) {
+                Some(ty) => ty.clone(),
+                None => match TypeKind::builtin(&type_name.literal) {
+                    Some(kind) => Type { kind },
+                    None => {
+                        return Err(ParserError::UnknownTypeName(
+                            type_name.literal,
+                            type_name.span,
+                        ))
+                    }
+                },
+            }
         };
 
         for _ in 0..ptr_cnt {
             ty = Type::pointer_to(Box::new(ty));
         }
 
-        if self.consume(LBracket) {
-            let integer = self.parse_integer()?;
+        let mut lengths = Vec::new();
 
-            if let Expression::Integer(Integer { value: length }) = integer {
-                self.expect(RBracket)?;
-                ty = Type::array(Box::new(ty), length);
-            } else {
-                return Err(ParserError::UnexpectedExpr(integer));
-            }
+        while self.consume(LBracket) {
+            lengths.push(self.parse_array_length()?);
+            self.expect(RBracket)?;
+        }
+
+        // 右側の要素数ほど内側の配列になる
+        for length in lengths.into_iter().rev() {
+            ty = Type::array(Box::new(ty), length);
         }
 
         Ok(ty)
     }
+
+    /// array_length = integer | ident
+    ///
+    /// 名前は整数の定数として宣言されたものだけを受け付ける
+    fn parse_array_length(&mut self) -> PResult<isize> {
+        let length = if self.peek(TokenKind::Ident) {
+            let ident = self.parse_ident()?;
+
+            self.find_ident(&ident)?
+        } else {
+            self.parse_integer()?
+        };
+
+        match length {
+            Expression::Integer(Integer { value, .. }) if value >= 0 => Ok(value),
+            length => Err(ParserError::UnexpectedExpr(Box::new(length))),
+        }
+    }
+
+    /// paren_type = '('  (type_expr (',' type_expr)* ','?)? ')' ('->' type_expr)?
+    ///
+    /// '->'が続く場合は関数の型、続かない場合はタプルの型とする
+    /// カンマを含まない`(int)`は`int`と同じ型として扱い、型をまとめるのに使う
+    fn parse_paren_type(&mut self) -> PResult<Type> {
+        let mut tys = Vec::new();
+        let mut has_comma = false;
+
+        self.expect(LParen)?;
+
+        while !self.consume(RParen) {
+            tys.push(self.parse_type_expr()?);
+
+            if !self.consume(Comma) {
+                self.expect(RParen)?;
+                break;
+            }
+
+            has_comma = true;
+        }
+
+        if self.consume(Arrow) {
+            let return_ty = self.parse_type_expr()?;
+
+            return Ok(Type::func(tys, Box::new(return_ty)));
+        }
+
+        if tys.len() == 1 && !has_comma {
+            return Ok(tys.remove(0));
+        }
+
+        Ok(Type::tuple(tys))
+    }
 }
@@ -1,17 +1,62 @@
-use std::num::ParseIntError;
-use wervc_ast::Expression;
-use wervc_lexer::token::TokenKind;
+use wervc_ast::{ty::Type, Expression};
+use wervc_lexer::{error::LexError, token::TokenKind};
+use wervc_span::Span;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ParserError {
     UnexpectedToken {
         expected: TokenKind,
         actual: TokenKind,
+        span: Span,
     },
-    ParseIntError(ParseIntError),
-    RequiredSemiColon,
-    IdentAlreadyDefined(String),
-    UndefinedIdent(String),
+    InvalidInteger(String, Span),
+    IntegerOverflow(String, Span),
+    InvalidFloat(String, Span),
+    RequiredSemiColon(Span),
+    LexError(LexError),
+    InvalidChar(String, Span),
+    UnknownEscape(char, Span),
+    IdentAlreadyDefined(String, Span),
+    UndefinedIdent(String, Span),
+    BreakOutsideLoop(Span),
+    ContinueOutsideLoop(Span),
+    UndefinedLabel(String, Span),
+    UndefinedVariant(String, Span),
+    UnknownTypeName(String, Span),
+    /// コンパイル時に評価できない定数の初期値
+    NotConstant(Span),
+    /// 定数の初期値が、注釈された型と一致しない
+    ConstTypeMismatch(Type, Span),
+    /// 定数への代入
+    AssignToConst(String, Span),
     U,
-    UnexpectedExpr(Expression),
+    UnexpectedExpr(Box<Expression>),
+}
+
+impl ParserError {
+    /// エラーの原因となったソースコード上の範囲を返す
+    pub fn span(&self) -> Span {
+        match self {
+            ParserError::UnexpectedToken { span, .. }
+            | ParserError::InvalidInteger(_, span)
+            | ParserError::IntegerOverflow(_, span)
+            | ParserError::InvalidFloat(_, span)
+            | ParserError::RequiredSemiColon(span)
+            | ParserError::InvalidChar(_, span)
+            | ParserError::UnknownEscape(_, span)
+            | ParserError::IdentAlreadyDefined(_, span)
+            | ParserError::UndefinedIdent(_, span)
+            | ParserError::UndefinedLabel(_, span)
+            | ParserError::UndefinedVariant(_, span)
+            | ParserError::UnknownTypeName(_, span)
+            | ParserError::NotConstant(span)
+            | ParserError::ConstTypeMismatch(_, span)
+            | ParserError::AssignToConst(_, span)
+            | ParserError::BreakOutsideLoop(span)
+            | ParserError::ContinueOutsideLoop(span) => *span,
+            ParserError::LexError(error) => error.span(),
+            ParserError::UnexpectedExpr(expr) => expr.span(),
+            ParserError::U => Span::default(),
+        }
+    }
 }
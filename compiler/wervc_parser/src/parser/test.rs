@@ -1,6 +1,10 @@
 use super::{error::ParserError, Parser};
-use wervc_ast::{ty::Type, *};
-use wervc_lexer::token::TokenKind;
+use wervc_ast::{
+    ty::{Type, TypeKind},
+    *,
+};
+use wervc_lexer::{error::LexError, token::TokenKind};
+use wervc_span::assert_eq_ignoring_spans;
 
 fn loop_assert<T, U, const N: usize>(inputs: [T; N], expects: [U; N], f: impl Fn(&mut Parser, U))
 where
@@ -15,24 +19,168 @@ where
 
 #[test]
 fn parse_error_test() {
-    let inputs = ["{ 123", "10 10;", "let x: = 10"];
+    let inputs = [
+        "{ 123",
+        "10 10;",
+        "let x: = 10",
+        "1 + /* 2",
+        r#""abc"#,
+        r#""\q""#,
+        "'ab'",
+        "''",
+        "'1",
+        "9223372036854775808",
+        "0xFFFF_FFFF_FFFF_FFFF",
+        "0b102",
+        "0x",
+        "12ab",
+        "1.5e",
+        "'あ'",
+        "1 @ 2",
+        "let $x: int = 1;",
+        "# 1 + 2",
+        "1 + '",
+        "let y: int = y",
+        "break",
+        "while true { let f(): int = { continue; 1 }; }",
+        "'a: loop { break 'b }",
+        "'a: loop { 1 }; loop { continue 'a }",
+        "'a: if true 1",
+        "struct P { x: int, x: int }",
+        "struct P { x: int }; P { x: 1, x: 2 }",
+        "{ struct P { x: int }; }; P { x: 1 }",
+        "enum O { A, A }",
+        "enum O { A }; O::B",
+        "enum O { A }; match O::A { O::B => 1 }",
+        "match 1 { n => n }; n",
+        "let i: Idx = 1;",
+        "{ type I = int; }; let i: I = 1;",
+        "let mut f(): int = 1",
+        "let x: int = 1; const N: int = x;",
+        "const N: int = 1 / 0;",
+        "const N: int = -1; let a: int[N];",
+        "const N: int = true;",
+        "const N: int = loop {};",
+        "const N: int = { let f(n: int): int = f(n + 1); f(0) };",
+        "const N: int = 5; N = 3",
+        "const N: int = { let a: int; a };",
+    ];
     let expects = [
         ParserError::UnexpectedToken {
             expected: TokenKind::RBrace,
             actual: TokenKind::EOF,
+            span: Span::default(),
         },
-        ParserError::RequiredSemiColon,
+        ParserError::RequiredSemiColon(Span::default()),
         ParserError::UnexpectedToken {
             expected: TokenKind::Ident,
             actual: TokenKind::Assign,
+            span: Span::default(),
+        },
+        ParserError::LexError(LexError::UnterminatedComment(Span::default())),
+        ParserError::LexError(LexError::UnterminatedStr(Span::default())),
+        ParserError::UnknownEscape('q', Span::default()),
+        ParserError::InvalidChar("'ab'".to_string(), Span::default()),
+        ParserError::InvalidChar("''".to_string(), Span::default()),
+        ParserError::LexError(LexError::UnterminatedChar(Span::default())),
+        ParserError::IntegerOverflow("9223372036854775808".to_string(), Span::default()),
+        ParserError::IntegerOverflow("0xFFFF_FFFF_FFFF_FFFF".to_string(), Span::default()),
+        ParserError::InvalidInteger("0b102".to_string(), Span::default()),
+        ParserError::InvalidInteger("0x".to_string(), Span::default()),
+        ParserError::InvalidInteger("12ab".to_string(), Span::default()),
+        ParserError::InvalidFloat("1.5e".to_string(), Span::default()),
+        ParserError::InvalidChar("'あ'".to_string(), Span::default()),
+        ParserError::LexError(LexError::UnexpectedChar('@', Span::default())),
+        ParserError::LexError(LexError::UnexpectedChar('$', Span::default())),
+        ParserError::LexError(LexError::UnexpectedChar('#', Span::default())),
+        ParserError::LexError(LexError::UnterminatedChar(Span::default())),
+        ParserError::UndefinedIdent("y".to_string(), Span::default()),
+        ParserError::BreakOutsideLoop(Span::default()),
+        ParserError::ContinueOutsideLoop(Span::default()),
+        ParserError::UndefinedLabel("'b".to_string(), Span::default()),
+        ParserError::UndefinedLabel("'a".to_string(), Span::default()),
+        ParserError::UnexpectedToken {
+            expected: TokenKind::Loop,
+            actual: TokenKind::If,
+            span: Span::default(),
+        },
+        ParserError::IdentAlreadyDefined("x".to_string(), Span::default()),
+        ParserError::IdentAlreadyDefined("x".to_string(), Span::default()),
+        ParserError::UndefinedIdent("P".to_string(), Span::default()),
+        ParserError::IdentAlreadyDefined("A".to_string(), Span::default()),
+        ParserError::UndefinedVariant("B".to_string(), Span::default()),
+        ParserError::UndefinedVariant("B".to_string(), Span::default()),
+        ParserError::UndefinedIdent("n".to_string(), Span::default()),
+        ParserError::UnknownTypeName("Idx".to_string(), Span::default()),
+        ParserError::UnknownTypeName("I".to_string(), Span::default()),
+        ParserError::UnexpectedToken {
+            expected: TokenKind::Ident,
+            actual: TokenKind::Mut,
+            span: Span::default(),
         },
+        ParserError::NotConstant(Span::default()),
+        ParserError::NotConstant(Span::default()),
+        ParserError::UnexpectedExpr(Box::new(Expression::Integer(Integer {
+            value: -1,
+            span: Span::default(),
+        }))),
+        ParserError::ConstTypeMismatch(Type::int(), Span::default()),
+        ParserError::NotConstant(Span::default()),
+        ParserError::NotConstant(Span::default()),
+        ParserError::AssignToConst("N".to_string(), Span::default()),
+        ParserError::NotConstant(Span::default()),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(parser.parse_program(), Err(expect));
+        assert_eq_ignoring_spans!(parser.parse_program().unwrap_err(), expect);
     })
 }
 
+#[test]
+fn parse_recovery_test() {
+    // エラーのある文を取り除いたプログラムと、見つかるエラーの数
+    let inputs = [
+        "let x: int = ; let y: int = 2; y + ; y",
+        "1 + ; 2; } 3",
+        "let f(x: int): int = { x + ; x }; f(1)",
+        "let f(): int = { 1 + ; let y: int = 2; y }; f()",
+        "1 @ 2 +; 3",
+        "1 2 3",
+    ];
+    let expects = [
+        ("let y: int = 2; y", 2),
+        ("2; 3", 2),
+        ("let f(x: int): int = { x }; f(1)", 1),
+        ("let f(): int = { let y: int = 2; y }; f()", 1),
+        ("1 3", 3),
+        ("1 2 3", 2),
+    ];
+
+    loop_assert(inputs, expects, |parser, (expect, error_count)| {
+        let program = parser.parse_partial_program();
+        let mut expect_parser = Parser::new(expect);
+
+        assert_eq_ignoring_spans!(expect_parser.parse_partial_program(), program);
+        assert_eq!(error_count, parser.errors().len(), "{:?}", parser.errors());
+    });
+
+    let mut parser = Parser::new("1 + @; y; 3");
+    parser.parse_partial_program();
+
+    assert_eq_ignoring_spans!(
+        parser.errors(),
+        [
+            ParserError::LexError(LexError::UnexpectedChar('@', Span::default())),
+            ParserError::UnexpectedToken {
+                expected: TokenKind::False,
+                actual: TokenKind::SemiColon,
+                span: Span::default(),
+            },
+            ParserError::UndefinedIdent("y".to_string(), Span::default()),
+        ]
+    );
+}
+
 #[test]
 fn parse_stmt_test() {
     let inputs = [
@@ -48,71 +196,129 @@ fn parse_stmt_test() {
     let expects = [
         Statement::ExprStmt(Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Add,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-            rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         })),
         Statement::ExprReturnStmt(Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Add,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-            rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         })),
         Statement::ExprStmt(Expression::LetExpr(LetExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             value: Some(Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Add,
-                lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             }))),
             ty: Type::int(),
+            span: Span::default(),
         })),
         Statement::ExprReturnStmt(Expression::LetExpr(LetExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             value: Some(Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Add,
-                lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             }))),
             ty: Type::int(),
+            span: Span::default(),
         })),
         Statement::ExprStmt(Expression::BlockExpr(BlockExpr {
             statements: vec![Statement::ExprReturnStmt(Expression::Integer(Integer {
                 value: 10,
+                span: Span::default(),
             }))],
+            span: Span::default(),
         })),
         Statement::ExprReturnStmt(Expression::BlockExpr(BlockExpr {
             statements: vec![Statement::ExprReturnStmt(Expression::Integer(Integer {
                 value: 10,
+                span: Span::default(),
             }))],
+            span: Span::default(),
         })),
         Statement::ExprStmt(Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Assign,
             lhs: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             rhs: Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Add,
-                lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         })),
         Statement::ExprReturnStmt(Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Assign,
             lhs: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             rhs: Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Add,
-                lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         })),
     ];
 
@@ -122,22 +328,99 @@ fn parse_stmt_test() {
             Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
-        assert_eq!(expect, parser.parse_stmt().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_stmt().unwrap())
     });
 }
 
 #[test]
 fn parse_integer_test() {
-    let inputs = ["0", "42"];
+    let inputs = [
+        "0",
+        "42",
+        "0xFF",
+        "0o755",
+        "0b1010",
+        "1_000_000",
+        "0x_dead_beef",
+        "9223372036854775807",
+    ];
     let expects = [
-        Expression::Integer(Integer { value: 0 }),
-        Expression::Integer(Integer { value: 42 }),
+        0,
+        42,
+        0xFF,
+        0o755,
+        0b1010,
+        1_000_000,
+        0xdead_beef,
+        isize::MAX,
+    ]
+    .map(|value| {
+        Expression::Integer(Integer {
+            value,
+            span: Span::default(),
+        })
+    });
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(expect, parser.parse_integer().unwrap())
+    });
+}
+
+#[test]
+fn parse_float_test() {
+    let inputs = ["1.5", "0.25e-3", "1_000.0E1"];
+    let expects = [1.5, 0.25e-3, 1_000.0E1].map(|value| {
+        Expression::Float(Float {
+            value,
+            span: Span::default(),
+        })
+    });
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(expect, parser.parse_float().unwrap())
+    });
+}
+
+#[test]
+fn parse_str_test() {
+    let inputs = [r#""hello""#, r#""a\nb\"c\\""#, r#""""#];
+    let expects = [
+        Expression::Str(Str {
+            value: "hello".to_string(),
+            span: Span::default(),
+        }),
+        Expression::Str(Str {
+            value: "a\nb\"c\\".to_string(),
+            span: Span::default(),
+        }),
+        Expression::Str(Str {
+            value: "".to_string(),
+            span: Span::default(),
+        }),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(expect, parser.parse_integer().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_str().unwrap())
+    });
+}
+
+#[test]
+fn parse_char_test() {
+    let inputs = ["'a'", r"'\n'", r"'\''", r"'\0'"];
+    let expects = [b'a', b'\n', b'\'', b'\0'];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(
+            Expression::Char(Char {
+                value: expect,
+                span: Span::default(),
+            }),
+            parser.parse_char().unwrap()
+        )
     });
 }
 
@@ -147,31 +430,55 @@ fn parse_binary_expr_test() {
     let expects = [
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Add,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
             rhs: Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Div,
                 lhs: Box::new(Expression::BinaryExpr(BinaryExpr {
                     kind: BinaryExprKind::Mul,
                     lhs: Box::new(Expression::BinaryExpr(BinaryExpr {
                         kind: BinaryExprKind::Sub,
-                        lhs: Box::new(Expression::Integer(Integer { value: 2 })),
-                        rhs: Box::new(Expression::Integer(Integer { value: 3 })),
+                        lhs: Box::new(Expression::Integer(Integer {
+                            value: 2,
+                            span: Span::default(),
+                        })),
+                        rhs: Box::new(Expression::Integer(Integer {
+                            value: 3,
+                            span: Span::default(),
+                        })),
+                        span: Span::default(),
+                    })),
+                    rhs: Box::new(Expression::Integer(Integer {
+                        value: 4,
+                        span: Span::default(),
                     })),
-                    rhs: Box::new(Expression::Integer(Integer { value: 4 })),
+                    span: Span::default(),
                 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 5 })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 5,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Add,
             lhs: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             rhs: Box::new(Expression::Ident(Ident {
                 name: "y".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
     ];
 
@@ -181,6 +488,8 @@ fn parse_binary_expr_test() {
             Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
         parser.local_vars.register_item(
@@ -188,9 +497,86 @@ fn parse_binary_expr_test() {
             Ident {
                 name: "y".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
-        assert_eq!(expect, parser.parse_expr().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_bitwise_test() {
+    // 優先順位は括弧を補った式と同じ構文木になることで確かめる
+    let inputs = [
+        "1 | 2 ^ 3 & 4 << 5 + 6 % 7",
+        "7 % 6 + 5 << 4 & 3 ^ 2 | 1",
+        "1 & 2 == 3 | 4",
+        "1 << 2 >> 3",
+        "~1 + ~-2",
+        "1 || 2 && 3 == 4 | 5",
+        "1 && 2 || 3 && 4",
+    ];
+    let expects = [
+        "1 | (2 ^ (3 & (4 << (5 + (6 % 7)))))",
+        "((((7 % 6) + 5) << 4) & 3) ^ 2 | 1",
+        "(1 & 2) == (3 | 4)",
+        "(1 << 2) >> 3",
+        "(~1) + (~(-2))",
+        "1 || (2 && ((3 == (4 | 5))))",
+        "(1 && 2) || (3 && 4)",
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        let expect = Parser::new(expect).parse_expr().unwrap();
+
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_precedence_test() {
+    let inputs = [
+        "x = y = 1",
+        "x = y == 1 || y != 2",
+        "-~2 * 3",
+        "-x[1]",
+        "*x[1][2] + 3",
+        "f(1)(2)[3]",
+        "&x = 1 < 2",
+    ];
+    let expects = [
+        "x = (y = 1)",
+        "x = ((y == 1) || (y != 2))",
+        "(-(~2)) * 3",
+        "-(x[1])",
+        "(*((x[1])[2])) + 3",
+        "((f(1))(2))[3]",
+        "(&x) = (1 < 2)",
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        let mut expect_parser = Parser::new(expect);
+
+        for parser in [&mut *parser, &mut expect_parser] {
+            for name in ["x", "y", "f"] {
+                parser.local_vars.register_item(
+                    name.to_string(),
+                    Ident {
+                        name: name.to_string(),
+                        offset: 0,
+                        mutable: false,
+                        span: Span::default(),
+                    },
+                );
+            }
+        }
+
+        assert_eq_ignoring_spans!(
+            expect_parser.parse_expr().unwrap(),
+            parser.parse_expr().unwrap()
+        );
+        assert!(parser.peek(TokenKind::EOF));
     });
 }
 
@@ -211,47 +597,79 @@ fn parse_let_expr() {
             name: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             value: Some(Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Add,
-                lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             }))),
             ty: Type::int(),
+            span: Span::default(),
         }),
         Expression::LetExpr(LetExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "y".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
-            value: Some(Box::new(Expression::Integer(Integer { value: 0 }))),
+            value: Some(Box::new(Expression::Integer(Integer {
+                value: 0,
+                span: Span::default(),
+            }))),
             ty: Type::int(),
+            span: Span::default(),
         }),
         Expression::LetExpr(LetExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "foo_bar".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
-            value: Some(Box::new(Expression::Integer(Integer { value: 1 }))),
+            value: Some(Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            }))),
             ty: Type::int(),
+            span: Span::default(),
         }),
         Expression::LetExpr(LetExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "_123".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
-            value: Some(Box::new(Expression::Integer(Integer { value: 1 }))),
+            value: Some(Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            }))),
             ty: Type::int(),
+            span: Span::default(),
         }),
         Expression::FunctionDefExpr(FunctionDefExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "id".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             params: vec![(
                 Expression::Ident(Ident {
                     name: "x".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 }),
                 Type::int(),
             )],
@@ -259,18 +677,25 @@ fn parse_let_expr() {
             body: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::FunctionDefExpr(FunctionDefExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "add".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             params: vec![
                 (
                     Expression::Ident(Ident {
                         name: "x".to_string(),
                         offset: 0,
+                        mutable: false,
+                        span: Span::default(),
                     }),
                     Type::int(),
                 ),
@@ -278,6 +703,8 @@ fn parse_let_expr() {
                     Expression::Ident(Ident {
                         name: "y".to_string(),
                         offset: 0,
+                        mutable: false,
+                        span: Span::default(),
                     }),
                     Type::int(),
                 ),
@@ -288,40 +715,108 @@ fn parse_let_expr() {
                 lhs: Box::new(Expression::Ident(Ident {
                     name: "x".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
                 rhs: Box::new(Expression::Ident(Ident {
                     name: "y".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::FunctionDefExpr(FunctionDefExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "zero".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             return_ty: Type::int(),
             params: vec![],
-            body: Box::new(Expression::Integer(Integer { value: 0 })),
+            body: Box::new(Expression::Integer(Integer {
+                value: 0,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::LetExpr(LetExpr {
             name: Box::new(Expression::Ident(Ident {
                 name: "arr".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             value: Some(Box::new(Expression::Array(Array {
                 elements: vec![
-                    Expression::Integer(Integer { value: 1 }),
-                    Expression::Integer(Integer { value: 2 }),
-                    Expression::Integer(Integer { value: 3 }),
+                    Expression::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    }),
+                    Expression::Integer(Integer {
+                        value: 2,
+                        span: Span::default(),
+                    }),
+                    Expression::Integer(Integer {
+                        value: 3,
+                        span: Span::default(),
+                    }),
                 ],
+                span: Span::default(),
             }))),
             ty: Type::array(Box::new(Type::int()), 3),
+            span: Span::default(),
         }),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(expect, parser.parse_let_expr().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_let_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_let_mut_test() {
+    let inputs = ["let mut x: int = 1; x = 2"];
+    let expects = [vec![
+        Statement::ExprStmt(Expression::LetExpr(LetExpr {
+            name: Box::new(Expression::Ident(Ident {
+                name: "x".to_string(),
+                offset: 0,
+                mutable: true,
+                span: Span::default(),
+            })),
+            value: Some(Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            }))),
+            ty: Type::int(),
+            span: Span::default(),
+        })),
+        // 参照している箇所も`mutable`になる
+        Statement::ExprReturnStmt(Expression::BinaryExpr(BinaryExpr {
+            kind: BinaryExprKind::Assign,
+            lhs: Box::new(Expression::Ident(Ident {
+                name: "x".to_string(),
+                offset: 0,
+                mutable: true,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        })),
+    ]];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(
+            Node::Program(Program { statements: expect }),
+            parser.parse_program().unwrap()
+        )
     });
 }
 
@@ -338,7 +833,9 @@ fn parse_block_expr() {
         Expression::BlockExpr(BlockExpr {
             statements: vec![Statement::ExprReturnStmt(Expression::Integer(Integer {
                 value: 10,
+                span: Span::default(),
             }))],
+            span: Span::default(),
         }),
         Expression::BlockExpr(BlockExpr {
             statements: vec![
@@ -346,49 +843,76 @@ fn parse_block_expr() {
                     name: Box::new(Expression::Ident(Ident {
                         name: "x".to_string(),
                         offset: 0,
+                        mutable: false,
+                        span: Span::default(),
                     })),
-                    value: Some(Box::new(Expression::Integer(Integer { value: 10 }))),
+                    value: Some(Box::new(Expression::Integer(Integer {
+                        value: 10,
+                        span: Span::default(),
+                    }))),
                     ty: Type::int(),
+                    span: Span::default(),
                 })),
                 Statement::ExprReturnStmt(Expression::Ident(Ident {
                     name: "x".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
             ],
+            span: Span::default(),
         }),
         Expression::BlockExpr(BlockExpr {
             statements: vec![Statement::ExprStmt(Expression::LetExpr(LetExpr {
                 name: Box::new(Expression::Ident(Ident {
                     name: "x".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
-                value: Some(Box::new(Expression::Integer(Integer { value: 10 }))),
+                value: Some(Box::new(Expression::Integer(Integer {
+                    value: 10,
+                    span: Span::default(),
+                }))),
                 ty: Type::int(),
+                span: Span::default(),
             }))],
+            span: Span::default(),
         }),
         Expression::BlockExpr(BlockExpr {
             statements: vec![Statement::ExprReturnStmt(Expression::LetExpr(LetExpr {
                 name: Box::new(Expression::Ident(Ident {
                     name: "x".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
                 value: Some(Box::new(Expression::BlockExpr(BlockExpr {
                     statements: vec![Statement::ExprReturnStmt(Expression::Integer(Integer {
                         value: 10,
+                        span: Span::default(),
                     }))],
+                    span: Span::default(),
                 }))),
                 ty: Type::int(),
+                span: Span::default(),
             }))],
+            span: Span::default(),
         }),
         Expression::BlockExpr(BlockExpr {
             statements: vec![Statement::ExprStmt(Expression::ReturnExpr(ReturnExpr {
-                value: Box::new(Expression::Integer(Integer { value: 10 })),
+                value: Box::new(Expression::Integer(Integer {
+                    value: 10,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             }))],
+            span: Span::default(),
         }),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(expect, parser.parse_block_expr().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_block_expr().unwrap())
     });
 }
 
@@ -401,35 +925,55 @@ fn parse_assign_test() {
             lhs: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             rhs: Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Add,
-                lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Assign,
             lhs: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             rhs: Box::new(Expression::Ident(Ident {
                 name: "y".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Assign,
             lhs: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             rhs: Box::new(Expression::BlockExpr(BlockExpr {
                 statements: vec![Statement::ExprReturnStmt(Expression::Integer(Integer {
                     value: 10,
+                    span: Span::default(),
                 }))],
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
     ];
 
@@ -439,6 +983,8 @@ fn parse_assign_test() {
             Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
         parser.local_vars.register_item(
@@ -446,9 +992,11 @@ fn parse_assign_test() {
             Ident {
                 name: "y".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
-        assert_eq!(expect, parser.parse_assign().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
     });
 }
 
@@ -457,22 +1005,39 @@ fn parse_call_test() {
     let inputs = ["foo()", "foo(1,2,3)"];
     let expects = [
         Expression::CallExpr(CallExpr {
+            offset: 0,
             func: Box::new(Expression::Ident(Ident {
                 name: "foo".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             args: vec![],
+            span: Span::default(),
         }),
         Expression::CallExpr(CallExpr {
+            offset: 0,
             func: Box::new(Expression::Ident(Ident {
                 name: "foo".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
             args: vec![
-                Expression::Integer(Integer { value: 1 }),
-                Expression::Integer(Integer { value: 2 }),
-                Expression::Integer(Integer { value: 3 }),
+                Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                }),
+                Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                }),
+                Expression::Integer(Integer {
+                    value: 3,
+                    span: Span::default(),
+                }),
             ],
+            span: Span::default(),
         }),
     ];
 
@@ -482,9 +1047,11 @@ fn parse_call_test() {
             Ident {
                 name: "foo".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
-        assert_eq!(expect, parser.parse_call().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
     });
 }
 
@@ -494,38 +1061,80 @@ fn parse_relation_test() {
     let expects = [
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Lt,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-            rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Le,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-            rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Gt,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-            rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Ge,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-            rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Eq,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-            rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::BinaryExpr(BinaryExpr {
             kind: BinaryExprKind::Ne,
-            lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-            rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+            lhs: Box::new(Expression::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            })),
+            rhs: Box::new(Expression::Integer(Integer {
+                value: 2,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(expect, parser.parse_relation().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
     });
 }
 
@@ -540,42 +1149,71 @@ fn parse_if_expr_test() {
         Expression::IfExpr(IfExpr {
             condition: Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Lt,
-                lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
             consequence: Box::new(Expression::BlockExpr(BlockExpr {
                 statements: vec![Statement::ExprReturnStmt(Expression::Integer(Integer {
                     value: 10,
+                    span: Span::default(),
                 }))],
+                span: Span::default(),
             })),
             alternative: Some(Box::new(Expression::BlockExpr(BlockExpr {
                 statements: vec![Statement::ExprReturnStmt(Expression::Integer(Integer {
                     value: 20,
+                    span: Span::default(),
                 }))],
+                span: Span::default(),
             }))),
+            span: Span::default(),
         }),
         Expression::IfExpr(IfExpr {
             condition: Box::new(Expression::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Lt,
-                lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
             consequence: Box::new(Expression::BlockExpr(BlockExpr {
                 statements: vec![Statement::ExprReturnStmt(Expression::Integer(Integer {
                     value: 10,
+                    span: Span::default(),
                 }))],
+                span: Span::default(),
             })),
             alternative: None,
+            span: Span::default(),
         }),
         Expression::IfExpr(IfExpr {
-            condition: Box::new(Expression::Boolean(Boolean { value: true })),
-            consequence: Box::new(Expression::Boolean(Boolean { value: false })),
+            condition: Box::new(Expression::Boolean(Boolean {
+                value: true,
+                span: Span::default(),
+            })),
+            consequence: Box::new(Expression::Boolean(Boolean {
+                value: false,
+                span: Span::default(),
+            })),
             alternative: None,
+            span: Span::default(),
         }),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(expect, parser.parse_if_expr().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_if_expr().unwrap())
     });
 }
 
@@ -583,12 +1221,18 @@ fn parse_if_expr_test() {
 fn parse_bool_test() {
     let inputs = ["true", "false"];
     let expects = [
-        Expression::Boolean(Boolean { value: true }),
-        Expression::Boolean(Boolean { value: false }),
+        Expression::Boolean(Boolean {
+            value: true,
+            span: Span::default(),
+        }),
+        Expression::Boolean(Boolean {
+            value: false,
+            span: Span::default(),
+        }),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(expect, parser.parse_bool().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_bool().unwrap())
     });
 }
 
@@ -597,15 +1241,789 @@ fn parse_return_expr_test() {
     let inputs = ["return 10", "return true"];
     let expects = [
         Expression::ReturnExpr(ReturnExpr {
-            value: Box::new(Expression::Integer(Integer { value: 10 })),
+            value: Box::new(Expression::Integer(Integer {
+                value: 10,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::ReturnExpr(ReturnExpr {
-            value: Box::new(Expression::Boolean(Boolean { value: true })),
+            value: Box::new(Expression::Boolean(Boolean {
+                value: true,
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(expect, parser.parse_return_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_while_expr_test() {
+    let inputs = ["while true { break }", "while 1 < 2 continue"];
+    let expects = [
+        Expression::WhileExpr(WhileExpr {
+            label: None,
+            condition: Box::new(Expression::Boolean(Boolean {
+                value: true,
+                span: Span::default(),
+            })),
+            body: Box::new(Expression::BlockExpr(BlockExpr {
+                statements: vec![Statement::ExprReturnStmt(Expression::BreakExpr(
+                    BreakExpr {
+                        label: None,
+                        value: None,
+                        span: Span::default(),
+                    },
+                ))],
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+        Expression::WhileExpr(WhileExpr {
+            label: None,
+            condition: Box::new(Expression::BinaryExpr(BinaryExpr {
+                kind: BinaryExprKind::Lt,
+                lhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
+            })),
+            body: Box::new(Expression::ContinueExpr(ContinueExpr {
+                label: None,
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_for_expr_test() {
+    let inputs = [
+        "for i in 0..10 i",
+        "for i in 1..=3 { break }",
+        "for x in [1, 2] x",
+    ];
+    let expects = [
+        Expression::ForExpr(ForExpr {
+            label: None,
+            var: Box::new(Expression::Ident(Ident {
+                name: "i".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            })),
+            iterable: Iterable::Range {
+                start: Box::new(Expression::Integer(Integer {
+                    value: 0,
+                    span: Span::default(),
+                })),
+                end: Box::new(Expression::Integer(Integer {
+                    value: 10,
+                    span: Span::default(),
+                })),
+                inclusive: false,
+            },
+            body: Box::new(Expression::Ident(Ident {
+                name: "i".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+        Expression::ForExpr(ForExpr {
+            label: None,
+            var: Box::new(Expression::Ident(Ident {
+                name: "i".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            })),
+            iterable: Iterable::Range {
+                start: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                end: Box::new(Expression::Integer(Integer {
+                    value: 3,
+                    span: Span::default(),
+                })),
+                inclusive: true,
+            },
+            body: Box::new(Expression::BlockExpr(BlockExpr {
+                statements: vec![Statement::ExprReturnStmt(Expression::BreakExpr(
+                    BreakExpr {
+                        label: None,
+                        value: None,
+                        span: Span::default(),
+                    },
+                ))],
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+        Expression::ForExpr(ForExpr {
+            label: None,
+            var: Box::new(Expression::Ident(Ident {
+                name: "x".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            })),
+            iterable: Iterable::Array(Box::new(Expression::Array(Array {
+                elements: vec![
+                    Expression::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    }),
+                    Expression::Integer(Integer {
+                        value: 2,
+                        span: Span::default(),
+                    }),
+                ],
+                span: Span::default(),
+            }))),
+            body: Box::new(Expression::Ident(Ident {
+                name: "x".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_loop_expr_test() {
+    let inputs = [
+        "loop { break 1 }",
+        "'outer: while true loop { continue 'outer }",
+        "'a: for i in 0..3 break 'a",
+    ];
+    let expects = [
+        Expression::LoopExpr(LoopExpr {
+            label: None,
+            body: Box::new(Expression::BlockExpr(BlockExpr {
+                statements: vec![Statement::ExprReturnStmt(Expression::BreakExpr(
+                    BreakExpr {
+                        label: None,
+                        value: Some(Box::new(Expression::Integer(Integer {
+                            value: 1,
+                            span: Span::default(),
+                        }))),
+                        span: Span::default(),
+                    },
+                ))],
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+        Expression::WhileExpr(WhileExpr {
+            label: Some("'outer".to_string()),
+            condition: Box::new(Expression::Boolean(Boolean {
+                value: true,
+                span: Span::default(),
+            })),
+            body: Box::new(Expression::LoopExpr(LoopExpr {
+                label: None,
+                body: Box::new(Expression::BlockExpr(BlockExpr {
+                    statements: vec![Statement::ExprReturnStmt(Expression::ContinueExpr(
+                        ContinueExpr {
+                            label: Some("'outer".to_string()),
+                            span: Span::default(),
+                        },
+                    ))],
+                    span: Span::default(),
+                })),
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+        Expression::ForExpr(ForExpr {
+            label: Some("'a".to_string()),
+            var: Box::new(Expression::Ident(Ident {
+                name: "i".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            })),
+            iterable: Iterable::Range {
+                start: Box::new(Expression::Integer(Integer {
+                    value: 0,
+                    span: Span::default(),
+                })),
+                end: Box::new(Expression::Integer(Integer {
+                    value: 3,
+                    span: Span::default(),
+                })),
+                inclusive: false,
+            },
+            body: Box::new(Expression::BreakExpr(BreakExpr {
+                label: Some("'a".to_string()),
+                value: None,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(expect, parser.parse_return_expr().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_lambda_test() {
+    let inputs = ["fn(x: int): int => x", "fn(f: (int, *char) -> bool) => f"];
+    let expects = [
+        Expression::LambdaExpr(LambdaExpr {
+            params: vec![(
+                Expression::Ident(Ident {
+                    name: "x".to_string(),
+                    offset: 0,
+                    mutable: false,
+                    span: Span::default(),
+                }),
+                Type::int(),
+            )],
+            return_ty: Type::int(),
+            body: Box::new(Expression::Ident(Ident {
+                name: "x".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+        Expression::LambdaExpr(LambdaExpr {
+            params: vec![(
+                Expression::Ident(Ident {
+                    name: "f".to_string(),
+                    offset: 0,
+                    mutable: false,
+                    span: Span::default(),
+                }),
+                Type::func(
+                    vec![Type::int(), Type::pointer_to(Box::new(Type::char()))],
+                    Box::new(Type::bool()),
+                ),
+            )],
+            return_ty: Type::unknown(),
+            body: Box::new(Expression::Ident(Ident {
+                name: "f".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }),
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_struct_test() {
+    let point = Type::structure(
+        "P",
+        vec![
+            ("x".to_string(), Type::int()),
+            ("c".to_string(), Type::char()),
+        ],
+    );
+    let inputs = [
+        "struct P { x: int, c: char, }",
+        "struct P { x: int, c: char }; let p: P = P { c: 'a', x: 1 }; p.x",
+    ];
+    let expects = [
+        vec![Statement::ExprReturnStmt(Expression::StructDefExpr(
+            StructDefExpr {
+                name: "P".to_string(),
+                ty: point.clone(),
+                span: Span::default(),
+            },
+        ))],
+        vec![
+            Statement::ExprStmt(Expression::StructDefExpr(StructDefExpr {
+                name: "P".to_string(),
+                ty: point.clone(),
+                span: Span::default(),
+            })),
+            Statement::ExprStmt(Expression::LetExpr(LetExpr {
+                name: Box::new(Expression::Ident(Ident {
+                    name: "p".to_string(),
+                    offset: 0,
+                    mutable: false,
+                    span: Span::default(),
+                })),
+                value: Some(Box::new(Expression::StructLiteral(StructLiteral {
+                    ty: point.clone(),
+                    fields: vec![
+                        (
+                            "c".to_string(),
+                            Expression::Char(Char {
+                                value: b'a',
+                                span: Span::default(),
+                            }),
+                        ),
+                        (
+                            "x".to_string(),
+                            Expression::Integer(Integer {
+                                value: 1,
+                                span: Span::default(),
+                            }),
+                        ),
+                    ],
+                    offset: 0,
+                    span: Span::default(),
+                }))),
+                ty: point.clone(),
+                span: Span::default(),
+            })),
+            Statement::ExprReturnStmt(Expression::FieldExpr(FieldExpr {
+                expr: Box::new(Expression::Ident(Ident {
+                    name: "p".to_string(),
+                    offset: 0,
+                    mutable: false,
+                    span: Span::default(),
+                })),
+                field: "x".to_string(),
+                span: Span::default(),
+            })),
+        ],
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(
+            Node::Program(Program { statements: expect }),
+            parser.parse_program().unwrap()
+        )
+    });
+}
+
+#[test]
+fn parse_tuple_test() {
+    let ident = |name: &str| {
+        Expression::Ident(Ident {
+            name: name.to_string(),
+            offset: 0,
+            mutable: false,
+            span: Span::default(),
+        })
+    };
+    let integer = |value: isize| {
+        Expression::Integer(Integer {
+            value,
+            span: Span::default(),
+        })
+    };
+    let tuple = |elements: Vec<Expression>| {
+        Expression::TupleExpr(TupleExpr {
+            elements,
+            offset: 0,
+            span: Span::default(),
+        })
+    };
+    let field = |expr: Expression, field: &str| {
+        Expression::FieldExpr(FieldExpr {
+            expr: Box::new(expr),
+            field: field.to_string(),
+            span: Span::default(),
+        })
+    };
+    let pair = Type::tuple(vec![Type::int(), Type::bool()]);
+    let nested = Type::tuple(vec![
+        Type::int(),
+        Type::tuple(vec![Type::int(), Type::int()]),
+    ]);
+    let inputs = [
+        "let (q, r): (int, bool) = (1, true,); q",
+        "let t: (int, (int, int)) = (1, (2, 3)); t.1.0",
+        "let t: (int,) = (1,); (t.0)",
+    ];
+    let expects = [
+        vec![
+            Statement::ExprStmt(Expression::LetExpr(LetExpr {
+                name: Box::new(tuple(vec![ident("q"), ident("r")])),
+                value: Some(Box::new(tuple(vec![
+                    integer(1),
+                    Expression::Boolean(Boolean {
+                        value: true,
+                        span: Span::default(),
+                    }),
+                ]))),
+                ty: pair,
+                span: Span::default(),
+            })),
+            Statement::ExprReturnStmt(ident("q")),
+        ],
+        vec![
+            Statement::ExprStmt(Expression::LetExpr(LetExpr {
+                name: Box::new(ident("t")),
+                value: Some(Box::new(tuple(vec![
+                    integer(1),
+                    tuple(vec![integer(2), integer(3)]),
+                ]))),
+                ty: nested,
+                span: Span::default(),
+            })),
+            Statement::ExprReturnStmt(field(field(ident("t"), "1"), "0")),
+        ],
+        vec![
+            Statement::ExprStmt(Expression::LetExpr(LetExpr {
+                name: Box::new(ident("t")),
+                value: Some(Box::new(tuple(vec![integer(1)]))),
+                ty: Type::tuple(vec![Type::int()]),
+                span: Span::default(),
+            })),
+            Statement::ExprReturnStmt(field(ident("t"), "0")),
+        ],
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(
+            Node::Program(Program { statements: expect }),
+            parser.parse_program().unwrap()
+        )
+    });
+}
+
+#[test]
+fn parse_type_alias_test() {
+    let index = Type::alias("Index", Box::new(Type::int()));
+    let point = Type::structure("P", vec![("x".to_string(), Type::int())]);
+    let inputs = [
+        "type Index = int; let i: Index = 1;",
+        "struct P { x: int }; type Q = P; Q { x: 1 }",
+    ];
+    let expects = [
+        vec![
+            Statement::ExprStmt(Expression::TypeAliasExpr(TypeAliasExpr {
+                name: "Index".to_string(),
+                ty: index.clone(),
+                span: Span::default(),
+            })),
+            Statement::ExprStmt(Expression::LetExpr(LetExpr {
+                name: Box::new(Expression::Ident(Ident {
+                    name: "i".to_string(),
+                    offset: 0,
+                    mutable: false,
+                    span: Span::default(),
+                })),
+                value: Some(Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                }))),
+                ty: index,
+                span: Span::default(),
+            })),
+        ],
+        vec![
+            Statement::ExprStmt(Expression::StructDefExpr(StructDefExpr {
+                name: "P".to_string(),
+                ty: point.clone(),
+                span: Span::default(),
+            })),
+            Statement::ExprStmt(Expression::TypeAliasExpr(TypeAliasExpr {
+                name: "Q".to_string(),
+                ty: Type::alias("Q", Box::new(point.clone())),
+                span: Span::default(),
+            })),
+            Statement::ExprReturnStmt(Expression::StructLiteral(StructLiteral {
+                ty: point,
+                fields: vec![(
+                    "x".to_string(),
+                    Expression::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    }),
+                )],
+                offset: 0,
+                span: Span::default(),
+            })),
+        ],
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        let program = parser.parse_program().unwrap();
+        let Node::Program(Program { statements }) = &program else {
+            unreachable!();
+        };
+
+        // 別名は元の型と等しいものとして比べられるので、別名の名前が残っていることは別に確かめる
+        assert!(statements.iter().any(|stmt| matches!(
+            stmt,
+            Statement::ExprStmt(Expression::TypeAliasExpr(TypeAliasExpr {
+                ty: Type {
+                    kind: TypeKind::Alias { .. }
+                },
+                ..
+            }))
+        )));
+        assert_eq_ignoring_spans!(Node::Program(Program { statements: expect }), program)
+    });
+}
+
+#[test]
+fn parse_const_test() {
+    let inputs = [
+        "const N: int = 4 * 16; let a: int[N]; N",
+        "const N: int = 2; const M: int = N + 1; { let N: int = M; N }",
+        "const N: int = 1; let x: int = 2; N + x = 3",
+        "const N: int = 9223372036854775807 + 1;",
+    ];
+    let expects = [
+        vec![
+            Statement::ExprStmt(Expression::ConstExpr(ConstExpr {
+                name: "N".to_string(),
+                ty: Type::int(),
+                value: Box::new(Expression::Integer(Integer {
+                    value: 64,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
+            })),
+            Statement::ExprStmt(Expression::LetExpr(LetExpr {
+                name: Box::new(Expression::Ident(Ident {
+                    name: "a".to_string(),
+                    offset: 0,
+                    mutable: false,
+                    span: Span::default(),
+                })),
+                value: None,
+                ty: Type::array(Box::new(Type::int()), 64),
+                span: Span::default(),
+            })),
+            // 定数を参照している箇所は値のリテラルになる
+            Statement::ExprReturnStmt(Expression::Integer(Integer {
+                value: 64,
+                span: Span::default(),
+            })),
+        ],
+        vec![
+            Statement::ExprStmt(Expression::ConstExpr(ConstExpr {
+                name: "N".to_string(),
+                ty: Type::int(),
+                value: Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
+            })),
+            Statement::ExprStmt(Expression::ConstExpr(ConstExpr {
+                name: "M".to_string(),
+                ty: Type::int(),
+                value: Box::new(Expression::Integer(Integer {
+                    value: 3,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
+            })),
+            // 同じ名前の変数は定数を隠す
+            Statement::ExprReturnStmt(Expression::BlockExpr(BlockExpr {
+                statements: vec![
+                    Statement::ExprStmt(Expression::LetExpr(LetExpr {
+                        name: Box::new(Expression::Ident(Ident {
+                            name: "N".to_string(),
+                            offset: 0,
+                            mutable: false,
+                            span: Span::default(),
+                        })),
+                        value: Some(Box::new(Expression::Integer(Integer {
+                            value: 3,
+                            span: Span::default(),
+                        }))),
+                        ty: Type::int(),
+                        span: Span::default(),
+                    })),
+                    Statement::ExprReturnStmt(Expression::Ident(Ident {
+                        name: "N".to_string(),
+                        offset: 0,
+                        mutable: false,
+                        span: Span::default(),
+                    })),
+                ],
+                span: Span::default(),
+            })),
+        ],
+        vec![
+            Statement::ExprStmt(Expression::ConstExpr(ConstExpr {
+                name: "N".to_string(),
+                ty: Type::int(),
+                value: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
+            })),
+            Statement::ExprStmt(Expression::LetExpr(LetExpr {
+                name: Box::new(Expression::Ident(Ident {
+                    name: "x".to_string(),
+                    offset: 0,
+                    mutable: false,
+                    span: Span::default(),
+                })),
+                value: Some(Box::new(Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                }))),
+                ty: Type::int(),
+                span: Span::default(),
+            })),
+            // 定数で始まるだけの左辺は、定数への代入ではない
+            Statement::ExprReturnStmt(Expression::BinaryExpr(BinaryExpr {
+                kind: BinaryExprKind::Assign,
+                lhs: Box::new(Expression::BinaryExpr(BinaryExpr {
+                    kind: BinaryExprKind::Add,
+                    lhs: Box::new(Expression::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    })),
+                    rhs: Box::new(Expression::Ident(Ident {
+                        name: "x".to_string(),
+                        offset: 0,
+                        mutable: false,
+                        span: Span::default(),
+                    })),
+                    span: Span::default(),
+                })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 3,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
+            })),
+        ],
+        // 溢れた値はコンパイル後と同じく折り返す
+        vec![Statement::ExprStmt(Expression::ConstExpr(ConstExpr {
+            name: "N".to_string(),
+            ty: Type::int(),
+            value: Box::new(Expression::Integer(Integer {
+                value: isize::MIN,
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        }))],
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(
+            Node::Program(Program { statements: expect }),
+            parser.parse_program().unwrap()
+        )
+    });
+}
+
+#[test]
+fn parse_match_test() {
+    let option = Type::enumeration(
+        "O",
+        vec![
+            ("Some".to_string(), vec![Type::int()]),
+            ("None".to_string(), vec![]),
+        ],
+    );
+    let inputs =
+        ["enum O { Some(int), None }; match O::Some(1) { O::Some(n) if n > 0 => n, _ => -1 }"];
+    let expects = [vec![
+        Statement::ExprStmt(Expression::EnumDefExpr(EnumDefExpr {
+            name: "O".to_string(),
+            ty: option.clone(),
+            span: Span::default(),
+        })),
+        Statement::ExprReturnStmt(Expression::MatchExpr(MatchExpr {
+            expr: Box::new(Expression::EnumVariantExpr(EnumVariantExpr {
+                ty: option.clone(),
+                variant: "Some".to_string(),
+                args: vec![Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })],
+                offset: 0,
+                span: Span::default(),
+            })),
+            arms: vec![
+                MatchArm {
+                    pattern: Pattern::Variant {
+                        ty: option.clone(),
+                        variant: "Some".to_string(),
+                        fields: vec![Pattern::Binding {
+                            ident: Box::new(Expression::Ident(Ident {
+                                name: "n".to_string(),
+                                offset: 0,
+                                mutable: false,
+                                span: Span::default(),
+                            })),
+                            span: Span::default(),
+                        }],
+                        span: Span::default(),
+                    },
+                    guard: Some(Box::new(Expression::BinaryExpr(BinaryExpr {
+                        kind: BinaryExprKind::Gt,
+                        lhs: Box::new(Expression::Ident(Ident {
+                            name: "n".to_string(),
+                            offset: 0,
+                            mutable: false,
+                            span: Span::default(),
+                        })),
+                        rhs: Box::new(Expression::Integer(Integer {
+                            value: 0,
+                            span: Span::default(),
+                        })),
+                        span: Span::default(),
+                    }))),
+                    body: Box::new(Expression::Ident(Ident {
+                        name: "n".to_string(),
+                        offset: 0,
+                        mutable: false,
+                        span: Span::default(),
+                    })),
+                    span: Span::default(),
+                },
+                MatchArm {
+                    pattern: Pattern::Wildcard(Span::default()),
+                    guard: None,
+                    body: Box::new(Expression::UnaryExpr(UnaryExpr {
+                        kind: UnaryExprKind::Minus,
+                        expr: Box::new(Expression::Integer(Integer {
+                            value: 1,
+                            span: Span::default(),
+                        })),
+                        span: Span::default(),
+                    })),
+                    span: Span::default(),
+                },
+            ],
+            span: Span::default(),
+        })),
+    ]];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(
+            Node::Program(Program { statements: expect }),
+            parser.parse_program().unwrap()
+        )
     });
 }
 
@@ -617,39 +2035,63 @@ fn parse_unary_test() {
     let expects = [
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Not,
-            expr: Box::new(Expression::Boolean(Boolean { value: true })),
+            expr: Box::new(Expression::Boolean(Boolean {
+                value: true,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Minus,
-            expr: Box::new(Expression::Integer(Integer { value: 10 })),
+            expr: Box::new(Expression::Integer(Integer {
+                value: 10,
+                span: Span::default(),
+            })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Not,
             expr: Box::new(Expression::UnaryExpr(UnaryExpr {
                 kind: UnaryExprKind::Not,
-                expr: Box::new(Expression::Boolean(Boolean { value: true })),
+                expr: Box::new(Expression::Boolean(Boolean {
+                    value: true,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Minus,
             expr: Box::new(Expression::UnaryExpr(UnaryExpr {
                 kind: UnaryExprKind::Minus,
-                expr: Box::new(Expression::Integer(Integer { value: 10 })),
+                expr: Box::new(Expression::Integer(Integer {
+                    value: 10,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Addr,
             expr: Box::new(Expression::Ident(Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Deref,
             expr: Box::new(Expression::Ident(Ident {
                 name: "p".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Deref,
@@ -658,8 +2100,12 @@ fn parse_unary_test() {
                 expr: Box::new(Expression::Ident(Ident {
                     name: "p".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Deref,
@@ -676,12 +2122,20 @@ fn parse_unary_test() {
                                 expr: Box::new(Expression::Ident(Ident {
                                     name: "q".to_string(),
                                     offset: 0,
+                                    mutable: false,
+                                    span: Span::default(),
                                 })),
+                                span: Span::default(),
                             })),
+                            span: Span::default(),
                         })),
+                        span: Span::default(),
                     })),
+                    span: Span::default(),
                 })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Addr,
@@ -698,12 +2152,20 @@ fn parse_unary_test() {
                                 expr: Box::new(Expression::Ident(Ident {
                                     name: "p".to_string(),
                                     offset: 0,
+                                    mutable: false,
+                                    span: Span::default(),
                                 })),
+                                span: Span::default(),
                             })),
+                            span: Span::default(),
                         })),
+                        span: Span::default(),
                     })),
+                    span: Span::default(),
                 })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
     ];
 
@@ -713,6 +2175,8 @@ fn parse_unary_test() {
             Ident {
                 name: "x".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
         parser.local_vars.register_item(
@@ -720,6 +2184,8 @@ fn parse_unary_test() {
             Ident {
                 name: "p".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
         parser.local_vars.register_item(
@@ -727,10 +2193,12 @@ fn parse_unary_test() {
             Ident {
                 name: "q".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
 
-        assert_eq!(expect, parser.parse_unary().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_unary().unwrap())
     });
 }
 
@@ -740,16 +2208,29 @@ fn parse_array_test() {
     let expects = [
         Expression::Array(Array {
             elements: vec![
-                Expression::Integer(Integer { value: 1 }),
-                Expression::Integer(Integer { value: 2 }),
-                Expression::Integer(Integer { value: 3 }),
+                Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                }),
+                Expression::Integer(Integer {
+                    value: 2,
+                    span: Span::default(),
+                }),
+                Expression::Integer(Integer {
+                    value: 3,
+                    span: Span::default(),
+                }),
             ],
+            span: Span::default(),
+        }),
+        Expression::Array(Array {
+            elements: vec![],
+            span: Span::default(),
         }),
-        Expression::Array(Array { elements: vec![] }),
     ];
 
     loop_assert(inputs, expects, |parser, expect| {
-        assert_eq!(expect, parser.parse_array().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_array().unwrap())
     });
 }
 
@@ -764,9 +2245,16 @@ fn parse_index_test() {
                 lhs: Box::new(Expression::Ident(Ident {
                     name: "array".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
-                rhs: Box::new(Expression::Integer(Integer { value: 1 })),
+                rhs: Box::new(Expression::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
         Expression::UnaryExpr(UnaryExpr {
             kind: UnaryExprKind::Deref,
@@ -775,13 +2263,24 @@ fn parse_index_test() {
                 lhs: Box::new(Expression::Ident(Ident {
                     name: "array".to_string(),
                     offset: 0,
+                    mutable: false,
+                    span: Span::default(),
                 })),
                 rhs: Box::new(Expression::BinaryExpr(BinaryExpr {
                     kind: BinaryExprKind::Add,
-                    lhs: Box::new(Expression::Integer(Integer { value: 1 })),
-                    rhs: Box::new(Expression::Integer(Integer { value: 2 })),
+                    lhs: Box::new(Expression::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    })),
+                    rhs: Box::new(Expression::Integer(Integer {
+                        value: 2,
+                        span: Span::default(),
+                    })),
+                    span: Span::default(),
                 })),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }),
     ];
 
@@ -791,8 +2290,75 @@ fn parse_index_test() {
             Ident {
                 name: "array".to_string(),
                 offset: 0,
+                mutable: false,
+                span: Span::default(),
             },
         );
-        assert_eq!(expect, parser.parse_index().unwrap())
+        assert_eq_ignoring_spans!(expect, parser.parse_expr().unwrap())
     });
 }
+
+#[test]
+fn parse_type_test() {
+    let array = |ty: Type, length: isize| Type::array(Box::new(ty), length);
+    let pointer = |ty: Type| Type::pointer_to(Box::new(ty));
+    let inputs = [
+        "int[2][3]",
+        "*int[4]",
+        "*(int[4])",
+        "(*int)[2][3]",
+        "(int[2])[3]",
+        "**char",
+        "*(int[3])[2]",
+    ];
+    let expects = [
+        array(array(Type::int(), 3), 2),
+        array(pointer(Type::int()), 4),
+        pointer(array(Type::int(), 4)),
+        array(array(pointer(Type::int()), 3), 2),
+        array(array(Type::int(), 2), 3),
+        pointer(pointer(Type::char())),
+        array(pointer(array(Type::int(), 3)), 2),
+    ];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        assert_eq_ignoring_spans!(expect, parser.parse_type_expr().unwrap())
+    });
+}
+
+#[test]
+fn parse_span_test() {
+    let inputs = ["let x: int = 1 + 2", "foo(1)", "-10", "{ 10 }"];
+    let expects = [(0, 18), (0, 6), (0, 3), (0, 6)];
+
+    loop_assert(inputs, expects, |parser, expect| {
+        parser.local_vars.register_item(
+            "foo".to_string(),
+            Ident {
+                name: "foo".to_string(),
+                offset: 0,
+                mutable: false,
+                span: Span::default(),
+            },
+        );
+
+        let span = parser.parse_expr().unwrap().span();
+
+        assert_eq!(expect, (span.start, span.end));
+    });
+
+    let mut parser = Parser::new("let x: int = 1;\nx + y");
+    let Err(error) = parser.parse_program() else {
+        panic!("expected an error");
+    };
+    let span = error.span();
+
+    assert_eq!(
+        ParserError::UndefinedIdent("y".to_string(), Span::new(20, 21, 2, 5)),
+        error
+    );
+    assert_eq!(
+        (20, 21, 2, 5),
+        (span.start, span.end, span.line, span.column)
+    );
+}
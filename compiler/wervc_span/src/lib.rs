@@ -0,0 +1,70 @@
+/// ソースコード上の範囲を表す
+///
+/// `start`と`end`はバイトオフセット、`line`と`column`は開始位置の行と列(どちらも1始まり)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// `self`の先頭から`other`の末尾までを覆うSpanを返す
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end.max(self.end),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// `Debug`で書き出した文字列から`Span`の中身を取り除く
+///
+/// 位置情報を除いて構文木やトークンを比較するときに使う
+pub fn strip_spans(debug: &str) -> String {
+    const OPEN: &str = "Span {";
+    let mut result = String::new();
+    let mut rest = debug;
+
+    while let Some(start) = rest.find(OPEN) {
+        result.push_str(&rest[..start]);
+        result.push_str("Span");
+        rest = &rest[start + OPEN.len()..];
+        rest = &rest[rest.find('}').map_or(rest.len(), |end| end + 1)..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// 位置情報を除いて2つの値が等しいことを確かめる
+///
+/// `Span`自体は位置まで比較するので、構文木の形だけを確かめたいテストで使う
+#[macro_export]
+macro_rules! assert_eq_ignoring_spans {
+    ($left:expr, $right:expr $(, $($arg:tt)+)?) => {
+        assert_eq!(
+            $crate::strip_spans(&format!("{:#?}", $left)),
+            $crate::strip_spans(&format!("{:#?}", $right))
+            $(, $($arg)+)?
+        )
+    };
+}
@@ -1,10 +1,61 @@
 use crate::TypedExpression;
-use wervc_ast::ty::Type;
+use wervc_ast::{ty::Type, Span};
 
 #[derive(Debug)]
 pub enum TypeCheckError {
-    TypeError { expected: Type, actual: Type },
-    AmbiguousTypeExprError(TypedExpression),
-    NotCallableError(TypedExpression),
-    NotIdentError(TypedExpression),
+    TypeError {
+        expected: Type,
+        actual: Type,
+        span: Span,
+    },
+    AmbiguousTypeExprError(Box<TypedExpression>),
+    NotCallableError(Box<TypedExpression>),
+    NotIdentError(Box<TypedExpression>),
+    NotIterableError(Box<TypedExpression>),
+    NotStructError(Box<TypedExpression>),
+    UndefinedField {
+        ty: Type,
+        field: String,
+        span: Span,
+    },
+    MissingField {
+        ty: Type,
+        field: String,
+        span: Span,
+    },
+    NonExhaustiveMatch(Span),
+    /// `mut`をつけずに定義した変数への代入
+    AssignToImmutable(String, Span),
+    /// `mut`をつけずに定義した変数のアドレスの取得
+    AddrOfImmutable(String, Span),
+    /// 外側の関数で定義された変数の参照
+    ///
+    /// コンパイル後の関数からは、外側の関数のスタック上の変数を参照できない
+    CapturedLocal(String, Span),
+    UnmatchedArgsLen {
+        expected: usize,
+        actual: usize,
+        span: Span,
+    },
+}
+
+impl TypeCheckError {
+    /// エラーの原因となったソースコード上の範囲を返す
+    pub fn span(&self) -> Span {
+        match self {
+            TypeCheckError::TypeError { span, .. }
+            | TypeCheckError::UnmatchedArgsLen { span, .. }
+            | TypeCheckError::UndefinedField { span, .. }
+            | TypeCheckError::MissingField { span, .. }
+            | TypeCheckError::NonExhaustiveMatch(span)
+            | TypeCheckError::AssignToImmutable(_, span)
+            | TypeCheckError::AddrOfImmutable(_, span)
+            | TypeCheckError::CapturedLocal(_, span) => *span,
+            TypeCheckError::AmbiguousTypeExprError(expr)
+            | TypeCheckError::NotCallableError(expr)
+            | TypeCheckError::NotIdentError(expr)
+            | TypeCheckError::NotIterableError(expr)
+            | TypeCheckError::NotStructError(expr) => expr.span(),
+        }
+    }
 }
@@ -0,0 +1,91 @@
+use crate::{TypedExpression, TypedExpressionKind};
+use wervc_ast::{
+    ty::{Type, TypeKind},
+    Pattern,
+};
+
+/// パターンの行の中で、どんな値にも一致する位置を`None`で表す
+type Row<'a> = Vec<Option<&'a Pattern<TypedExpression>>>;
+
+/// 値を組み立てる方法。これで値を場合分けする
+#[derive(PartialEq)]
+enum Constructor<'a> {
+    Variant(&'a str),
+    Bool(bool),
+}
+
+/// それぞれの行が、型の並び`tys`の値の組と照合するパターンの並びであるとき、
+/// どの値の組もいずれかの行に一致するか
+///
+/// 先頭の値を取りうるコンストラクタで場合分けし、残りの値について再帰的に調べる
+pub(crate) fn is_exhaustive(rows: Vec<Row>, tys: &[&Type]) -> bool {
+    let Some((ty, rest_tys)) = tys.split_first() else {
+        return !rows.is_empty();
+    };
+    let constructors: Vec<(Constructor, Vec<&Type>)> = match &ty.unaliased().kind {
+        TypeKind::Enum { variants, .. } => variants
+            .iter()
+            .map(|(name, payload)| (Constructor::Variant(name), payload.iter().collect()))
+            .collect(),
+        TypeKind::Bool => vec![
+            (Constructor::Bool(true), vec![]),
+            (Constructor::Bool(false), vec![]),
+        ],
+        // 整数や文字はリテラルで網羅できないので、どんな値にも一致するパターンだけを見る
+        _ => {
+            let rows = rows
+                .into_iter()
+                .filter(|row| is_irrefutable(row[0]))
+                .map(|row| row[1..].to_vec())
+                .collect();
+
+            return is_exhaustive(rows, rest_tys);
+        }
+    };
+
+    constructors.into_iter().all(|(constructor, field_tys)| {
+        let rows = rows
+            .iter()
+            .filter_map(|row| {
+                let mut specialized = specialize(row[0], &constructor, field_tys.len())?;
+
+                specialized.extend_from_slice(&row[1..]);
+
+                Some(specialized)
+            })
+            .collect();
+        let tys: Vec<&Type> = field_tys.iter().chain(rest_tys).copied().collect();
+
+        is_exhaustive(rows, &tys)
+    })
+}
+
+/// パターンがコンストラクタで作られた値に一致しうるなら、その値の中身と照合するパターンの並びを返す
+fn specialize<'a>(
+    pattern: Option<&'a Pattern<TypedExpression>>,
+    constructor: &Constructor,
+    arity: usize,
+) -> Option<Row<'a>> {
+    match pattern {
+        _ if is_irrefutable(pattern) => Some(vec![None; arity]),
+        Some(Pattern::Variant {
+            variant, fields, ..
+        }) if *constructor == Constructor::Variant(variant) => {
+            Some(fields.iter().map(Some).collect())
+        }
+        Some(Pattern::Literal { value, .. }) => match value.kind {
+            TypedExpressionKind::Boolean(ref b) if *constructor == Constructor::Bool(b.value) => {
+                Some(vec![])
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_irrefutable(pattern: Option<&Pattern<TypedExpression>>) -> bool {
+    matches!(
+        pattern,
+        None | Some(Pattern::Wildcard(_) | Pattern::Binding { .. })
+    )
+}
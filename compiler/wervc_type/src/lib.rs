@@ -1,13 +1,18 @@
 pub mod error;
+mod exhaustiveness;
 #[cfg(test)]
 mod test;
 
 use error::TypeCheckError;
+use exhaustiveness::is_exhaustive;
+use std::collections::{HashMap, HashSet};
 use wervc_ast::{
     ty::{Type, TypeKind},
-    Array, BinaryExpr, BinaryExprKind, BlockExpr, Boolean, CallExpr, Expression, FunctionDefExpr,
-    Ident, IfExpr, Integer, LetExpr, Node, Program, ReturnExpr, Statement, UnaryExpr,
-    UnaryExprKind,
+    Array, BinaryExpr, BinaryExprKind, BlockExpr, Boolean, BreakExpr, CallExpr, Char, ConstExpr,
+    ContinueExpr, EnumDefExpr, EnumVariantExpr, Expression, FieldExpr, Float, ForExpr,
+    FunctionDefExpr, Ident, IfExpr, Integer, Iterable, LambdaExpr, LetExpr, LoopExpr, MatchArm,
+    MatchExpr, Node, Pattern, Program, ReturnExpr, Span, Statement, Str, StructDefExpr,
+    StructLiteral, TupleExpr, TypeAliasExpr, UnaryExpr, UnaryExprKind, WhileExpr,
 };
 use wervc_environment::Environment;
 
@@ -27,6 +32,9 @@ pub struct TypedExpression {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum TypedExpressionKind {
     Integer(Integer),
+    Float(Float),
+    Str(Str),
+    Char(Char),
     Ident(Ident),
     Boolean(Boolean),
     Array(Array<TypedExpression>),
@@ -35,29 +43,87 @@ pub enum TypedExpressionKind {
     BlockExpr(BlockExpr<TypedExpression>),
     CallExpr(CallExpr<TypedExpression>),
     FunctionDefExpr(FunctionDefExpr<TypedExpression>),
+    LambdaExpr(LambdaExpr<TypedExpression>),
+    StructDefExpr(StructDefExpr),
+    StructLiteral(StructLiteral<TypedExpression>),
+    FieldExpr(FieldExpr<TypedExpression>),
+    TupleExpr(TupleExpr<TypedExpression>),
+    TypeAliasExpr(TypeAliasExpr),
+    ConstExpr(ConstExpr<TypedExpression>),
+    EnumDefExpr(EnumDefExpr),
+    EnumVariantExpr(EnumVariantExpr<TypedExpression>),
+    MatchExpr(MatchExpr<TypedExpression>),
     IfExpr(IfExpr<TypedExpression>),
     ReturnExpr(ReturnExpr<TypedExpression>),
+    WhileExpr(WhileExpr<TypedExpression>),
+    ForExpr(ForExpr<TypedExpression>),
+    LoopExpr(LoopExpr<TypedExpression>),
+    BreakExpr(BreakExpr<TypedExpression>),
+    ContinueExpr(ContinueExpr),
     UnaryExpr(UnaryExpr<TypedExpression>),
 }
 
+impl TypedExpression {
+    pub fn span(&self) -> Span {
+        match &self.kind {
+            TypedExpressionKind::Integer(e) => e.span,
+            TypedExpressionKind::Float(e) => e.span,
+            TypedExpressionKind::Str(e) => e.span,
+            TypedExpressionKind::Char(e) => e.span,
+            TypedExpressionKind::Ident(e) => e.span,
+            TypedExpressionKind::Boolean(e) => e.span,
+            TypedExpressionKind::Array(e) => e.span,
+            TypedExpressionKind::BinaryExpr(e) => e.span,
+            TypedExpressionKind::LetExpr(e) => e.span,
+            TypedExpressionKind::BlockExpr(e) => e.span,
+            TypedExpressionKind::CallExpr(e) => e.span,
+            TypedExpressionKind::FunctionDefExpr(e) => e.span,
+            TypedExpressionKind::LambdaExpr(e) => e.span,
+            TypedExpressionKind::StructDefExpr(e) => e.span,
+            TypedExpressionKind::StructLiteral(e) => e.span,
+            TypedExpressionKind::FieldExpr(e) => e.span,
+            TypedExpressionKind::TupleExpr(e) => e.span,
+            TypedExpressionKind::TypeAliasExpr(e) => e.span,
+            TypedExpressionKind::ConstExpr(e) => e.span,
+            TypedExpressionKind::EnumDefExpr(e) => e.span,
+            TypedExpressionKind::EnumVariantExpr(e) => e.span,
+            TypedExpressionKind::MatchExpr(e) => e.span,
+            TypedExpressionKind::IfExpr(e) => e.span,
+            TypedExpressionKind::ReturnExpr(e) => e.span,
+            TypedExpressionKind::WhileExpr(e) => e.span,
+            TypedExpressionKind::ForExpr(e) => e.span,
+            TypedExpressionKind::LoopExpr(e) => e.span,
+            TypedExpressionKind::BreakExpr(e) => e.span,
+            TypedExpressionKind::ContinueExpr(e) => e.span,
+            TypedExpressionKind::UnaryExpr(e) => e.span,
+        }
+    }
+}
+
 impl From<Expression> for TypedExpression {
     fn from(value: Expression) -> Self {
         let kind = match value {
             Expression::Integer(e) => TypedExpressionKind::Integer(e),
+            Expression::Float(e) => TypedExpressionKind::Float(e),
+            Expression::Str(e) => TypedExpressionKind::Str(e),
+            Expression::Char(e) => TypedExpressionKind::Char(e),
             Expression::Ident(e) => TypedExpressionKind::Ident(e),
             Expression::Boolean(e) => TypedExpressionKind::Boolean(e),
             Expression::Array(e) => TypedExpressionKind::Array(Array {
                 elements: e.elements.into_iter().map(TypedExpression::from).collect(),
+                span: e.span,
             }),
             Expression::BinaryExpr(e) => TypedExpressionKind::BinaryExpr(BinaryExpr {
                 kind: e.kind,
                 lhs: Box::new(TypedExpression::from(*e.lhs)),
                 rhs: Box::new(TypedExpression::from(*e.rhs)),
+                span: e.span,
             }),
             Expression::LetExpr(e) => TypedExpressionKind::LetExpr(LetExpr {
                 name: Box::new(TypedExpression::from(*e.name)),
                 value: e.value.map(|v| Box::new(TypedExpression::from(*v))),
                 ty: e.ty,
+                span: e.span,
             }),
             Expression::BlockExpr(e) => TypedExpressionKind::BlockExpr(BlockExpr {
                 statements: e
@@ -70,10 +136,13 @@ impl From<Expression> for TypedExpression {
                         }
                     })
                     .collect(),
+                span: e.span,
             }),
             Expression::CallExpr(e) => TypedExpressionKind::CallExpr(CallExpr {
+                offset: e.offset,
                 func: Box::new(TypedExpression::from(*e.func)),
                 args: e.args.into_iter().map(TypedExpression::from).collect(),
+                span: e.span,
             }),
             Expression::FunctionDefExpr(e) => {
                 TypedExpressionKind::FunctionDefExpr(FunctionDefExpr {
@@ -85,19 +154,120 @@ impl From<Expression> for TypedExpression {
                         .collect(),
                     return_ty: e.return_ty,
                     body: Box::new(TypedExpression::from(*e.body)),
+                    span: e.span,
                 })
             }
+            Expression::LambdaExpr(e) => TypedExpressionKind::LambdaExpr(LambdaExpr {
+                params: e
+                    .params
+                    .into_iter()
+                    .map(|(e, ty)| (TypedExpression::from(e), ty))
+                    .collect(),
+                return_ty: e.return_ty,
+                body: Box::new(TypedExpression::from(*e.body)),
+                span: e.span,
+            }),
+            Expression::StructDefExpr(e) => TypedExpressionKind::StructDefExpr(e),
+            Expression::StructLiteral(e) => TypedExpressionKind::StructLiteral(StructLiteral {
+                ty: e.ty,
+                fields: e
+                    .fields
+                    .into_iter()
+                    .map(|(name, e)| (name, TypedExpression::from(e)))
+                    .collect(),
+                offset: e.offset,
+                span: e.span,
+            }),
+            Expression::FieldExpr(e) => TypedExpressionKind::FieldExpr(FieldExpr {
+                expr: Box::new(TypedExpression::from(*e.expr)),
+                field: e.field,
+                span: e.span,
+            }),
+            Expression::TupleExpr(e) => TypedExpressionKind::TupleExpr(TupleExpr {
+                elements: e.elements.into_iter().map(TypedExpression::from).collect(),
+                offset: e.offset,
+                span: e.span,
+            }),
+            Expression::TypeAliasExpr(e) => TypedExpressionKind::TypeAliasExpr(e),
+            Expression::ConstExpr(e) => TypedExpressionKind::ConstExpr(ConstExpr {
+                name: e.name,
+                ty: e.ty,
+                value: Box::new(TypedExpression::from(*e.value)),
+                span: e.span,
+            }),
+            Expression::EnumDefExpr(e) => TypedExpressionKind::EnumDefExpr(e),
+            Expression::EnumVariantExpr(e) => {
+                TypedExpressionKind::EnumVariantExpr(EnumVariantExpr {
+                    ty: e.ty,
+                    variant: e.variant,
+                    args: e.args.into_iter().map(TypedExpression::from).collect(),
+                    offset: e.offset,
+                    span: e.span,
+                })
+            }
+            Expression::MatchExpr(e) => TypedExpressionKind::MatchExpr(MatchExpr {
+                expr: Box::new(TypedExpression::from(*e.expr)),
+                arms: e
+                    .arms
+                    .into_iter()
+                    .map(|arm| MatchArm {
+                        pattern: arm.pattern.map(&mut TypedExpression::from),
+                        guard: arm.guard.map(|e| Box::new(TypedExpression::from(*e))),
+                        body: Box::new(TypedExpression::from(*arm.body)),
+                        span: arm.span,
+                    })
+                    .collect(),
+                span: e.span,
+            }),
             Expression::IfExpr(e) => TypedExpressionKind::IfExpr(IfExpr {
                 condition: Box::new(TypedExpression::from(*e.condition)),
                 consequence: Box::new(TypedExpression::from(*e.consequence)),
                 alternative: e.alternative.map(|e| Box::new(TypedExpression::from(*e))),
+                span: e.span,
             }),
             Expression::ReturnExpr(e) => TypedExpressionKind::ReturnExpr(ReturnExpr {
                 value: Box::new(TypedExpression::from(*e.value)),
+                span: e.span,
+            }),
+            Expression::WhileExpr(e) => TypedExpressionKind::WhileExpr(WhileExpr {
+                label: e.label,
+                condition: Box::new(TypedExpression::from(*e.condition)),
+                body: Box::new(TypedExpression::from(*e.body)),
+                span: e.span,
+            }),
+            Expression::ForExpr(e) => TypedExpressionKind::ForExpr(ForExpr {
+                label: e.label,
+                var: Box::new(TypedExpression::from(*e.var)),
+                iterable: match e.iterable {
+                    Iterable::Range {
+                        start,
+                        end,
+                        inclusive,
+                    } => Iterable::Range {
+                        start: Box::new(TypedExpression::from(*start)),
+                        end: Box::new(TypedExpression::from(*end)),
+                        inclusive,
+                    },
+                    Iterable::Array(e) => Iterable::Array(Box::new(TypedExpression::from(*e))),
+                },
+                body: Box::new(TypedExpression::from(*e.body)),
+                span: e.span,
+            }),
+            Expression::LoopExpr(e) => TypedExpressionKind::LoopExpr(LoopExpr {
+                label: e.label,
+                body: Box::new(TypedExpression::from(*e.body)),
+                span: e.span,
+            }),
+            Expression::BreakExpr(e) => TypedExpressionKind::BreakExpr(BreakExpr {
+                label: e.label,
+                value: e.value.map(|v| Box::new(TypedExpression::from(*v))),
+                span: e.span,
             }),
+            Expression::ContinueExpr(e) => TypedExpressionKind::ContinueExpr(e),
             Expression::UnaryExpr(e) => TypedExpressionKind::UnaryExpr(UnaryExpr {
                 kind: e.kind,
                 expr: Box::new(TypedExpression::from(*e.expr)),
+                span: e.span,
             }),
         };
 
@@ -111,20 +281,26 @@ impl From<TypedExpression> for Expression {
     fn from(val: TypedExpression) -> Self {
         match val.kind {
             TypedExpressionKind::Integer(e) => Expression::Integer(e),
+            TypedExpressionKind::Float(e) => Expression::Float(e),
+            TypedExpressionKind::Str(e) => Expression::Str(e),
+            TypedExpressionKind::Char(e) => Expression::Char(e),
             TypedExpressionKind::Ident(e) => Expression::Ident(e),
             TypedExpressionKind::Boolean(e) => Expression::Boolean(e),
             TypedExpressionKind::Array(e) => Expression::Array(Array {
                 elements: e.elements.into_iter().map(TypedExpression::into).collect(),
+                span: e.span,
             }),
             TypedExpressionKind::BinaryExpr(e) => Expression::BinaryExpr(BinaryExpr {
                 kind: e.kind,
                 lhs: Box::new(TypedExpression::into(*e.lhs)),
                 rhs: Box::new(TypedExpression::into(*e.rhs)),
+                span: e.span,
             }),
             TypedExpressionKind::LetExpr(e) => Expression::LetExpr(LetExpr {
                 name: Box::new(TypedExpression::into(*e.name)),
                 value: e.value.map(|v| Box::new(TypedExpression::into(*v))),
                 ty: e.ty,
+                span: e.span,
             }),
             TypedExpressionKind::BlockExpr(e) => Expression::BlockExpr(BlockExpr {
                 statements: e
@@ -137,10 +313,13 @@ impl From<TypedExpression> for Expression {
                         }
                     })
                     .collect(),
+                span: e.span,
             }),
             TypedExpressionKind::CallExpr(e) => Expression::CallExpr(CallExpr {
+                offset: e.offset,
                 func: Box::new(TypedExpression::into(*e.func)),
                 args: e.args.into_iter().map(TypedExpression::into).collect(),
+                span: e.span,
             }),
             TypedExpressionKind::FunctionDefExpr(e) => {
                 Expression::FunctionDefExpr(FunctionDefExpr {
@@ -152,19 +331,120 @@ impl From<TypedExpression> for Expression {
                         .collect(),
                     return_ty: e.return_ty,
                     body: Box::new(TypedExpression::into(*e.body)),
+                    span: e.span,
                 })
             }
+            TypedExpressionKind::LambdaExpr(e) => Expression::LambdaExpr(LambdaExpr {
+                params: e
+                    .params
+                    .into_iter()
+                    .map(|(e, ty)| (TypedExpression::into(e), ty))
+                    .collect(),
+                return_ty: e.return_ty,
+                body: Box::new(TypedExpression::into(*e.body)),
+                span: e.span,
+            }),
+            TypedExpressionKind::StructDefExpr(e) => Expression::StructDefExpr(e),
+            TypedExpressionKind::StructLiteral(e) => Expression::StructLiteral(StructLiteral {
+                ty: e.ty,
+                fields: e
+                    .fields
+                    .into_iter()
+                    .map(|(name, e)| (name, TypedExpression::into(e)))
+                    .collect(),
+                offset: e.offset,
+                span: e.span,
+            }),
+            TypedExpressionKind::FieldExpr(e) => Expression::FieldExpr(FieldExpr {
+                expr: Box::new(TypedExpression::into(*e.expr)),
+                field: e.field,
+                span: e.span,
+            }),
+            TypedExpressionKind::TupleExpr(e) => Expression::TupleExpr(TupleExpr {
+                elements: e.elements.into_iter().map(TypedExpression::into).collect(),
+                offset: e.offset,
+                span: e.span,
+            }),
+            TypedExpressionKind::TypeAliasExpr(e) => Expression::TypeAliasExpr(e),
+            TypedExpressionKind::ConstExpr(e) => Expression::ConstExpr(ConstExpr {
+                name: e.name,
+                ty: e.ty,
+                value: Box::new(TypedExpression::into(*e.value)),
+                span: e.span,
+            }),
+            TypedExpressionKind::EnumDefExpr(e) => Expression::EnumDefExpr(e),
+            TypedExpressionKind::EnumVariantExpr(e) => {
+                Expression::EnumVariantExpr(EnumVariantExpr {
+                    ty: e.ty,
+                    variant: e.variant,
+                    args: e.args.into_iter().map(TypedExpression::into).collect(),
+                    offset: e.offset,
+                    span: e.span,
+                })
+            }
+            TypedExpressionKind::MatchExpr(e) => Expression::MatchExpr(MatchExpr {
+                expr: Box::new(TypedExpression::into(*e.expr)),
+                arms: e
+                    .arms
+                    .into_iter()
+                    .map(|arm| MatchArm {
+                        pattern: arm.pattern.map(&mut TypedExpression::into),
+                        guard: arm.guard.map(|e| Box::new(TypedExpression::into(*e))),
+                        body: Box::new(TypedExpression::into(*arm.body)),
+                        span: arm.span,
+                    })
+                    .collect(),
+                span: e.span,
+            }),
             TypedExpressionKind::IfExpr(e) => Expression::IfExpr(IfExpr {
                 condition: Box::new(TypedExpression::into(*e.condition)),
                 consequence: Box::new(TypedExpression::into(*e.consequence)),
                 alternative: e.alternative.map(|e| Box::new(TypedExpression::into(*e))),
+                span: e.span,
             }),
             TypedExpressionKind::ReturnExpr(e) => Expression::ReturnExpr(ReturnExpr {
                 value: Box::new(TypedExpression::into(*e.value)),
+                span: e.span,
             }),
+            TypedExpressionKind::WhileExpr(e) => Expression::WhileExpr(WhileExpr {
+                label: e.label,
+                condition: Box::new(TypedExpression::into(*e.condition)),
+                body: Box::new(TypedExpression::into(*e.body)),
+                span: e.span,
+            }),
+            TypedExpressionKind::ForExpr(e) => Expression::ForExpr(ForExpr {
+                label: e.label,
+                var: Box::new(TypedExpression::into(*e.var)),
+                iterable: match e.iterable {
+                    Iterable::Range {
+                        start,
+                        end,
+                        inclusive,
+                    } => Iterable::Range {
+                        start: Box::new(TypedExpression::into(*start)),
+                        end: Box::new(TypedExpression::into(*end)),
+                        inclusive,
+                    },
+                    Iterable::Array(e) => Iterable::Array(Box::new(TypedExpression::into(*e))),
+                },
+                body: Box::new(TypedExpression::into(*e.body)),
+                span: e.span,
+            }),
+            TypedExpressionKind::LoopExpr(e) => Expression::LoopExpr(LoopExpr {
+                label: e.label,
+                body: Box::new(TypedExpression::into(*e.body)),
+                span: e.span,
+            }),
+            TypedExpressionKind::BreakExpr(e) => Expression::BreakExpr(BreakExpr {
+                label: e.label,
+                value: e.value.map(|v| Box::new(TypedExpression::into(*v))),
+                span: e.span,
+            }),
+            TypedExpressionKind::ContinueExpr(e) => Expression::ContinueExpr(e),
             TypedExpressionKind::UnaryExpr(e) => Expression::UnaryExpr(UnaryExpr {
                 kind: e.kind,
                 expr: Box::new(TypedExpression::into(*e.expr)),
+                span: e.span,
             }),
         }
     }
@@ -245,6 +525,25 @@ impl TypedNode {
 pub struct TypeResolver {
     pub local_vars: Environment<String, (Type, isize)>, // 型とオフセット
     pub cur_offset: isize,
+    /// 関数の外側で定義された変数のオフセット
+    ///
+    /// どの関数からも参照できるよう、コンパイラはこれらの変数をスタックではなく静的な領域に置く
+    pub globals: HashSet<isize>,
+    /// 解析中の式を囲んでいる関数の数
+    function_depth: usize,
+    /// 関数の中で定義された変数のオフセットと、その変数を定義した関数の深さ
+    local_depths: HashMap<isize, usize>,
+    /// 解析中の式を囲んでいるループ。内側のループほど後ろに並ぶ
+    loops: Vec<LoopScope>,
+}
+
+/// `break`の飛び先になるループ
+struct LoopScope {
+    label: Option<String>,
+    /// `break`で値を返せるか。値を返せるのは`loop`式だけ
+    yields_value: bool,
+    /// これまでに見つかった`break`の値の型
+    ty: Type,
 }
 
 impl TypeResolver {
@@ -264,44 +563,223 @@ impl TypeResolver {
         ty: Type,
     ) -> Result<Option<(Type, isize)>, TypeCheckError> {
         if let TypedExpressionKind::Ident(ident) = &mut ident.kind {
-            self.cur_offset += ty.calc_size();
-            ident.offset = self.cur_offset;
+            ident.offset = self.allocate(&ty);
+
+            if self.function_depth == 0 {
+                self.globals.insert(ident.offset);
+            } else {
+                self.local_depths.insert(ident.offset, self.function_depth);
+            }
 
             Ok(self
                 .local_vars
                 .register_item(ident.name.clone(), (ty, ident.offset)))
         } else {
-            Err(TypeCheckError::NotIdentError(ident.clone()))
+            Err(TypeCheckError::NotIdentError(Box::new(ident.clone())))
+        }
+    }
+
+    /// `let`の左辺の変数を定義する
+    ///
+    /// 左辺がタプルの場合は、型を要素ごとに分けてそれぞれの変数を定義する
+    fn create_let_target(
+        &mut self,
+        target: &mut TypedExpression,
+        ty: &Type,
+    ) -> Result<(), TypeCheckError> {
+        target.ty = ty.clone();
+
+        let TypedExpressionKind::TupleExpr(TupleExpr { elements, span, .. }) = &mut target.kind
+        else {
+            self.create_ident(target, ty.clone())?;
+
+            return Ok(());
+        };
+        let TypeKind::Tuple { elements: tys } = &ty.unaliased().kind else {
+            return Err(TypeCheckError::TypeError {
+                expected: Type::tuple(vec![Type::unknown(); elements.len()]),
+                actual: ty.clone(),
+                span: *span,
+            });
+        };
+
+        if elements.len() != tys.len() {
+            return Err(TypeCheckError::UnmatchedArgsLen {
+                expected: tys.len(),
+                actual: elements.len(),
+                span: *span,
+            });
         }
+
+        for (element, ty) in elements.iter_mut().zip(tys) {
+            self.create_let_target(element, ty)?;
+        }
+
+        Ok(())
+    }
+
+    /// スタック上に型の大きさの領域を確保し、そのオフセットを返す
+    fn allocate(&mut self, ty: &Type) -> isize {
+        // スタック上の変数は8バイト境界に揃えて配置する
+        self.cur_offset += ty.calc_size();
+        self.cur_offset = (self.cur_offset + 7) / 8 * 8;
+        self.cur_offset
     }
 
     fn enter_scope(&mut self) {
         self.local_vars.create_deeper_scope();
     }
 
+    /// 仮引数を定義し、関数の本体の型を解決する。関数の外側のループへは`break`できない
+    fn resolve_function_body(
+        &mut self,
+        params: &mut [(TypedExpression, Type)],
+        body: &mut TypedExpression,
+    ) -> Result<(), TypeCheckError> {
+        let loops = std::mem::take(&mut self.loops);
+
+        self.function_depth += 1;
+
+        let result = params
+            .iter_mut()
+            .try_for_each(|(param_ident, param_ty)| {
+                param_ident.ty = param_ty.clone();
+                self.create_ident(param_ident, param_ty.clone()).map(|_| ())
+            })
+            .and_then(|_| self.resolve_type(body));
+
+        self.function_depth -= 1;
+        self.loops = loops;
+        result?;
+
+        Ok(())
+    }
+
+    /// ループの本体の型を解決し、本体の中の`break`の値から推論したループの型を返す
+    fn resolve_loop_body(
+        &mut self,
+        label: &Option<String>,
+        yields_value: bool,
+        body: &mut TypedExpression,
+    ) -> Result<Type, TypeCheckError> {
+        self.loops.push(LoopScope {
+            label: label.clone(),
+            yields_value,
+            ty: Type::never(),
+        });
+
+        let result = self.resolve_type(body);
+        let scope = self.loops.pop().unwrap();
+
+        result?;
+
+        Ok(scope.ty)
+    }
+
     fn leave_scope(&mut self) {
         self.local_vars.create_shallow_scope();
     }
 
+    /// パターンを照合する値の型と突き合わせ、パターンで束縛する変数を定義する
+    fn resolve_pattern(
+        &mut self,
+        pattern: &mut Pattern<TypedExpression>,
+        ty: &Type,
+    ) -> Result<(), TypeCheckError> {
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding { ident, .. } => {
+                ident.ty = ty.clone();
+                self.create_ident(ident, ty.clone())?;
+            }
+            Pattern::Literal { value, .. } => {
+                self.resolve_type(value)?;
+
+                if value.ty != *ty {
+                    return Err(TypeCheckError::TypeError {
+                        expected: ty.clone(),
+                        actual: value.ty.clone(),
+                        span: value.span(),
+                    });
+                }
+            }
+            Pattern::Variant {
+                ty: pattern_ty,
+                variant,
+                fields,
+                span,
+            } => {
+                if pattern_ty != ty {
+                    return Err(TypeCheckError::TypeError {
+                        expected: ty.clone(),
+                        actual: pattern_ty.clone(),
+                        span: *span,
+                    });
+                }
+
+                // バリアントがあることはパーサーで確かめている
+                let (_, payload) = ty.variant(variant).unwrap();
+
+                if fields.len() != payload.len() {
+                    return Err(TypeCheckError::UnmatchedArgsLen {
+                        expected: payload.len(),
+                        actual: fields.len(),
+                        span: *span,
+                    });
+                }
+
+                for (field, (_, field_ty)) in fields.iter_mut().zip(payload) {
+                    self.resolve_pattern(field, field_ty)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn resolve_type(&mut self, expr: &mut TypedExpression) -> Result<Type, TypeCheckError> {
         match &mut expr.kind {
             TypedExpressionKind::Integer(_) => {
                 expr.ty = Type::int();
             }
+            TypedExpressionKind::Float(_) => {
+                expr.ty = Type::float();
+            }
+            TypedExpressionKind::Str(_) => {
+                expr.ty = Type::str();
+            }
+            TypedExpressionKind::Char(_) => {
+                expr.ty = Type::char();
+            }
             TypedExpressionKind::Ident(ident) => {
                 let (ident_ty, offset) = self.find_ident(ident);
 
                 ident.offset = *offset;
 
+                // 外側の関数の変数は、その関数のスタック上にあるので参照できない
+                if self
+                    .local_depths
+                    .get(offset)
+                    .is_some_and(|depth| *depth < self.function_depth)
+                {
+                    return Err(TypeCheckError::CapturedLocal(
+                        ident.name.clone(),
+                        ident.span,
+                    ));
+                }
+
                 // identの型が不明な場合はエラー
                 if *ident_ty == Type::unknown() {
-                    return Err(TypeCheckError::AmbiguousTypeExprError(expr.clone()));
+                    return Err(TypeCheckError::AmbiguousTypeExprError(Box::new(
+                        expr.clone(),
+                    )));
                 }
 
                 if expr.ty != Type::unknown() && expr.ty == *ident_ty {
                     return Err(TypeCheckError::TypeError {
                         expected: ident_ty.clone(),
                         actual: expr.ty.clone(),
+                        span: ident.span,
                     });
                 }
 
@@ -310,12 +788,16 @@ impl TypeResolver {
             TypedExpressionKind::Boolean(_) => {
                 expr.ty = Type::bool();
             }
-            TypedExpressionKind::BinaryExpr(BinaryExpr { kind, lhs, rhs }) => {
+            TypedExpressionKind::BinaryExpr(BinaryExpr { kind, lhs, rhs, .. }) => {
                 self.resolve_type(lhs)?;
                 self.resolve_type(rhs)?;
 
-                lhs.ty.try_cast_to_ptr();
-                rhs.ty.try_cast_to_ptr();
+                // 配列はポインタとして扱うが、オペランド自体の型は書き換えない
+                let mut lhs_ty = lhs.ty.clone();
+                let mut rhs_ty = rhs.ty.clone();
+
+                lhs_ty.try_cast_to_ptr();
+                rhs_ty.try_cast_to_ptr();
 
                 match kind {
                     BinaryExprKind::Eq
@@ -326,89 +808,182 @@ impl TypeResolver {
                     | BinaryExprKind::Ge => {
                         expr.ty = Type::bool();
                     }
-                    BinaryExprKind::Add | BinaryExprKind::Sub => match (&lhs.ty.kind, &rhs.ty.kind)
-                    {
-                        (TypeKind::Int, TypeKind::Int) => {
-                            expr.ty = lhs.ty.clone();
-                        }
-                        (TypeKind::Ptr { .. }, TypeKind::Int) => {
-                            expr.ty = lhs.ty.clone();
+                    BinaryExprKind::Add | BinaryExprKind::Sub => {
+                        match (&lhs_ty.unaliased().kind, &rhs_ty.unaliased().kind) {
+                            (TypeKind::Int, TypeKind::Int) => {
+                                expr.ty = Type::int();
+                            }
+                            (TypeKind::Float, TypeKind::Float) => {
+                                expr.ty = Type::float();
+                            }
+                            // 文字に整数を足し引きすると文字になる
+                            (TypeKind::Char, TypeKind::Int) => {
+                                expr.ty = Type::char();
+                            }
+                            // 文字同士の差は整数になる
+                            (TypeKind::Char, TypeKind::Char) if *kind == BinaryExprKind::Sub => {
+                                expr.ty = Type::int();
+                            }
+                            (TypeKind::Ptr { ptr_to }, TypeKind::Int) => {
+                                let size = ptr_to.calc_size();
+
+                                expr.ty = lhs_ty.clone();
+
+                                // 左辺がポインタの場合は、右辺の整数×指す先の型のサイズを加算する式に変換する
+                                let span = rhs.span();
 
-                            // 左辺がポインタの場合は、右辺の整数×ポインタのサイズを加算する式に変換する
-                            *rhs = Box::new(TypedExpression::from(Expression::BinaryExpr(
-                                BinaryExpr {
+                                **rhs = TypedExpression::from(Expression::BinaryExpr(BinaryExpr {
                                     kind: BinaryExprKind::Mul,
                                     lhs: Box::new((*rhs.clone()).into()),
                                     rhs: Box::new(Expression::Integer(Integer {
-                                        value: lhs.ty.calc_size(),
+                                        value: size,
+                                        span,
                                     })),
-                                },
-                            )));
+                                    span,
+                                }));
 
-                            self.resolve_type(&mut *rhs)?;
-                        }
-                        (TypeKind::Ptr { .. }, TypeKind::Ptr { .. }) => {
-                            expr.ty = lhs.ty.clone();
+                                self.resolve_type(&mut *rhs)?;
+                            }
+                            (TypeKind::Ptr { ptr_to }, TypeKind::Ptr { .. }) => {
+                                let size = ptr_to.calc_size();
+
+                                expr.ty = lhs_ty.clone();
 
-                            // 両辺ともポインタの場合は、ポインタの間にいくつ要素があるか計算する式に変換する
-                            // ポインタの差を取って、ポインタのサイズで割る
-                            *rhs = Box::new(TypedExpression::from(Expression::BinaryExpr(
-                                BinaryExpr {
+                                // 両辺ともポインタの場合は、ポインタの間にいくつ要素があるか計算する式に変換する
+                                // ポインタの差を取って、指す先の型のサイズで割る
+                                let span = lhs.span().to(rhs.span());
+
+                                **rhs = TypedExpression::from(Expression::BinaryExpr(BinaryExpr {
                                     kind: BinaryExprKind::Div,
                                     lhs: Box::new(Expression::BinaryExpr(BinaryExpr {
                                         kind: BinaryExprKind::Sub,
                                         lhs: Box::new((*lhs.clone()).into()),
                                         rhs: Box::new((*rhs.clone()).into()),
+                                        span,
                                     })),
                                     rhs: Box::new(Expression::Integer(Integer {
-                                        value: lhs.ty.calc_size(),
+                                        value: size,
+                                        span,
                                     })),
-                                },
-                            )));
+                                    span,
+                                }));
 
-                            self.resolve_type(&mut *rhs)?;
+                                self.resolve_type(&mut *rhs)?;
+                            }
+                            _ => {
+                                return Err(TypeCheckError::TypeError {
+                                    expected: Type::int(),
+                                    actual: lhs_ty.clone(),
+                                    span: lhs.span(),
+                                });
+                            }
                         }
-                        _ => {
-                            return Err(TypeCheckError::TypeError {
-                                expected: Type::int(),
-                                actual: lhs.ty.clone(),
-                            });
+                    }
+                    BinaryExprKind::Mul | BinaryExprKind::Div => {
+                        match (&lhs_ty.unaliased().kind, &rhs_ty.unaliased().kind) {
+                            (TypeKind::Int, TypeKind::Int) => {
+                                expr.ty = Type::int();
+                            }
+                            (TypeKind::Float, TypeKind::Float) => {
+                                expr.ty = Type::float();
+                            }
+                            _ => {
+                                return Err(TypeCheckError::TypeError {
+                                    expected: lhs_ty.clone(),
+                                    actual: rhs_ty.clone(),
+                                    span: rhs.span(),
+                                });
+                            }
                         }
-                    },
+                    }
+                    BinaryExprKind::Rem
+                    | BinaryExprKind::BitAnd
+                    | BinaryExprKind::BitOr
+                    | BinaryExprKind::BitXor
+                    | BinaryExprKind::Shl
+                    | BinaryExprKind::Shr => {
+                        // 剰余とビット演算は整数同士でのみ行える
+                        for (ty, span) in [(&lhs_ty, lhs.span()), (&rhs_ty, rhs.span())] {
+                            if *ty != Type::int() {
+                                return Err(TypeCheckError::TypeError {
+                                    expected: Type::int(),
+                                    actual: ty.clone(),
+                                    span,
+                                });
+                            }
+                        }
+
+                        expr.ty = Type::int();
+                    }
+                    BinaryExprKind::And | BinaryExprKind::Or => {
+                        // 論理演算は真偽値同士でのみ行える
+                        for (ty, span) in [(&lhs_ty, lhs.span()), (&rhs_ty, rhs.span())] {
+                            if *ty != Type::bool() {
+                                return Err(TypeCheckError::TypeError {
+                                    expected: Type::bool(),
+                                    actual: ty.clone(),
+                                    span,
+                                });
+                            }
+                        }
+
+                        expr.ty = Type::bool();
+                    }
                     BinaryExprKind::Assign => {
-                        if !lhs.ty.is_assignable_to(&rhs.ty) {
+                        if let Some(ident) = mutated_binding(lhs) {
+                            if !ident.mutable {
+                                return Err(TypeCheckError::AssignToImmutable(
+                                    ident.name.clone(),
+                                    ident.span,
+                                ));
+                            }
+                        }
+
+                        if !lhs_ty.is_assignable_to(&rhs_ty) {
                             return Err(TypeCheckError::TypeError {
-                                expected: lhs.ty.clone(),
-                                actual: rhs.ty.clone(),
+                                expected: lhs_ty.clone(),
+                                actual: rhs_ty.clone(),
+                                span: rhs.span(),
                             });
                         }
 
-                        expr.ty = lhs.ty.clone();
-                    }
-                    _ => {
-                        expr.ty = lhs.ty.clone();
+                        if let TypeKind::Ptr { .. } = lhs_ty.unaliased().kind {
+                            check_decay(rhs)?;
+                        }
+
+                        expr.ty = lhs_ty.clone();
                     }
                 };
             }
-            TypedExpressionKind::LetExpr(LetExpr { name, value, ty }) => {
+            TypedExpressionKind::LetExpr(LetExpr {
+                name, value, ty, ..
+            }) => {
                 if let Some(value) = value {
                     self.resolve_type(value)?;
 
+                    let mut value_ty = value.ty.clone();
+
+                    // ポインタの変数には、配列をその先頭を指すポインタとして代入できる
+                    if let TypeKind::Ptr { .. } = ty.unaliased().kind {
+                        value_ty.try_cast_to_ptr();
+                        check_decay(value)?;
+                    }
+
                     // 左辺に代入できる型でなければエラー
-                    if !value.ty.is_assignable_to(ty) {
+                    if !value_ty.is_assignable_to(ty) {
                         return Err(TypeCheckError::TypeError {
                             expected: ty.clone(),
                             actual: value.ty.clone(),
+                            span: value.span(),
                         });
                     }
                 }
 
-                name.ty = ty.clone();
                 expr.ty = ty.clone();
 
-                self.create_ident(name, ty.clone())?;
+                self.create_let_target(name, ty)?;
             }
-            TypedExpressionKind::BlockExpr(BlockExpr { statements }) => {
+            TypedExpressionKind::BlockExpr(BlockExpr { statements, .. }) => {
                 let mut ty = Type::never();
 
                 self.enter_scope();
@@ -421,24 +996,52 @@ impl TypeResolver {
 
                 self.leave_scope();
             }
-            TypedExpressionKind::CallExpr(CallExpr { func, args }) => {
+            TypedExpressionKind::CallExpr(CallExpr {
+                func, args, offset, ..
+            }) => {
                 self.resolve_type(func)?;
 
-                let TypeKind::Func { ref params_ty, ref return_ty } = func.ty.kind else {
-                    return Err(TypeCheckError::NotCallableError(expr.clone()));
+                let TypeKind::Func {
+                    ref params_ty,
+                    ref return_ty,
+                } = func.ty.unaliased().kind
+                else {
+                    return Err(TypeCheckError::NotCallableError(Box::new(expr.clone())));
                 };
 
+                if args.len() != params_ty.len() {
+                    return Err(TypeCheckError::UnmatchedArgsLen {
+                        expected: params_ty.len(),
+                        actual: args.len(),
+                        span: expr.span(),
+                    });
+                }
+
                 for (arg, param_ty) in args.iter_mut().zip(params_ty.iter()) {
                     self.resolve_type(arg)?;
 
-                    if &arg.ty != param_ty {
+                    let mut arg_ty = arg.ty.clone();
+
+                    // ポインタの引数には、配列をその先頭を指すポインタとして渡せる
+                    if let TypeKind::Ptr { .. } = param_ty.unaliased().kind {
+                        arg_ty.try_cast_to_ptr();
+                        check_decay(arg)?;
+                    }
+
+                    if &arg_ty != param_ty {
                         return Err(TypeCheckError::TypeError {
                             expected: param_ty.clone(),
                             actual: arg.ty.clone(),
+                            span: arg.span(),
                         });
                     }
                 }
 
+                // 構造体などの戻り値は、呼び出し元の一時領域に書き込んでもらう
+                if return_ty.is_aggregate() {
+                    *offset = self.allocate(return_ty);
+                }
+
                 expr.ty = *return_ty.clone();
             }
             TypedExpressionKind::FunctionDefExpr(FunctionDefExpr {
@@ -446,14 +1049,9 @@ impl TypeResolver {
                 params,
                 return_ty,
                 body,
+                ..
             }) => {
-                let mut params_ty = Vec::new();
-
-                for (param_ident, param_ty) in &mut *params {
-                    param_ident.ty = param_ty.clone();
-                    params_ty.push(param_ty.clone());
-                }
-
+                let params_ty = params.iter().map(|(_, ty)| ty.clone()).collect();
                 let func_ty = Type::func(params_ty, Box::new(return_ty.clone()));
 
                 name.ty = func_ty.clone();
@@ -461,27 +1059,253 @@ impl TypeResolver {
 
                 self.create_ident(name, func_ty)?;
 
+                // 関数はラベルで直接呼び出すので、外側の関数で定義されていても参照できる
+                if let TypedExpressionKind::Ident(name) = &name.kind {
+                    self.local_depths.remove(&name.offset);
+                }
+
                 self.enter_scope();
+                self.resolve_function_body(params, body)?;
 
-                for (param_ident, param_ty) in params {
-                    self.create_ident(param_ident, param_ty.clone())?;
+                if body.ty != *return_ty {
+                    return Err(TypeCheckError::TypeError {
+                        expected: return_ty.clone(),
+                        actual: body.ty.clone(),
+                        span: body.span(),
+                    });
                 }
 
-                self.resolve_type(body)?;
+                self.leave_scope();
+            }
+            TypedExpressionKind::LambdaExpr(LambdaExpr {
+                params,
+                return_ty,
+                body,
+                ..
+            }) => {
+                let params_ty = params.iter().map(|(_, ty)| ty.clone()).collect();
 
-                if body.ty != *return_ty {
+                self.enter_scope();
+                self.resolve_function_body(params, body)?;
+
+                // 戻り値の型が省略された場合は本体の型を使う
+                if *return_ty == Type::unknown() {
+                    *return_ty = body.ty.clone();
+                } else if body.ty != *return_ty {
                     return Err(TypeCheckError::TypeError {
                         expected: return_ty.clone(),
                         actual: body.ty.clone(),
+                        span: body.span(),
                     });
                 }
 
                 self.leave_scope();
+
+                expr.ty = Type::func(params_ty, Box::new(return_ty.clone()));
+            }
+            TypedExpressionKind::StructDefExpr(_) => {
+                expr.ty = Type::never();
+            }
+            TypedExpressionKind::StructLiteral(StructLiteral {
+                ty, fields, offset, ..
+            }) => {
+                let TypeKind::Struct {
+                    fields: ref fields_ty,
+                    ..
+                } = ty.unaliased().kind
+                else {
+                    unreachable!("struct literal of non-struct type: {:?}", ty);
+                };
+
+                for (name, value) in &mut *fields {
+                    self.resolve_type(value)?;
+
+                    let Some((_, field_ty)) = fields_ty.iter().find(|(field, _)| field == name)
+                    else {
+                        return Err(TypeCheckError::UndefinedField {
+                            ty: ty.clone(),
+                            field: name.clone(),
+                            span: value.span(),
+                        });
+                    };
+
+                    if !value.ty.is_assignable_to(field_ty) {
+                        return Err(TypeCheckError::TypeError {
+                            expected: field_ty.clone(),
+                            actual: value.ty.clone(),
+                            span: value.span(),
+                        });
+                    }
+                }
+
+                // 構造体のフィールドはすべて初期化しなければならない
+                if let Some((field, _)) = fields_ty
+                    .iter()
+                    .find(|(field, _)| fields.iter().all(|(name, _)| name != field))
+                {
+                    return Err(TypeCheckError::MissingField {
+                        ty: ty.clone(),
+                        field: field.clone(),
+                        span: expr.span(),
+                    });
+                }
+
+                // 値はスタック上の一時領域で組み立てる
+                *offset = self.allocate(ty);
+                expr.ty = ty.clone();
+            }
+            TypedExpressionKind::FieldExpr(FieldExpr {
+                expr: struct_expr,
+                field,
+                span,
+            }) => {
+                self.resolve_type(struct_expr)?;
+
+                if !matches!(
+                    struct_expr.ty.unaliased().kind,
+                    TypeKind::Struct { .. } | TypeKind::Tuple { .. }
+                ) {
+                    return Err(TypeCheckError::NotStructError(struct_expr.clone()));
+                }
+
+                let Some((_, field_ty)) = struct_expr.ty.field(field) else {
+                    return Err(TypeCheckError::UndefinedField {
+                        ty: struct_expr.ty.clone(),
+                        field: field.clone(),
+                        span: *span,
+                    });
+                };
+
+                expr.ty = field_ty.clone();
+            }
+            TypedExpressionKind::TupleExpr(TupleExpr {
+                elements, offset, ..
+            }) => {
+                for element in &mut *elements {
+                    self.resolve_type(element)?;
+                }
+
+                let ty = Type::tuple(elements.iter().map(|e| e.ty.clone()).collect());
+
+                // 値はスタック上の一時領域で組み立てる
+                *offset = self.allocate(&ty);
+                expr.ty = ty;
+            }
+            TypedExpressionKind::EnumDefExpr(_) | TypedExpressionKind::TypeAliasExpr(_) => {
+                expr.ty = Type::never();
+            }
+            TypedExpressionKind::ConstExpr(ConstExpr { ty, value, .. }) => {
+                self.resolve_type(value)?;
+
+                // 初期値はパーサーで評価済みのリテラルなので、宣言した型と比べるだけでよい
+                if !value.ty.is_assignable_to(ty) {
+                    return Err(TypeCheckError::TypeError {
+                        expected: ty.clone(),
+                        actual: value.ty.clone(),
+                        span: value.span(),
+                    });
+                }
+
+                expr.ty = Type::never();
+            }
+            TypedExpressionKind::EnumVariantExpr(EnumVariantExpr {
+                ty,
+                variant,
+                args,
+                offset,
+                span,
+            }) => {
+                // バリアントがあることはパーサーで確かめている
+                let (_, payload) = ty.variant(variant).unwrap();
+
+                if args.len() != payload.len() {
+                    return Err(TypeCheckError::UnmatchedArgsLen {
+                        expected: payload.len(),
+                        actual: args.len(),
+                        span: *span,
+                    });
+                }
+
+                for (arg, (_, field_ty)) in args.iter_mut().zip(payload) {
+                    self.resolve_type(arg)?;
+
+                    if !arg.ty.is_assignable_to(field_ty) {
+                        return Err(TypeCheckError::TypeError {
+                            expected: field_ty.clone(),
+                            actual: arg.ty.clone(),
+                            span: arg.span(),
+                        });
+                    }
+                }
+
+                // 値はスタック上の一時領域で組み立てる
+                *offset = self.allocate(ty);
+                expr.ty = ty.clone();
+            }
+            TypedExpressionKind::MatchExpr(MatchExpr {
+                expr: scrutinee,
+                arms,
+                span,
+            }) => {
+                self.resolve_type(scrutinee)?;
+
+                let mut ty = Type::never();
+
+                for MatchArm {
+                    pattern,
+                    guard,
+                    body,
+                    ..
+                } in &mut *arms
+                {
+                    self.enter_scope();
+                    self.resolve_pattern(pattern, &scrutinee.ty)?;
+
+                    if let Some(guard) = guard {
+                        self.resolve_type(guard)?;
+
+                        if guard.ty != Type::bool() && guard.ty != Type::int() {
+                            return Err(TypeCheckError::TypeError {
+                                expected: Type::bool(),
+                                actual: guard.ty.clone(),
+                                span: guard.span(),
+                            });
+                        }
+                    }
+
+                    self.resolve_type(body)?;
+                    self.leave_scope();
+
+                    // 値を返さない腕は、他の腕の型に合わせる
+                    if ty == Type::never() {
+                        ty = body.ty.clone();
+                    } else if body.ty != Type::never() && body.ty != ty {
+                        return Err(TypeCheckError::TypeError {
+                            expected: ty,
+                            actual: body.ty.clone(),
+                            span: body.span(),
+                        });
+                    }
+                }
+
+                // ガードのある腕は一致するとは限らないので、網羅性の判定に使わない
+                let rows = arms
+                    .iter()
+                    .filter(|arm| arm.guard.is_none())
+                    .map(|arm| vec![Some(&arm.pattern)])
+                    .collect();
+
+                if !is_exhaustive(rows, &[&scrutinee.ty]) {
+                    return Err(TypeCheckError::NonExhaustiveMatch(*span));
+                }
+
+                expr.ty = ty;
             }
             TypedExpressionKind::IfExpr(IfExpr {
                 condition,
                 consequence,
                 alternative,
+                ..
             }) => {
                 self.resolve_type(condition)?;
                 self.resolve_type(consequence)?;
@@ -490,23 +1314,143 @@ impl TypeResolver {
                     return Err(TypeCheckError::TypeError {
                         expected: Type::bool(),
                         actual: condition.ty.clone(),
+                        span: condition.span(),
                     });
                 }
 
+                expr.ty = consequence.ty.clone();
+
                 if let Some(alternative) = alternative.as_mut() {
                     self.resolve_type(alternative)?;
 
-                    if consequence.ty != alternative.ty {
+                    // 値を返さない分岐は、もう一方の分岐の型に合わせる
+                    if consequence.ty == Type::never() {
+                        expr.ty = alternative.ty.clone();
+                    } else if alternative.ty != Type::never() && consequence.ty != alternative.ty {
                         return Err(TypeCheckError::TypeError {
                             expected: consequence.ty.clone(),
                             actual: alternative.ty.clone(),
+                            span: alternative.span(),
                         });
                     }
                 }
+            }
+            TypedExpressionKind::WhileExpr(WhileExpr {
+                label,
+                condition,
+                body,
+                ..
+            }) => {
+                self.resolve_type(condition)?;
+                self.resolve_loop_body(label, false, body)?;
 
-                expr.ty = consequence.ty.clone();
+                if condition.ty != Type::bool() && condition.ty != Type::int() {
+                    return Err(TypeCheckError::TypeError {
+                        expected: Type::bool(),
+                        actual: condition.ty.clone(),
+                        span: condition.span(),
+                    });
+                }
+
+                expr.ty = Type::never();
             }
-            TypedExpressionKind::ReturnExpr(ReturnExpr { value }) => {
+            TypedExpressionKind::ForExpr(ForExpr {
+                label,
+                var,
+                iterable,
+                body,
+                ..
+            }) => {
+                let var_ty = match iterable {
+                    Iterable::Range { start, end, .. } => {
+                        for bound in [start, end] {
+                            self.resolve_type(bound)?;
+
+                            if bound.ty != Type::int() {
+                                return Err(TypeCheckError::TypeError {
+                                    expected: Type::int(),
+                                    actual: bound.ty.clone(),
+                                    span: bound.span(),
+                                });
+                            }
+                        }
+
+                        Type::int()
+                    }
+                    Iterable::Array(array) => {
+                        self.resolve_type(array)?;
+
+                        let TypeKind::Array {
+                            ref element_type, ..
+                        } = array.ty.unaliased().kind
+                        else {
+                            return Err(TypeCheckError::NotIterableError(array.clone()));
+                        };
+
+                        *element_type.clone()
+                    }
+                };
+
+                // ループ変数は本体の中でのみ参照できる
+                self.enter_scope();
+
+                var.ty = var_ty.clone();
+                self.create_ident(var, var_ty)?;
+                self.resolve_loop_body(label, false, body)?;
+
+                self.leave_scope();
+
+                expr.ty = Type::never();
+            }
+            TypedExpressionKind::LoopExpr(LoopExpr { label, body, .. }) => {
+                expr.ty = self.resolve_loop_body(label, true, body)?;
+            }
+            TypedExpressionKind::BreakExpr(BreakExpr { label, value, span }) => {
+                let (value_ty, span) = match value {
+                    Some(value) => {
+                        self.resolve_type(value)?;
+
+                        (value.ty.clone(), value.span())
+                    }
+                    None => (Type::never(), *span),
+                };
+
+                // 飛び先のループがあることはパーサーで確かめている
+                let target = match label {
+                    Some(label) => self
+                        .loops
+                        .iter_mut()
+                        .rfind(|scope| scope.label.as_ref() == Some(label)),
+                    None => self.loops.last_mut(),
+                };
+
+                if let Some(target) = target {
+                    if !target.yields_value && value_ty != Type::never() {
+                        return Err(TypeCheckError::TypeError {
+                            expected: Type::never(),
+                            actual: value_ty,
+                            span,
+                        });
+                    }
+
+                    // 値を返さない`break`は、他の`break`の値の型に合わせる
+                    if target.ty == Type::never() {
+                        target.ty = value_ty;
+                    } else if value_ty != Type::never() && target.ty != value_ty {
+                        return Err(TypeCheckError::TypeError {
+                            expected: target.ty.clone(),
+                            actual: value_ty,
+                            span,
+                        });
+                    }
+                }
+
+                expr.ty = Type::never();
+            }
+            TypedExpressionKind::ContinueExpr(_) => {
+                expr.ty = Type::never();
+            }
+            TypedExpressionKind::ReturnExpr(ReturnExpr { value, .. }) => {
                 self.resolve_type(value)?;
 
                 expr.ty = value.ty.clone();
@@ -514,48 +1458,73 @@ impl TypeResolver {
             TypedExpressionKind::UnaryExpr(UnaryExpr {
                 kind,
                 expr: unary_expr,
+                ..
             }) => {
                 self.resolve_type(unary_expr)?;
 
                 match kind {
                     UnaryExprKind::Minus => {
-                        if unary_expr.ty != Type::int() {
+                        if unary_expr.ty != Type::int() && unary_expr.ty != Type::float() {
                             return Err(TypeCheckError::TypeError {
                                 expected: Type::int(),
                                 actual: unary_expr.ty.clone(),
+                                span: unary_expr.span(),
                             });
                         }
 
-                        unary_expr.ty = Type::int();
+                        expr.ty = unary_expr.ty.clone();
                     }
                     UnaryExprKind::Not => {
                         if unary_expr.ty != Type::bool() && unary_expr.ty != Type::int() {
                             return Err(TypeCheckError::TypeError {
                                 expected: Type::bool(),
                                 actual: unary_expr.ty.clone(),
+                                span: unary_expr.span(),
                             });
                         }
 
-                        unary_expr.ty = Type::bool();
+                        expr.ty = Type::bool();
+                    }
+                    UnaryExprKind::BitNot => {
+                        if unary_expr.ty != Type::int() {
+                            return Err(TypeCheckError::TypeError {
+                                expected: Type::int(),
+                                actual: unary_expr.ty.clone(),
+                                span: unary_expr.span(),
+                            });
+                        }
+
+                        expr.ty = Type::int();
                     }
                     UnaryExprKind::Deref => {
-                        unary_expr.ty.try_cast_to_ptr();
+                        let mut ptr_ty = unary_expr.ty.clone();
+
+                        ptr_ty.try_cast_to_ptr();
 
-                        if let TypeKind::Ptr { ptr_to } = &unary_expr.ty.kind {
-                            unary_expr.ty = *ptr_to.clone();
+                        if let TypeKind::Ptr { ptr_to } = &ptr_ty.unaliased().kind {
+                            expr.ty = *ptr_to.clone();
                         } else {
                             return Err(TypeCheckError::TypeError {
                                 expected: Type::pointer_to(Box::new(Type::unknown())),
                                 actual: unary_expr.ty.clone(),
+                                span: unary_expr.span(),
                             });
                         }
                     }
                     UnaryExprKind::Addr => {
-                        unary_expr.ty = Type::pointer_to(Box::new(unary_expr.ty.clone()));
+                        // 取ったアドレスを通して書き換えられるので、変更可能な変数でなければならない
+                        if let Some(ident) = mutated_binding(unary_expr) {
+                            if !ident.mutable {
+                                return Err(TypeCheckError::AddrOfImmutable(
+                                    ident.name.clone(),
+                                    ident.span,
+                                ));
+                            }
+                        }
+
+                        expr.ty = Type::pointer_to(Box::new(unary_expr.ty.clone()));
                     }
                 }
-
-                expr.ty = unary_expr.ty.clone();
             }
             _ => panic!("unimplemented type of expression: {:?}", expr),
         }
@@ -563,3 +1532,61 @@ impl TypeResolver {
         Ok(expr.ty.clone())
     }
 }
+
+/// 代入やアドレスの取得によって書き換えられる変数を返す
+///
+/// ポインタの指す先への書き込みは変数を書き換えないが、
+/// 構造体のフィールドや配列の要素への書き込みは、それを持つ変数を書き換える
+fn mutated_binding(expr: &TypedExpression) -> Option<&Ident> {
+    match &expr.kind {
+        TypedExpressionKind::Ident(ident) => Some(ident),
+        TypedExpressionKind::FieldExpr(FieldExpr { expr, .. }) => mutated_binding(expr),
+        TypedExpressionKind::UnaryExpr(UnaryExpr {
+            kind: UnaryExprKind::Deref,
+            expr,
+            ..
+        }) => {
+            // `a[i]`は`*(a + i)`なので、足される側が配列ならその配列の要素になる
+            let base = match &expr.kind {
+                TypedExpressionKind::BinaryExpr(BinaryExpr {
+                    kind: BinaryExprKind::Add | BinaryExprKind::Sub,
+                    lhs,
+                    ..
+                }) => lhs,
+                _ => expr,
+            };
+
+            match base.ty.unaliased().kind {
+                TypeKind::Array { .. } => mutated_binding(base),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// 配列から先頭を指すポインタに変換される式について、その配列の変数を返す
+///
+/// `a + 1`のようにポインタの演算をした結果も、元の配列を指している
+fn decayed_binding(expr: &TypedExpression) -> Option<&Ident> {
+    match &expr.kind {
+        TypedExpressionKind::BinaryExpr(BinaryExpr {
+            kind: BinaryExprKind::Add | BinaryExprKind::Sub,
+            lhs,
+            ..
+        }) => decayed_binding(lhs),
+        _ if matches!(expr.ty.unaliased().kind, TypeKind::Array { .. }) => mutated_binding(expr),
+        _ => None,
+    }
+}
+
+/// 配列をポインタとして受け取ると配列を書き換えられるので、`&a`と同じく変更可能な変数でなければならない
+fn check_decay(value: &TypedExpression) -> Result<(), TypeCheckError> {
+    match decayed_binding(value) {
+        Some(ident) if !ident.mutable => Err(TypeCheckError::AddrOfImmutable(
+            ident.name.clone(),
+            ident.span,
+        )),
+        _ => Ok(()),
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{TypedExpression, TypedExpressionKind, TypedNode};
 use wervc_ast::{
-    ty::Type, BinaryExpr, BinaryExprKind, Boolean, FunctionDefExpr, Ident, Integer, LetExpr,
-    Program, Statement, UnaryExpr, UnaryExprKind,
+    ty::Type, BinaryExpr, BinaryExprKind, Boolean, BreakExpr, Char, Float, FunctionDefExpr, Ident,
+    Integer, LetExpr, LoopExpr, MatchArm, MatchExpr, Pattern, Program, Span, Statement, Str,
+    UnaryExpr, UnaryExprKind,
 };
 
 #[test]
@@ -9,36 +10,118 @@ fn test_type_resolution() {
     let mut inputs = [
         TypedNode::Program(Program {
             statements: vec![Statement::ExprStmt(TypedExpression {
-                kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+                kind: TypedExpressionKind::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                }),
                 ty: Type::unknown(),
             })],
         }),
         TypedNode::Program(Program {
             statements: vec![Statement::ExprReturnStmt(TypedExpression {
-                kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+                kind: TypedExpressionKind::Integer(Integer {
+                    value: 1,
+                    span: Span::default(),
+                }),
                 ty: Type::unknown(),
             })],
         }),
         TypedNode::Expression(TypedExpression {
-            kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+            kind: TypedExpressionKind::Integer(Integer {
+                value: 1,
+                span: Span::default(),
+            }),
             ty: Type::unknown(),
         }),
         TypedNode::Expression(TypedExpression {
             kind: TypedExpressionKind::BinaryExpr(BinaryExpr {
                 kind: BinaryExprKind::Add,
                 lhs: Box::new(TypedExpression {
-                    kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+                    kind: TypedExpressionKind::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    }),
                     ty: Type::unknown(),
                 }),
                 rhs: Box::new(TypedExpression {
-                    kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+                    kind: TypedExpressionKind::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    }),
                     ty: Type::unknown(),
                 }),
+                span: Span::default(),
+            }),
+            ty: Type::unknown(),
+        }),
+        TypedNode::Expression(TypedExpression {
+            kind: TypedExpressionKind::Boolean(Boolean {
+                value: true,
+                span: Span::default(),
+            }),
+            ty: Type::unknown(),
+        }),
+        TypedNode::Expression(TypedExpression {
+            kind: TypedExpressionKind::Float(Float {
+                value: 1.5,
+                span: Span::default(),
+            }),
+            ty: Type::unknown(),
+        }),
+        TypedNode::Expression(TypedExpression {
+            kind: TypedExpressionKind::Str(Str {
+                value: "werv".to_string(),
+                span: Span::default(),
+            }),
+            ty: Type::unknown(),
+        }),
+        TypedNode::Expression(TypedExpression {
+            kind: TypedExpressionKind::Char(Char {
+                value: b'a',
+                span: Span::default(),
             }),
             ty: Type::unknown(),
         }),
         TypedNode::Expression(TypedExpression {
-            kind: TypedExpressionKind::Boolean(Boolean { value: true }),
+            kind: TypedExpressionKind::BinaryExpr(BinaryExpr {
+                kind: BinaryExprKind::Add,
+                lhs: Box::new(TypedExpression {
+                    kind: TypedExpressionKind::Char(Char {
+                        value: b'a',
+                        span: Span::default(),
+                    }),
+                    ty: Type::unknown(),
+                }),
+                rhs: Box::new(TypedExpression {
+                    kind: TypedExpressionKind::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    }),
+                    ty: Type::unknown(),
+                }),
+                span: Span::default(),
+            }),
+            ty: Type::unknown(),
+        }),
+        TypedNode::Expression(TypedExpression {
+            kind: TypedExpressionKind::BinaryExpr(BinaryExpr {
+                kind: BinaryExprKind::Sub,
+                lhs: Box::new(TypedExpression {
+                    kind: TypedExpressionKind::Char(Char {
+                        value: b'b',
+                        span: Span::default(),
+                    }),
+                    ty: Type::unknown(),
+                }),
+                rhs: Box::new(TypedExpression {
+                    kind: TypedExpressionKind::Char(Char {
+                        value: b'a',
+                        span: Span::default(),
+                    }),
+                    ty: Type::unknown(),
+                }),
+                span: Span::default(),
+            }),
             ty: Type::unknown(),
         }),
         TypedNode::Expression(TypedExpression {
@@ -47,14 +130,20 @@ fn test_type_resolution() {
                     kind: TypedExpressionKind::Ident(Ident {
                         name: "x".to_string(),
                         offset: 4,
+                        mutable: true,
+                        span: Span::default(),
                     }),
                     ty: Type::unknown(),
                 }),
                 value: Some(Box::new(TypedExpression {
-                    kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+                    kind: TypedExpressionKind::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    }),
                     ty: Type::unknown(),
                 })),
                 ty: Type::int(),
+                span: Span::default(),
             }),
             ty: Type::unknown(),
         }),
@@ -64,15 +153,21 @@ fn test_type_resolution() {
                     kind: TypedExpressionKind::Ident(Ident {
                         name: "x".to_string(),
                         offset: 0,
+                        mutable: true,
+                        span: Span::default(),
                     }),
                     ty: Type::unknown(),
                 }),
                 params: vec![],
                 return_ty: Type::int(),
                 body: Box::new(TypedExpression {
-                    kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+                    kind: TypedExpressionKind::Integer(Integer {
+                        value: 1,
+                        span: Span::default(),
+                    }),
                     ty: Type::unknown(),
                 }),
+                span: Span::default(),
             }),
             ty: Type::unknown(),
         }),
@@ -84,14 +179,20 @@ fn test_type_resolution() {
                             kind: TypedExpressionKind::Ident(Ident {
                                 name: "x".to_string(),
                                 offset: 0,
+                                mutable: true,
+                                span: Span::default(),
                             }),
                             ty: Type::unknown(),
                         }),
                         value: Some(Box::new(TypedExpression {
-                            kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+                            kind: TypedExpressionKind::Integer(Integer {
+                                value: 1,
+                                span: Span::default(),
+                            }),
                             ty: Type::unknown(),
                         })),
                         ty: Type::int(),
+                        span: Span::default(),
                     }),
                     ty: Type::unknown(),
                 }),
@@ -102,9 +203,12 @@ fn test_type_resolution() {
                             kind: TypedExpressionKind::Ident(Ident {
                                 name: "x".to_string(),
                                 offset: 0,
+                                mutable: true,
+                                span: Span::default(),
                             }),
                             ty: Type::unknown(),
                         }),
+                        span: Span::default(),
                     }),
                     ty: Type::unknown(),
                 }),
@@ -118,14 +222,20 @@ fn test_type_resolution() {
                             kind: TypedExpressionKind::Ident(Ident {
                                 name: "x".to_string(),
                                 offset: 0,
+                                mutable: true,
+                                span: Span::default(),
                             }),
                             ty: Type::unknown(),
                         }),
                         value: Some(Box::new(TypedExpression {
-                            kind: TypedExpressionKind::Integer(Integer { value: 1 }),
+                            kind: TypedExpressionKind::Integer(Integer {
+                                value: 1,
+                                span: Span::default(),
+                            }),
                             ty: Type::unknown(),
                         })),
                         ty: Type::int(),
+                        span: Span::default(),
                     }),
                     ty: Type::unknown(),
                 }),
@@ -135,6 +245,8 @@ fn test_type_resolution() {
                             kind: TypedExpressionKind::Ident(Ident {
                                 name: "y".to_string(),
                                 offset: 0,
+                                mutable: false,
+                                span: Span::default(),
                             }),
                             ty: Type::unknown(),
                         }),
@@ -145,13 +257,17 @@ fn test_type_resolution() {
                                     kind: TypedExpressionKind::Ident(Ident {
                                         name: "x".to_string(),
                                         offset: 0,
+                                        mutable: true,
+                                        span: Span::default(),
                                     }),
                                     ty: Type::unknown(),
                                 }),
+                                span: Span::default(),
                             }),
                             ty: Type::unknown(),
                         })),
                         ty: Type::pointer_to(Box::new(Type::int())),
+                        span: Span::default(),
                     }),
                     ty: Type::unknown(),
                 }),
@@ -162,14 +278,95 @@ fn test_type_resolution() {
                             kind: TypedExpressionKind::Ident(Ident {
                                 name: "y".to_string(),
                                 offset: 0,
+                                mutable: false,
+                                span: Span::default(),
                             }),
                             ty: Type::unknown(),
                         }),
+                        span: Span::default(),
                     }),
                     ty: Type::unknown(),
                 }),
             ],
         }),
+        TypedNode::Expression(TypedExpression {
+            kind: TypedExpressionKind::LoopExpr(LoopExpr {
+                label: None,
+                body: Box::new(TypedExpression {
+                    kind: TypedExpressionKind::BreakExpr(BreakExpr {
+                        label: None,
+                        value: Some(Box::new(TypedExpression {
+                            kind: TypedExpressionKind::Char(Char {
+                                value: b'a',
+                                span: Span::default(),
+                            }),
+                            ty: Type::unknown(),
+                        })),
+                        span: Span::default(),
+                    }),
+                    ty: Type::unknown(),
+                }),
+                span: Span::default(),
+            }),
+            ty: Type::unknown(),
+        }),
+        TypedNode::Expression(TypedExpression {
+            kind: TypedExpressionKind::MatchExpr(MatchExpr {
+                expr: Box::new(TypedExpression {
+                    kind: TypedExpressionKind::Boolean(Boolean {
+                        value: true,
+                        span: Span::default(),
+                    }),
+                    ty: Type::unknown(),
+                }),
+                arms: vec![
+                    MatchArm {
+                        pattern: Pattern::Literal {
+                            value: Box::new(TypedExpression {
+                                kind: TypedExpressionKind::Boolean(Boolean {
+                                    value: true,
+                                    span: Span::default(),
+                                }),
+                                ty: Type::unknown(),
+                            }),
+                            span: Span::default(),
+                        },
+                        guard: None,
+                        body: Box::new(TypedExpression {
+                            kind: TypedExpressionKind::Char(Char {
+                                value: b'a',
+                                span: Span::default(),
+                            }),
+                            ty: Type::unknown(),
+                        }),
+                        span: Span::default(),
+                    },
+                    MatchArm {
+                        pattern: Pattern::Literal {
+                            value: Box::new(TypedExpression {
+                                kind: TypedExpressionKind::Boolean(Boolean {
+                                    value: false,
+                                    span: Span::default(),
+                                }),
+                                ty: Type::unknown(),
+                            }),
+                            span: Span::default(),
+                        },
+                        guard: None,
+                        body: Box::new(TypedExpression {
+                            kind: TypedExpressionKind::Char(Char {
+                                value: b'b',
+                                span: Span::default(),
+                            }),
+                            ty: Type::unknown(),
+                        }),
+                        span: Span::default(),
+                    },
+                ],
+                span: Span::default(),
+            }),
+            ty: Type::unknown(),
+        }),
     ];
     let expects = [
         Type::never(),
@@ -177,10 +374,17 @@ fn test_type_resolution() {
         Type::int(),
         Type::int(),
         Type::bool(),
+        Type::float(),
+        Type::str(),
+        Type::char(),
+        Type::char(),
+        Type::int(),
         Type::int(),
         Type::func(vec![], Box::new(Type::int())),
         Type::pointer_to(Box::new(Type::int())),
         Type::int(),
+        Type::char(),
+        Type::char(),
     ];
 
     for (input, expect) in inputs.iter_mut().zip(expects.iter()) {
@@ -21,6 +21,29 @@ assert() {
   fi
 }
 
+# コンパイラとインタプリタが、どちらも同じエラーで失敗することを確かめる
+assert_error() {
+  expected="$1"
+  input="$2"
+
+  echo "$input" > tmp/tmp.we
+
+  for mode in compile run; do
+    if [ "$mode" = compile ]; then
+      actual=$(cargo run --release tmp/tmp.we 2>&1 > /dev/null)
+    else
+      actual=$(cargo run --release run tmp/tmp.we 2>&1 > /dev/null)
+    fi
+
+    if ! echo "$actual" | grep -q "$expected"; then
+      echo "$input => $expected expected on $mode, but got $actual"
+      exit 1
+    fi
+  done
+
+  echo "$input => $expected"
+}
+
 assert 0 0
 assert 42 42
 assert 21 '5+20-4'
@@ -54,7 +77,7 @@ assert 1 'let a: int = 1; a'
 assert 2 'let a: int = 1; let b: int = 2; b'
 assert 1 'let a: int = 1; let b: int = 2; a'
 assert 3 'let a: int = 1;let b: int = 2; a+b'
-assert 4 'let a: int = 1; a=2; a+a'
+assert 4 'let mut a: int = 1; a=2; a+a'
 
 assert 6 "let foo: int = 1; let bar: int = 2 + 3; foo + bar"
 
@@ -92,33 +115,143 @@ assert 1 "let fib(n: int): int = if n < 2 n else fib(n-1) + fib(n-2); fib(1)"
 assert 55 "let fib(n: int): int = if n < 2 n else fib(n-1) + fib(n-2); fib(10)"
 assert 1 "let mod(n: int, modder: int): int = if n < modder n else mod(n-modder, modder); mod(100001, 2)"
 assert 4 "let mod(n: int, modder: int): int = if n < modder n else mod(n-modder, modder); mod(100004, 5)"
-assert 5 "let a: int = 4; a = 5; a"
-assert 5 "let a: int; a = 5; a"
+assert 5 "let mut a: int = 4; a = 5; a"
+assert 5 "let mut a: int; a = 5; a"
+assert 3 'let s: str = "werv"; let t: str = "lang"; 3'
+assert 2 "let f(c: char): int = c - 'A'; f('C')"
+assert 1 "1.5 + 2.25 == 3.75"
+assert 1 "let half(x: float): float = x / 2.0; half(5.0) < 2.6"
+assert 7 "let sub(a: int, x: float, b: int): int = a - b; sub(10, 1.0, 3)"
+assert 1 "let f(a: float, b: float, c: float, d: float, e: float, g: float, h: float, i: float, n: int): int = if i > h { n } else { 0 }; f(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 1)"
+
+assert 2 "17 % 5"
+assert 48 "0xF0 & 0x3C"
+assert 255 "0xF0 | 0x0F"
+assert 240 "0xFF ^ 0x0F"
+assert 255 "0xFFFFFFFF & 0xFF"
+assert 1 "let m: int = 0x1_0000_0000; m >> 32"
+assert 3 "let m: int = 0x7FFF_FFFF_FFFF_FFFF; m >> 61"
+assert 1 "let m: int = 0x7FFF_FFFF_FFFF_FFFF; if m + 1 == -m - 1 { 1 } else { 0 }"
+assert 1 "match 0x1_0000_0001 { 0x1_0000_0001 => 1, _ => 0 }"
+assert 16 "1 << 4"
+assert 4 "64 >> 4"
+assert 4 "~5 + 10"
+
+assert 0 "true && false"
+assert 1 "false || true"
+assert 5 "if 1 == 1 && 2 == 2 { 5 } else { 7 }"
+assert 0 "let mut a: int = 0; let set(p: *int): bool = { *p = 1; true }; false && set(&a); a"
+assert 1 "let mut a: int = 0; let set(p: *int): bool = { *p = 1; true }; false || set(&a); a"
+assert 0 "let mut a: int = 0; let set(p: *int): bool = { *p = 1; true }; true || set(&a); a"
+
+assert 6 "let mut a: int = 1; let mut b: int = 2; a = b = 3; a + b"
+assert 3 "let mut a: int = 1; let mut b: int = 2; a = b = a + b"
+assert 7 "let mut x: int = 1; let p: *int = &x; *p = x = 7"
+
+assert 0 "let x: int = 7; let x: bool = false; x"
+assert 21 "let mut x: int = 1; let p: *int = &x; let x: int = 2; *p + x * 10"
+assert 3 "let x: int = 3; { let x: int = 5; x }; x"
+assert 2 "let f(): int = 1; let f(): int = 2; f()"
+assert 12 "let f(): int = 1; let g: () -> int = f; let f(): int = 2; g() * 10 + f()"
+assert 3 "let x: int = 3; let get(): int = x; let x: int = 4; get()"
+assert 5 "let mut g: int = 3; let get(): int = g; g = 5; get()"
+assert 9 "let f(x: int): int = { let x: int = x * 2; let y: int = x + 1; y }; f(4)"
+
+assert 45 "let mut i: int = 0; let mut s: int = 0; while i < 10 { s = s + i; i = i + 1; }; s"
+assert 7 "let mut i: int = 0; while true { i = i + 1; if i == 7 { break }; }; i"
+assert 25 "let mut i: int = 0; let mut s: int = 0; while i < 10 { i = i + 1; if i % 2 == 0 { continue }; s = s + i; }; s"
+assert 44 "let mut i: int = 0; let mut s: int = 0; while i < 5 { i = i + 1; s = s + 1 + { if i == 3 { continue } else { 10 } }; }; s"
+assert 8 "let f(n: int): int = { let mut i: int = 0; while true { if i * i >= n { return i }; i = i + 1; }; 0 }; f(50)"
+assert 3 "let mut i: int = 0; let mut c: int = 0; while i < 3 { let mut j: int = 0; while j < 3 { j = j + 1; if j == 2 { break }; c = c + 1; }; i = i + 1; }; c"
+assert 10 "let mut s: int = 0; for i in 0..5 { s = s + i; }; s"
+assert 55 "let mut s: int = 0; for i in 1..=10 { s = s + i; }; s"
+assert 12 "let mut s: int = 0; for i in 0..10 { if i == 3 { continue }; if i == 6 { break }; s = s + i; }; s"
+assert 12 "let mut a: int[3]; a[0] = 3; a[1] = 4; a[2] = 5; let mut s: int = 0; for x in a { s = s + x; }; s"
+assert 6 "let mut c: int = 0; for i in 0..3 { for j in 0..=i { c = c + 1; }; }; c"
+assert 8 "let f(n: int): int = { for i in 0..10 { if i * i > n { return i }; }; 0 }; f(50)"
+assert 7 "let i: int = 7; for i in 0..3 { }; i"
+assert 5 "let mut i: int = 0; loop { i = i + 1; if i == 5 { break } }; i"
+assert 16 "let mut i: int = 0; let x: int = loop { i = i + 1; if i * i > 50 { break i * 2 } }; x"
+assert 9 "let mut c: int = 0; 'outer: for i in 0..5 { for j in 0..5 { if j == 3 { continue 'outer }; if i == 3 { break 'outer }; c = c + 1; }; }; c"
+assert 40 "'a: loop { let mut i: int = 0; while true { i = i + 1; if i == 4 { break 'a i * 10 } } }"
+assert 101 "let mut n: int = 0; 'o: while n < 10 { n = n + 1; loop { if n % 2 == 0 { continue 'o }; break }; n = n + 100 }; n"
+assert 42 "let f: (int) -> int = fn(x: int): int => x + 1; f(41)"
+assert 15 "let apply(f: (int) -> int, x: int): int = f(x); apply(fn(x: int): int => x * 3, 5)"
+assert 42 "let double(x: int): int = x * 2; let apply(f: (int) -> int, x: int): int = f(x); apply(double, 21)"
+assert 42 "let adder(): (int, int) -> int = fn(a: int, b: int): int => a + b; adder()(20, 22)"
+assert 120 "let fact(n: int): int = if n == 0 1 else n * fact(n - 1); let g: (int) -> int = fact; g(5)"
+assert 7 "(fn(x: int) => x - 1)(8)"
+assert 41 "let compose(f: (int) -> int, g: (int) -> int, x: int): int = f(g(x)); compose(fn(x: int): int => x + 1, fn(x: int): int => x * 10, 4)"
+assert 11 "let y: int = 10; let g: (int) -> int = fn(x: int): int => x + y; g(1)"
+assert_error CapturedLocal "let add(n: int): (int) -> int = fn(x: int): int => x + n; add(1)(2)"
+assert_error CapturedLocal "let f(n: int): int = { let mut m: int = 0; let g(x: int): int = { m = x; 0 }; g(n) }; f(1)"
+assert 10 "let f(y: int): int = { let sq(x: int): int = x * x; let g(x: int): int = sq(x) + 1; g(y) }; f(3)"
+assert 7 "struct P { x: int, y: int }; let mut p: P = P { x: 1, y: 2 }; p.x = 5; p.x + p.y"
+assert 43 "struct P { x: int, y: int }; let p: P = P { y: 2, x: 40 }; let mut q: P = p; q.y = 1; p.x + p.y + q.y"
+assert 5 "struct S { c: char, d: char, n: int }; let s: S = S { c: 'a', d: 'b', n: 4 }; s.d - s.c + s.n"
+assert 7 "struct P { x: int, y: int }; let mut p: P = P { x: 1, y: 2 }; let pp: *P = &p; (*pp).y = 7; p.y"
+assert 12 "struct P { x: int, y: int }; struct L { a: P, b: P }; let mut l: L = L { a: P { x: 1, y: 2 }, b: P { x: 3, y: 4 } }; l.b.x = 10; l.a.y + l.b.x"
+assert 13 "struct P { x: int, y: int }; let f(mut p: P): int = { p.x = 10; p.x + p.y }; let p: P = P { x: 1, y: 2 }; f(p) + p.x"
+assert 10 "struct P { x: int, y: int }; let f(a: int, p: P, b: int): int = { a + p.x + p.y + b }; f(1, P { x: 2, y: 3 }, 4)"
+assert 24 "enum S { Circle(int), Rect(int, int), Empty }; let area(s: S): int = match s { S::Circle(r) => 3 * r * r, S::Rect(w, h) => w * h, S::Empty => 0 }; area(S::Circle(2)) + area(S::Rect(3, 4)) + area(S::Empty)"
+assert 5 "enum S { Rect(int, int) }; let s: S = S::Rect(2, 5); match s { S::Rect(w, h) if w > h => w, S::Rect(_, h) => h }"
+assert 7 "enum O { Some(int), None }; enum W { A(O), B }; let w: W = W::A(O::Some(7)); match w { W::A(O::Some(n)) => n, W::A(O::None) => 1, W::B => 2 }"
+assert 6 "enum O { Some(int), None }; let o: O = O::Some(3); let p: O = o; match p { O::Some(n) => { n * 2 } O::None => 0 }"
+assert 28 "enum C { A(char, int) }; match C::A('x', 5) { C::A(c, n) => c - 'a' + n }"
+assert 30 "let x: int = 3; match x { 1 => 10, 3 => 30, n => n }"
+assert 10 "let x: int = -2; match x { -2 => 10, _ => 0 }"
+assert 5 "match 1 > 2 { true => 4, false => 5 }"
+assert 32 "let divmod(a: int, b: int): (int, int) = (a / b, a % b); let (q, r): (int, int) = divmod(17, 5); q * 10 + r"
+assert 7 "let mut t: (int, (int, bool)) = (1, (2, true)); t.1.0 = 6; t.0 + t.1.0"
+assert 3 "let (a, (b, c)): (int, (int, bool)) = (1, (2, true)); if c { a + b } else { 0 }"
+assert 42 "let g(a: (int, int)): int = a.0 * a.1; g((6, 7))"
+assert 111 "let f(n: int): (int, int) = if n == 0 { (n, n + 1) } else { let (x, y): (int, int) = f(n - 1); (x + n, y + n) }; let (a, b): (int, int) = f(4); a * 10 + b"
+assert 13 "struct P { x: int, y: int }; let mk(x: int): P = P { x: x, y: x * 2 }; mk(4).y + mk(5).x"
+assert 5 "enum O { Some(int), None }; let h(x: int): O = if x > 0 { O::Some(x) } else { O::None }; match h(5) { O::Some(v) => v, O::None => 0 }"
+assert 15 "let mut a: int[2][3]; for i in 0..2 { for j in 0..3 { a[i][j] = i * 3 + j; }; }; a[1][2] * 3"
+assert 9 "let mut a: int[2][3][4]; a[1][2][3] = 9; a[0][0][0] = 1; a[1][2][3]"
+assert 8 "let mut a: int[2][3]; a[1][0] = 8; let p: *int = a[1]; *p"
+assert 15 "let sum(m: *(int[3]), n: int): int = { let mut s: int = 0; for i in 0..n { for j in 0..3 { s = s + m[i][j]; }; }; s }; let mut a: int[2][3]; for i in 0..2 { for j in 0..3 { a[i][j] = i * 3 + j; }; }; sum(a, 2)"
+assert 6 "let mut a: int[4]; let p: *(int[4]) = &a; (*p)[3] = 6; a[3]"
+assert 5 "let mut x: int = 4; let mut y: int = 9; let mut a: *int[2]; a[0] = &x; a[1] = &y; *a[1] - *a[0]"
+assert 6 "type Index = int; let i: Index = 3; let j: int = i; i + j"
+assert 7 "type Matrix = int[4][4]; let mut m: Matrix; m[3][2] = 7; m[3][2]"
+assert 1 "type F = float; let half(x: F): F = x / 2.0; let y: F = half(9.0); if y > 4.4 { 1 } else { 0 }"
+assert 2 "struct P { x: int, y: int }; type Q = P; let q: Q = Q { x: 1, y: 2 }; let p: P = q; p.y"
+assert 12 "type Pair = (int, int); let f(a: int): Pair = (a, a * 2); let (x, y): Pair = f(4); x + y"
+assert 9 "let mut s: char[3]; s[0] = 'a'; s[2] = 'c'; let x: int = 7; s[2] - s[0] + x"
+assert 3 "let mut (a, b): (int, int) = (1, 5); a = b - 2; a"
+assert 8 "let double(mut n: int): int = { n = n * 2; n }; let n: int = 4; double(n)"
+assert 4 "let x: int = 1; let mut x: int = x; x = x + 3; x"
+assert 64 "const N: int = 4 * 16; N"
+assert 7 "const N: int = 3; const M: int = N * 2 + 1; let mut a: int[M][N]; a[6][2] = M; a[6][2]"
+assert 3 "const N: int = 4; let sum(a: *int): int = { let mut s: int = 0; for i in 0..N { s = s + a[i]; }; s }; let mut a: int[N]; for i in 0..N { a[i] = i % 2; }; sum(a) + 1"
+assert 10 "const N: int = 2; match 2 { N => 10, _ => 20 }"
 
-assert 10 "let a: int = 10; let b: *int = &a; *b"
-assert 3 "let a: int = 3; let b: int = 5; let c: *int = &b + 1; *c"
-assert 10 "let a: int = 10; let b: *int = &a; let c: **int = &b; let d: ***int = &c; ***d"
-assert 15 "let a: int = 10; let b: *int = &a; let c: int = 5; let d: *int = &c; *b+*d"
+assert 10 "let mut a: int = 10; let b: *int = &a; *b"
+assert 3 "let a: int = 3; let mut b: int = 5; let c: *int = &b + 1; *c"
+assert 10 "let mut a: int = 10; let mut b: *int = &a; let mut c: **int = &b; let d: ***int = &c; ***d"
+assert 15 "let mut a: int = 10; let b: *int = &a; let mut c: int = 5; let d: *int = &c; *b+*d"
 
-assert 3 "let x: int = 0;
-let y: *int = 0;
+assert 3 "let mut x: int = 0;
+let mut y: *int = 0;
 y = &x;
 *y = 3;
 return x;
 "
 
-assert 3 "let a: int[2];
+assert 3 "let mut a: int[2];
 *a = 1;
 *(a + 1) = 2;
-let p: *int;
+let mut p: *int;
 p = a;
 *p + *(p + 1)
 "
 
-assert 3 "let a: int[2];
+assert 3 "let mut a: int[2];
 a[0] = 1;
 a[1] = 2;
-let p: *int;
+let mut p: *int;
 p = a;
 p[0] + p[1]
 "